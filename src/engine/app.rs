@@ -5,17 +5,27 @@ use pyo3::types::{PyAny, PyCFunction, PyDict, PyString, PyTuple};
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
+use std::time::Duration;
 use tracing::info;
 
 use super::server;
-pub use super::types::{FastrAPI, FrontendMount, StaticMount};
+use super::server::conn::parse_http2_params;
+use super::server::handle::PyServerHandle;
+use super::server::health::{ReadinessCheck, is_coroutine_function, validate_probe_urls};
+use super::server::listener::parse_bind_params;
+use super::server::logging::{init_logging, parse_log_params};
+use super::server::route_table::build_route_table;
+use super::server::threads::parse_runtime_params;
+use super::server::tls::parse_tls_params;
+pub use super::types::{AsgiMount, FastrAPI, FrontendMount, StaticMount};
 use crate::decorators::PyAPIRouter;
-use crate::globals::{MIDDLEWARE_COUNTER, MIDDLEWARES};
+use crate::globals::{MIDDLEWARE_COUNTER, MIDDLEWARES, config, settings};
 use crate::http::middleware::{
-    PyMiddleware, parse_cors_params, parse_gzip_params, parse_https_redirect_params,
-    parse_session_params, parse_trusted_host_params,
+    PyMiddleware, parse_access_log_params, parse_cors_params, parse_gzip_params,
+    parse_https_redirect_params, parse_session_params, parse_trusted_host_params,
 };
 use crate::http::staticfiles::PyStaticFiles;
+use crate::routing::introspection::route_objects;
 use crate::routing::types::HttpMethod;
 
 #[pymethods]
@@ -24,19 +34,18 @@ impl FastrAPI {
     #[pyo3(signature = (
         *,
         debug=false,
-        routes=None,
         title="FastrAPI".to_string(),
         summary=None,
         description="".to_string(),
         version="0.1.0".to_string(),
-        openapi_url="/api-docs/openapi.json".to_string(),
+        openapi_url=url_setting(&settings().openapi_url, "/api-docs/openapi.json"),
         openapi_tags=None,
         servers=None,
         dependencies=None,
         default_response_class=None,
         redirect_slashes=true,
-        docs_url=Some("/docs".to_string()),
-        redoc_url=Some("/redoc".to_string()),
+        docs_url=url_setting(&settings().docs_url, "/docs"),
+        redoc_url=url_setting(&settings().redoc_url, "/redoc"),
         scalar_url=Some("/scalar".to_string()),
         elements_url=Some("/elements".to_string()),
         swagger_ui_oauth2_redirect_url=Some("/docs/oauth2-redirect".to_string()),
@@ -62,25 +71,29 @@ impl FastrAPI {
         separate_input_output_schemas=true,
         openapi_external_docs=None,
         sync_to_threadpool=false,
-        max_body_size=Some(16 * 1024 * 1024),
-        max_field_size=Some(1024 * 1024),
-        max_file_size=Some(16 * 1024 * 1024),
+        max_sync_workers=settings().max_sync_workers,
+        sync_queue_size=settings().sync_queue_size.unwrap_or(64),
+        max_body_size=settings().max_body_size.or(Some(16 * 1024 * 1024)),
+        max_field_size=settings().max_field_size.or(Some(1024 * 1024)),
+        max_file_size=settings().max_file_size.or(Some(16 * 1024 * 1024)),
         reject_unknown_multipart_fields=false,
         trace_requests=false,
         catch_panics=false,
-        request_timeout=None,
+        request_timeout=settings().request_timeout,
         request_id_header=None,
+        otlp_endpoint=None,
         powered_by_header=None,
+        health_url=None,
+        ready_url=None,
     ))]
     fn new(
         py: Python<'_>,
         debug: bool,
-        routes: Option<Py<PyAny>>,
         title: String,
         summary: Option<String>,
         description: String,
         version: String,
-        openapi_url: String,
+        openapi_url: Option<String>,
         openapi_tags: Option<Py<PyAny>>,
         servers: Option<Py<PyAny>>,
         dependencies: Option<Py<PyAny>>,
@@ -113,6 +126,8 @@ impl FastrAPI {
         separate_input_output_schemas: bool,
         openapi_external_docs: Option<Py<PyAny>>,
         sync_to_threadpool: bool,
+        max_sync_workers: Option<usize>,
+        sync_queue_size: usize,
         max_body_size: Option<usize>,
         max_field_size: Option<usize>,
         max_file_size: Option<usize>,
@@ -121,8 +136,30 @@ impl FastrAPI {
         catch_panics: bool,
         request_timeout: Option<u64>,
         request_id_header: Option<String>,
+        otlp_endpoint: Option<String>,
         powered_by_header: Option<String>,
+        health_url: Option<String>,
+        ready_url: Option<String>,
     ) -> PyResult<Self> {
+        config().map_err(PyValueError::new_err)?;
+        if max_sync_workers == Some(0) {
+            return Err(PyValueError::new_err("max_sync_workers must be at least 1"));
+        }
+        validate_probe_urls(
+            health_url.as_deref(),
+            ready_url.as_deref(),
+            &[
+                ("openapi_url", openapi_url.as_deref()),
+                ("docs_url", docs_url.as_deref()),
+                ("redoc_url", redoc_url.as_deref()),
+                ("scalar_url", scalar_url.as_deref()),
+                ("elements_url", elements_url.as_deref()),
+                (
+                    "swagger_ui_oauth2_redirect_url",
+                    swagger_ui_oauth2_redirect_url.as_deref(),
+                ),
+            ],
+        )?;
         let default_response_class = default_response_class.unwrap_or_else(|| {
             py.import(intern!(py, "fastrapi"))
                 .and_then(|m| m.getattr(intern!(py, "responses")))
@@ -140,7 +177,6 @@ impl FastrAPI {
 
         Ok(Self {
             debug,
-            routes,
             title,
             summary,
             description,
@@ -178,6 +214,8 @@ impl FastrAPI {
             separate_input_output_schemas,
             openapi_external_docs,
             sync_to_threadpool,
+            max_sync_workers,
+            sync_queue_size,
             max_body_size,
             max_field_size,
             max_file_size,
@@ -186,15 +224,23 @@ impl FastrAPI {
             catch_panics,
             request_timeout,
             request_id_header,
+            otlp_endpoint,
             powered_by_header,
+            health_url,
+            ready_url,
+            readiness_checks: Vec::new(),
             static_mounts: Vec::new(),
             frontend_mounts: Vec::new(),
+            asgi_mounts: Vec::new(),
             prometheus_config: None,
             cors_config: None,
             trusted_host_config: None,
             https_redirect_config: None,
             gzip_config: None,
             session_config: None,
+            access_log_config: None,
+            asgi_app: Arc::default(),
+            live_routes: Arc::default(),
             router: base_router,
         })
     }
@@ -231,9 +277,13 @@ impl FastrAPI {
                 self.session_config = Some(parse_session_params(opts)?);
                 info!("Enabled SessionMiddleware");
             }
+            "AccessLogMiddleware" => {
+                self.access_log_config = Some(parse_access_log_params(opts)?);
+                info!("Enabled AccessLogMiddleware");
+            }
             _ => {
                 let msg = format!(
-                    "Middleware '{}' is not supported. Only CORSMiddleware, TrustedHostMiddleware, HTTPSRedirectMiddleware, GZipMiddleware, and SessionMiddleware are allowed via add_middleware.",
+                    "Middleware '{}' is not supported. Only CORSMiddleware, TrustedHostMiddleware, HTTPSRedirectMiddleware, GZipMiddleware, SessionMiddleware, and AccessLogMiddleware are allowed via add_middleware.",
                     class_name
                 );
                 return Err(pyo3::exceptions::PyValueError::new_err(msg));
@@ -269,18 +319,31 @@ impl FastrAPI {
             ));
         }
 
-        let static_files = app.bind(py).extract::<PyRef<'_, PyStaticFiles>>()?;
         let normalized_path = if path.len() > 1 {
             path.trim_end_matches('/').to_string()
         } else {
             path
         };
 
-        self.static_mounts.push(StaticMount {
+        if let Ok(static_files) = app.bind(py).extract::<PyRef<'_, PyStaticFiles>>() {
+            self.static_mounts.push(StaticMount {
+                path: normalized_path,
+                directory: static_files.directory.clone(),
+                html: static_files.html,
+                follow_symlink: static_files.follow_symlink,
+                name,
+            });
+            return Ok(());
+        }
+
+        if !app.bind(py).is_callable() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "mount() expects StaticFiles or an ASGI application",
+            ));
+        }
+        self.asgi_mounts.push(AsgiMount {
             path: normalized_path,
-            directory: static_files.directory.clone(),
-            html: static_files.html,
-            follow_symlink: static_files.follow_symlink,
+            app: Arc::new(app),
             name,
         });
         Ok(())
@@ -343,7 +406,7 @@ impl FastrAPI {
         .map(|f| f.into())
     }
 
-    #[pyo3(signature = (host=None, port=None, *, reload=false, reload_dirs=None, reload_ignore_dirs=None, reload_ignore_patterns=None, reload_ignore_paths=None, reload_tick=750, reload_ignore_worker_failure=false))]
+    #[pyo3(signature = (host=None, port=None, *, reload=false, reload_dirs=None, reload_includes=None, reload_ignore_dirs=None, reload_ignore_patterns=None, reload_ignore_paths=None, reload_tick=750, reload_ignore_worker_failure=false, ssl_certfile=settings().ssl_certfile.clone(), ssl_keyfile=settings().ssl_keyfile.clone(), ssl_ca_certs=None, ssl_cert_reqs=0, http2=false, http2_max_concurrent_streams=None, http2_initial_stream_window_size=None, http2_initial_connection_window_size=None, http2_keep_alive_interval=None, workers=settings().workers.unwrap_or(1), uds=None, uds_mode=None, uds_cleanup=true, fd=None, graceful_timeout=settings().graceful_timeout.unwrap_or(30.0), log_level=settings().log_level.clone(), log_format=settings().log_format.clone().unwrap_or_else(|| "text".to_string()), log_filter=None, log_to_python=false, worker_threads=settings().worker_threads, max_blocking_threads=settings().max_blocking_threads, thread_keep_alive=settings().thread_keep_alive, thread_name=settings().thread_name.clone()))]
     fn serve(
         slf: Py<Self>,
         py: Python,
@@ -351,28 +414,160 @@ impl FastrAPI {
         port: Option<u16>,
         reload: bool,
         reload_dirs: Option<Vec<String>>,
+        reload_includes: Option<Vec<String>>,
         reload_ignore_dirs: Option<Vec<String>>,
         reload_ignore_patterns: Option<Vec<String>>,
         reload_ignore_paths: Option<Vec<String>>,
         reload_tick: u64,
         reload_ignore_worker_failure: bool,
+        ssl_certfile: Option<String>,
+        ssl_keyfile: Option<String>,
+        ssl_ca_certs: Option<String>,
+        ssl_cert_reqs: u8,
+        http2: bool,
+        http2_max_concurrent_streams: Option<u32>,
+        http2_initial_stream_window_size: Option<u32>,
+        http2_initial_connection_window_size: Option<u32>,
+        http2_keep_alive_interval: Option<f64>,
+        workers: usize,
+        uds: Option<String>,
+        uds_mode: Option<u32>,
+        uds_cleanup: bool,
+        fd: Option<i32>,
+        graceful_timeout: f64,
+        log_level: Option<String>,
+        log_format: String,
+        log_filter: Option<String>,
+        log_to_python: bool,
+        worker_threads: Option<usize>,
+        max_blocking_threads: Option<usize>,
+        thread_keep_alive: Option<f64>,
+        thread_name: Option<String>,
     ) -> PyResult<()> {
-        if reload && std::env::var_os("FASTRAPI_RELOAD_CHILD").is_none() {
+        if workers == 0 {
+            return Err(PyValueError::new_err("workers must be at least 1"));
+        }
+        if reload && workers > 1 {
+            return Err(PyValueError::new_err(
+                "reload=True cannot be combined with workers > 1",
+            ));
+        }
+        if uds.is_some() && workers > 1 {
+            return Err(PyValueError::new_err(
+                "uds cannot be combined with workers > 1; use fd= with a pre-bound socket",
+            ));
+        }
+
+        let is_worker = server::is_worker_process();
+        let options = serve_options(
+            host,
+            port,
+            ssl_certfile,
+            ssl_keyfile,
+            ssl_ca_certs,
+            ssl_cert_reqs,
+            http2,
+            http2_max_concurrent_streams,
+            http2_initial_stream_window_size,
+            http2_initial_connection_window_size,
+            http2_keep_alive_interval,
+            uds,
+            uds_mode,
+            uds_cleanup,
+            fd,
+            graceful_timeout,
+            log_level,
+            log_format,
+            log_filter,
+            log_to_python,
+            worker_threads,
+            max_blocking_threads,
+            thread_keep_alive,
+            thread_name,
+            is_worker,
+        )?;
+        init_logging(&options.logging);
+        if workers > 1 && !is_worker {
+            server::serve_with_workers(py, workers, options.graceful_timeout)
+        } else if reload && std::env::var_os("FASTRAPI_RELOAD_CHILD").is_none() {
             server::serve_with_reload(
                 py,
                 reload_dirs,
+                reload_includes,
                 reload_ignore_dirs,
                 reload_ignore_patterns,
                 reload_ignore_paths,
                 reload_tick,
                 reload_ignore_worker_failure,
+                options.graceful_timeout,
             )
         } else {
-            server::serve(py, host, port, slf)
+            server::serve(py, options, slf)
         }
     }
 
-    #[pyo3(signature = (router, *, prefix="".to_string(), tags=None, dependencies=None, responses=None, deprecated=None, include_in_schema=true, default_response_class=None, generate_unique_id_function=None))]
+    /// Starts serving on a background thread and returns a `ServerHandle`
+    /// instead of blocking; pass `port=0` to let the OS pick a free port.
+    #[pyo3(signature = (host=None, port=None, *, ssl_certfile=settings().ssl_certfile.clone(), ssl_keyfile=settings().ssl_keyfile.clone(), ssl_ca_certs=None, ssl_cert_reqs=0, http2=false, http2_max_concurrent_streams=None, http2_initial_stream_window_size=None, http2_initial_connection_window_size=None, http2_keep_alive_interval=None, uds=None, uds_mode=None, uds_cleanup=true, fd=None, graceful_timeout=settings().graceful_timeout.unwrap_or(30.0), log_level=settings().log_level.clone(), log_format=settings().log_format.clone().unwrap_or_else(|| "text".to_string()), log_filter=None, log_to_python=false, worker_threads=settings().worker_threads, max_blocking_threads=settings().max_blocking_threads, thread_keep_alive=settings().thread_keep_alive, thread_name=settings().thread_name.clone()))]
+    fn start(
+        slf: Py<Self>,
+        py: Python,
+        host: Option<String>,
+        port: Option<u16>,
+        ssl_certfile: Option<String>,
+        ssl_keyfile: Option<String>,
+        ssl_ca_certs: Option<String>,
+        ssl_cert_reqs: u8,
+        http2: bool,
+        http2_max_concurrent_streams: Option<u32>,
+        http2_initial_stream_window_size: Option<u32>,
+        http2_initial_connection_window_size: Option<u32>,
+        http2_keep_alive_interval: Option<f64>,
+        uds: Option<String>,
+        uds_mode: Option<u32>,
+        uds_cleanup: bool,
+        fd: Option<i32>,
+        graceful_timeout: f64,
+        log_level: Option<String>,
+        log_format: String,
+        log_filter: Option<String>,
+        log_to_python: bool,
+        worker_threads: Option<usize>,
+        max_blocking_threads: Option<usize>,
+        thread_keep_alive: Option<f64>,
+        thread_name: Option<String>,
+    ) -> PyResult<PyServerHandle> {
+        let options = serve_options(
+            host,
+            port,
+            ssl_certfile,
+            ssl_keyfile,
+            ssl_ca_certs,
+            ssl_cert_reqs,
+            http2,
+            http2_max_concurrent_streams,
+            http2_initial_stream_window_size,
+            http2_initial_connection_window_size,
+            http2_keep_alive_interval,
+            uds,
+            uds_mode,
+            uds_cleanup,
+            fd,
+            graceful_timeout,
+            log_level,
+            log_format,
+            log_filter,
+            log_to_python,
+            worker_threads,
+            max_blocking_threads,
+            thread_keep_alive,
+            thread_name,
+            false,
+        )?;
+        server::spawn_server(py, options, slf, false)
+    }
+
+    #[pyo3(signature = (router, *, prefix="".to_string(), tags=None, dependencies=None, responses=None, deprecated=None, include_in_schema=true, default_response_class=None, generate_unique_id_function=None, host=None))]
     fn include_router(
         &self,
         py: Python<'_>,
@@ -385,6 +580,7 @@ impl FastrAPI {
         include_in_schema: bool,
         default_response_class: Option<Py<PyAny>>,
         generate_unique_id_function: Option<Py<PyAny>>,
+        host: Option<&str>,
     ) -> PyResult<()> {
         self.router.bind(py).borrow().include_router(
             py,
@@ -397,6 +593,7 @@ impl FastrAPI {
             include_in_schema,
             default_response_class,
             generate_unique_id_function,
+            host,
         )
     }
 
@@ -425,9 +622,18 @@ impl FastrAPI {
             include_in_schema,
             default_response_class,
             generate_unique_id_function,
+            None,
         )
     }
 
+    /// Rebuilds the routing table and OpenAPI schema from the registered routes
+    /// and swaps them into running servers, keeping their connections open.
+    #[pyo3(signature = ())]
+    fn reload_routes(&self, py: Python<'_>) {
+        let (table, _) = build_route_table(py, self);
+        self.live_routes.swap(table);
+    }
+
     #[pyo3(signature = ())]
     fn openapi(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
         let spec = crate::utils::openapi::build_openapi_spec(py, self);
@@ -459,6 +665,51 @@ impl FastrAPI {
         PyCFunction::new_closure(py, Some(c"exception_handler"), None, decorator).map(|f| f.into())
     }
 
+    /// Registers a check that `ready_url` runs on every probe; the app only
+    /// reports ready while all of them pass within their timeout.
+    #[pyo3(signature = (name=None, *, timeout=1.0))]
+    fn readiness_check(
+        slf: Py<Self>,
+        py: Python,
+        name: Option<String>,
+        timeout: f64,
+    ) -> PyResult<Py<PyAny>> {
+        if !timeout.is_finite() || timeout <= 0.0 {
+            return Err(PyValueError::new_err(
+                "timeout must be a positive number of seconds",
+            ));
+        }
+        let timeout = Duration::from_secs_f64(timeout);
+        let decorator = move |args: &Bound<'_, PyTuple>,
+                              _kwargs: Option<&Bound<'_, PyDict>>|
+              -> PyResult<Py<PyAny>> {
+            let py = args.py();
+            let func = args.get_item(0)?;
+            let name = match &name {
+                Some(name) => name.clone(),
+                None => func.getattr(intern!(py, "__name__"))?.extract()?,
+            };
+            let is_async = is_coroutine_function(py, &func)?;
+            slf.bind(py)
+                .borrow_mut()
+                .readiness_checks
+                .push(ReadinessCheck {
+                    name,
+                    check: func.clone().unbind(),
+                    is_async,
+                    timeout,
+                });
+            Ok(func.unbind())
+        };
+        PyCFunction::new_closure(py, Some(c"readiness_check"), None, decorator).map(|f| f.into())
+    }
+
+    /// The routes this app serves, as `Route` and `WebSocketRoute` objects.
+    #[getter]
+    fn routes(&self, py: Python<'_>) -> PyResult<Vec<Py<PyAny>>> {
+        route_objects(py, &self._router(py).list_routes(py))
+    }
+
     fn fallback(&self, py: Python, handler: Py<PyAny>) -> PyResult<Py<PyAny>> {
         if let Some(handlers) = &self.exception_handlers {
             handlers.bind(py).set_item(404, handler.clone_ref(py))?;
@@ -476,4 +727,90 @@ impl FastrAPI {
         self.router.bind(py).borrow()
     }
 }
+fn serve_options(
+    host: Option<String>,
+    port: Option<u16>,
+    ssl_certfile: Option<String>,
+    ssl_keyfile: Option<String>,
+    ssl_ca_certs: Option<String>,
+    ssl_cert_reqs: u8,
+    http2: bool,
+    http2_max_concurrent_streams: Option<u32>,
+    http2_initial_stream_window_size: Option<u32>,
+    http2_initial_connection_window_size: Option<u32>,
+    http2_keep_alive_interval: Option<f64>,
+    uds: Option<String>,
+    uds_mode: Option<u32>,
+    uds_cleanup: bool,
+    fd: Option<i32>,
+    graceful_timeout: f64,
+    log_level: Option<String>,
+    log_format: String,
+    log_filter: Option<String>,
+    log_to_python: bool,
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+    thread_keep_alive: Option<f64>,
+    thread_name: Option<String>,
+    reuse_port: bool,
+) -> PyResult<server::ServeOptions> {
+    if !graceful_timeout.is_finite() || graceful_timeout < 0.0 {
+        return Err(PyValueError::new_err(
+            "graceful_timeout must be a non-negative number of seconds",
+        ));
+    }
+    let config = config().map_err(PyValueError::new_err)?;
+
+    let tls = parse_tls_params(
+        ssl_certfile,
+        ssl_keyfile,
+        ssl_ca_certs,
+        ssl_cert_reqs,
+        http2,
+    )?;
+    let http2 = parse_http2_params(
+        http2,
+        http2_max_concurrent_streams,
+        http2_initial_stream_window_size,
+        http2_initial_connection_window_size,
+        http2_keep_alive_interval,
+    )?;
+    let bind = parse_bind_params(
+        host,
+        port,
+        config.host.clone(),
+        config.port,
+        uds,
+        uds_mode,
+        uds_cleanup,
+        fd,
+        reuse_port,
+    )?;
+    let logging = parse_log_params(log_level, log_format, log_filter, log_to_python)?;
+    let runtime = parse_runtime_params(
+        worker_threads,
+        max_blocking_threads,
+        thread_keep_alive,
+        thread_name,
+    )?;
+
+    Ok(server::ServeOptions {
+        bind,
+        tls,
+        http2,
+        graceful_timeout: Duration::from_secs_f64(graceful_timeout),
+        logging,
+        runtime,
+    })
+}
+
+/// A configured docs URL, where an empty string turns the page off.
+fn url_setting(configured: &Option<String>, default: &str) -> Option<String> {
+    match configured.as_deref() {
+        Some("") => None,
+        Some(url) => Some(url.to_string()),
+        None => Some(default.to_string()),
+    }
+}
+
 crate::generate_http_methods!(FastrAPI, _router);
@@ -0,0 +1,179 @@
+use clap::{Args, Parser, Subcommand};
+use pyo3::{
+    exceptions::{PySystemExit, PyTypeError, PyValueError},
+    intern,
+    prelude::*,
+    types::{PyDict, PyModule},
+};
+use std::path::Path;
+
+use super::types::FastrAPI;
+
+#[derive(Parser)]
+#[command(name = "fastrapi", version, about = "Serve a FastrAPI application")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the app
+    Run(RunArgs),
+    /// Serve the app and restart it when Python files change
+    Dev(RunArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// The app as `module:attribute`; the attribute defaults to `app`
+    app: String,
+    /// Directory to import the module from
+    #[arg(long, default_value = ".")]
+    app_dir: String,
+    #[arg(long)]
+    host: Option<String>,
+    #[arg(long)]
+    port: Option<u16>,
+    #[arg(long)]
+    workers: Option<usize>,
+    /// Restart when Python files change (always on for `dev`)
+    #[arg(long)]
+    reload: bool,
+    /// Directories to watch with --reload; defaults to --app-dir
+    #[arg(long = "reload-dir")]
+    reload_dirs: Vec<String>,
+    /// Extra glob patterns that trigger a reload, e.g. '*.html'
+    #[arg(long = "reload-include")]
+    reload_includes: Vec<String>,
+    /// Listen on a unix domain socket instead of host/port
+    #[arg(long)]
+    uds: Option<String>,
+    /// Adopt an already-bound socket by file descriptor
+    #[arg(long)]
+    fd: Option<i32>,
+    #[arg(long)]
+    graceful_timeout: Option<f64>,
+    #[arg(long)]
+    ssl_certfile: Option<String>,
+    #[arg(long)]
+    ssl_keyfile: Option<String>,
+    #[arg(long)]
+    log_level: Option<String>,
+    /// `text` or `json`
+    #[arg(long)]
+    log_format: Option<String>,
+    /// `RUST_LOG` style directives
+    #[arg(long)]
+    log_filter: Option<String>,
+}
+
+/// Imports `module:attribute` with `app_dir` first on `sys.path`.
+fn load_app<'py>(py: Python<'py>, target: &str, app_dir: &str) -> PyResult<Bound<'py, PyAny>> {
+    let (module_name, attribute) = target.split_once(':').unwrap_or((target, "app"));
+    if module_name.is_empty() || attribute.is_empty() {
+        return Err(PyValueError::new_err(format!(
+            "expected the app as 'module:attribute', got '{target}'"
+        )));
+    }
+
+    let app_dir = std::path::absolute(Path::new(app_dir))
+        .map_err(|err| PyValueError::new_err(format!("invalid --app-dir: {err}")))?;
+    py.import(intern!(py, "sys"))?
+        .getattr(intern!(py, "path"))?
+        .call_method1(intern!(py, "insert"), (0, app_dir.to_string_lossy()))?;
+
+    let mut app = py.import(module_name)?.into_any();
+    for name in attribute.split('.') {
+        app = app.getattr(name)?;
+    }
+    if !app.is_instance_of::<FastrAPI>() {
+        return Err(PyTypeError::new_err(format!(
+            "'{target}' is not a FastrAPI application"
+        )));
+    }
+    Ok(app)
+}
+
+/// Entry point of the `fastrapi` command.
+#[pyfunction]
+fn main(py: Python<'_>) -> PyResult<()> {
+    let argv: Vec<String> = py
+        .import(intern!(py, "sys"))?
+        .getattr(intern!(py, "argv"))?
+        .extract()?;
+    let cli = match Cli::try_parse_from(argv) {
+        Ok(cli) => cli,
+        Err(err) => {
+            let _ = err.print();
+            return Err(PySystemExit::new_err(err.exit_code()));
+        }
+    };
+
+    let (args, reload) = match cli.command {
+        Command::Run(args) => {
+            let reload = args.reload;
+            (args, reload)
+        }
+        Command::Dev(args) => (args, true),
+    };
+    let app = load_app(py, &args.app, &args.app_dir)?;
+
+    let kwargs = PyDict::new(py);
+    kwargs.set_item(intern!(py, "host"), args.host)?;
+    kwargs.set_item(intern!(py, "port"), args.port)?;
+    kwargs.set_item(intern!(py, "uds"), args.uds)?;
+    kwargs.set_item(intern!(py, "fd"), args.fd)?;
+    kwargs.set_item(intern!(py, "log_filter"), args.log_filter)?;
+    // left unset so the configured defaults apply
+    for (name, value) in [
+        ("ssl_certfile", args.ssl_certfile),
+        ("ssl_keyfile", args.ssl_keyfile),
+        ("log_level", args.log_level),
+        ("log_format", args.log_format),
+    ] {
+        if let Some(value) = value {
+            kwargs.set_item(name, value)?;
+        }
+    }
+    if let Some(workers) = args.workers {
+        kwargs.set_item(intern!(py, "workers"), workers)?;
+    }
+    if let Some(graceful_timeout) = args.graceful_timeout {
+        kwargs.set_item(intern!(py, "graceful_timeout"), graceful_timeout)?;
+    }
+    if reload {
+        let reload_dirs = if args.reload_dirs.is_empty() {
+            vec![args.app_dir]
+        } else {
+            args.reload_dirs
+        };
+        kwargs.set_item(intern!(py, "reload"), true)?;
+        kwargs.set_item(intern!(py, "reload_dirs"), reload_dirs)?;
+        kwargs.set_item(intern!(py, "reload_includes"), args.reload_includes)?;
+    }
+
+    app.call_method(intern!(py, "serve"), (), Some(&kwargs))?;
+    Ok(())
+}
+
+pub fn create_cli_submodule(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = parent.py();
+    let cli_module = PyModule::new(py, "cli")?;
+    cli_module.add_function(wrap_pyfunction!(main, &cli_module)?)?;
+    parent.add_submodule(&cli_module)?;
+
+    let parent_name: String = parent.getattr("__name__")?.extract()?;
+    let base_name = if parent_name.ends_with(".fastrapi") {
+        parent_name.strip_suffix(".fastrapi").unwrap().to_string()
+    } else {
+        parent_name
+    };
+    let full_name = format!("{}.cli", base_name);
+    cli_module.setattr("__name__", &full_name)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item(&full_name, &cli_module)?;
+
+    Ok(())
+}
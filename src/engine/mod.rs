@@ -1,4 +1,5 @@
 pub mod server;
 pub mod app;
 pub mod background;
+pub mod cli;
 pub mod types;
@@ -0,0 +1,514 @@
+use super::conn::{TlsConnection, connect_websocket};
+use super::serve::AppSlot;
+
+use crate::engine::types::FastrAPI;
+use crate::globals::PYTHON_RUNTIME;
+use axum::{
+    Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{
+        HeaderName, HeaderValue, Request,
+        header::{CONNECTION, HOST, SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_VERSION, UPGRADE},
+    },
+};
+use bytes::Bytes;
+use fastwebsockets::{FragmentCollector, Frame, OpCode, handshake};
+use futures_util::StreamExt;
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    intern,
+    prelude::*,
+    types::{PyBytes, PyDict, PyList},
+};
+use rsloop::rust_async::TaskLocals;
+use std::{
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+use tracing::debug;
+
+// characters that cannot appear literally in a request target
+const PATH: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// The `receive`/`send` pair of one ASGI call, awaited on the server's loop.
+struct AsgiChannel {
+    receive: Py<PyAny>,
+    send: Py<PyAny>,
+    locals: TaskLocals,
+}
+
+impl AsgiChannel {
+    async fn receive(&self) -> PyResult<Py<PyAny>> {
+        let message = Python::attach(|py| {
+            let awaitable = self.receive.bind(py).call0()?;
+            rsloop::rust_async::into_future_with_locals(&self.locals, awaitable)
+        })?;
+        message.await
+    }
+
+    async fn send(
+        &self,
+        message: impl for<'py> FnOnce(Python<'py>) -> PyResult<Bound<'py, PyDict>>,
+    ) -> PyResult<()> {
+        let sent = Python::attach(|py| {
+            let awaitable = self.send.bind(py).call1((message(py)?,))?;
+            rsloop::rust_async::into_future_with_locals(&self.locals, awaitable)
+        })?;
+        sent.await.map(drop)
+    }
+}
+
+fn message_type(message: &Py<PyAny>) -> PyResult<String> {
+    Python::attach(|py| message.bind(py).get_item(intern!(py, "type"))?.extract())
+}
+
+fn asgi_message<'py>(py: Python<'py>, kind: &str) -> PyResult<Bound<'py, PyDict>> {
+    let message = PyDict::new(py);
+    message.set_item(intern!(py, "type"), kind)?;
+    Ok(message)
+}
+
+#[pymethods]
+impl FastrAPI {
+    /// ASGI entry point, so the same app can run under uvicorn/hypercorn or be
+    /// mounted in another ASGI app. Requests go through the same router as
+    /// `serve()`; only the transport differs.
+    fn __call__<'py>(
+        slf: &Bound<'py, Self>,
+        scope: &Bound<'py, PyAny>,
+        receive: Py<PyAny>,
+        send: Py<PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        let scope_type: String = scope.get_item(intern!(py, "type"))?.extract()?;
+        let locals = rsloop::rust_async::get_current_locals(py)?;
+        let channel = Arc::new(AsgiChannel {
+            receive,
+            send,
+            locals: locals.clone(),
+        });
+        let app = slf.clone().unbind();
+        let slot = slf.borrow().asgi_app.clone();
+
+        match scope_type.as_str() {
+            "lifespan" => run_asgi(py, locals, run_lifespan(app, slot, channel)),
+            "http" => {
+                let router = slot.router(py, &app)?;
+                let request =
+                    build_request(py, scope, Body::from_stream(request_body(channel.clone())))?;
+                run_asgi(py, locals, handle_http(router, request, channel))
+            }
+            "websocket" => {
+                let router = slot.router(py, &app)?;
+                let mut request = build_request(py, scope, Body::empty())?;
+                let headers = request.headers_mut();
+                headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+                headers.insert(CONNECTION, HeaderValue::from_static("Upgrade"));
+                headers.insert(SEC_WEBSOCKET_VERSION, HeaderValue::from_static("13"));
+                headers.insert(
+                    SEC_WEBSOCKET_KEY,
+                    HeaderValue::from_str(&handshake::generate_key())
+                        .map_err(|err| PyValueError::new_err(err.to_string()))?,
+                );
+                run_asgi(py, locals, handle_websocket(router, request, channel))
+            }
+            other => Err(PyValueError::new_err(format!(
+                "unsupported ASGI scope type: {other}"
+            ))),
+        }
+    }
+}
+
+/// Runs `work` on the handler runtime and hands the ASGI server an awaitable
+/// on its own loop.
+fn run_asgi<'py, F>(py: Python<'py>, locals: TaskLocals, work: F) -> PyResult<Bound<'py, PyAny>>
+where
+    F: Future<Output = PyResult<()>> + Send + 'static,
+{
+    let task = PYTHON_RUNTIME.spawn(work);
+    rsloop::rust_async::future_into_py_with_locals(py, locals, async move {
+        task.await
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))??;
+        Python::attach(|py| Ok(py.None()))
+    })
+}
+
+/// Translates an ASGI scope into the request `serve()` would have built for
+/// the same connection. `root_path` is stripped so mounted apps see their own
+/// routes.
+fn build_request(py: Python<'_>, scope: &Bound<'_, PyAny>, body: Body) -> PyResult<Request<Body>> {
+    let path: String = scope.get_item(intern!(py, "path"))?.extract()?;
+    let root_path: String = scope_item(scope, "root_path")
+        .map(|root_path| root_path.extract())
+        .transpose()?
+        .unwrap_or_default();
+    let path = match path.strip_prefix(root_path.trim_end_matches('/')) {
+        Some(stripped) if !root_path.is_empty() && stripped.is_empty() => "/",
+        Some(stripped) if !root_path.is_empty() && stripped.starts_with('/') => stripped,
+        _ => path.as_str(),
+    };
+    let mut target = utf8_percent_encode(path, PATH).to_string();
+    if let Some(query) = scope_item(scope, "query_string") {
+        let query: Vec<u8> = query.extract()?;
+        if !query.is_empty() {
+            target.push('?');
+            target.push_str(&String::from_utf8_lossy(&query));
+        }
+    }
+
+    let method: String = match scope_item(scope, "method") {
+        Some(method) => method.extract()?,
+        None => "GET".to_string(),
+    };
+    let mut request = Request::builder()
+        .method(method.as_str())
+        .uri(target)
+        .body(body)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    let headers = request.headers_mut();
+    if let Some(scope_headers) = scope_item(scope, "headers") {
+        for header in scope_headers.try_iter()? {
+            let (name, value): (Vec<u8>, Vec<u8>) = header?.extract()?;
+            let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(&name),
+                HeaderValue::from_bytes(&value),
+            ) else {
+                continue;
+            };
+            headers.append(name, value);
+        }
+    }
+    if !headers.contains_key(HOST)
+        && let Some(server) = scope_item(scope, "server")
+        && let Ok((host, port)) = server.extract::<(String, u16)>()
+        && let Ok(host) = HeaderValue::from_str(&format!("{host}:{port}"))
+    {
+        headers.insert(HOST, host);
+    }
+
+    if let Some(client) = scope_item(scope, "client")
+        && let Ok((host, port)) = client.extract::<(String, u16)>()
+        && let Ok(ip) = host.parse::<IpAddr>()
+    {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::new(ip, port)));
+    }
+    let scheme: Option<String> = scope_item(scope, "scheme")
+        .map(|scheme| scheme.extract())
+        .transpose()?;
+    if matches!(scheme.as_deref(), Some("https" | "wss")) {
+        request.extensions_mut().insert(TlsConnection);
+    }
+
+    Ok(request)
+}
+
+/// Optional scope keys may be missing or None depending on the server.
+fn scope_item<'py>(scope: &Bound<'py, PyAny>, key: &str) -> Option<Bound<'py, PyAny>> {
+    scope.get_item(key).ok().filter(|value| !value.is_none())
+}
+
+/// Streams `http.request` messages into the request body as handlers read it.
+fn request_body(
+    channel: Arc<AsgiChannel>,
+) -> impl futures_util::Stream<Item = PyResult<Bytes>> + Send + 'static {
+    async_stream::try_stream! {
+        loop {
+            let message = channel.receive().await?;
+            let (chunk, more_body) = Python::attach(|py| -> PyResult<(Vec<u8>, bool)> {
+                let message = message.bind(py);
+                let kind: String = message.get_item(intern!(py, "type"))?.extract()?;
+                if kind != "http.request" {
+                    return Ok((Vec::new(), false));
+                }
+                let chunk = match message.get_item(intern!(py, "body")) {
+                    Ok(body) => body.extract()?,
+                    Err(_) => Vec::new(),
+                };
+                let more_body = match message.get_item(intern!(py, "more_body")) {
+                    Ok(more_body) => more_body.extract()?,
+                    Err(_) => false,
+                };
+                Ok((chunk, more_body))
+            })?;
+
+            if !chunk.is_empty() {
+                yield Bytes::from(chunk);
+            }
+            if !more_body {
+                break;
+            }
+        }
+    }
+}
+
+async fn handle_http(
+    router: Router,
+    request: Request<Body>,
+    channel: Arc<AsgiChannel>,
+) -> PyResult<()> {
+    let response = router
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|err| match err {});
+    let (parts, body) = response.into_parts();
+
+    channel
+        .send(|py| {
+            let message = asgi_message(py, "http.response.start")?;
+            message.set_item(intern!(py, "status"), parts.status.as_u16())?;
+            let headers = parts.headers.iter().map(|(name, value)| {
+                (
+                    PyBytes::new(py, name.as_str().as_bytes()),
+                    PyBytes::new(py, value.as_bytes()),
+                )
+            });
+            message.set_item(intern!(py, "headers"), PyList::new(py, headers)?)?;
+            Ok(message)
+        })
+        .await?;
+
+    // StreamingResponse bodies are forwarded chunk by chunk
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        if chunk.is_empty() {
+            continue;
+        }
+        channel
+            .send(|py| {
+                let message = asgi_message(py, "http.response.body")?;
+                message.set_item(intern!(py, "body"), PyBytes::new(py, &chunk))?;
+                message.set_item(intern!(py, "more_body"), true)?;
+                Ok(message)
+            })
+            .await?;
+    }
+
+    channel
+        .send(|py| {
+            let message = asgi_message(py, "http.response.body")?;
+            message.set_item(intern!(py, "body"), PyBytes::new(py, b""))?;
+            message.set_item(intern!(py, "more_body"), false)?;
+            Ok(message)
+        })
+        .await
+}
+
+enum ClientMessage {
+    Text(String),
+    Binary(Vec<u8>),
+    Disconnect,
+}
+
+/// Connects to the router's websocket route over an in-memory pipe and relays
+/// frames between it and the ASGI server.
+async fn handle_websocket(
+    router: Router,
+    request: Request<Body>,
+    channel: Arc<AsgiChannel>,
+) -> PyResult<()> {
+    // ASGI servers deliver websocket.connect before anything else
+    if message_type(&channel.receive().await?)? != "websocket.connect" {
+        return Ok(());
+    }
+
+    let remote_addr = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let mut ws = match connect_websocket(router, request, remote_addr).await {
+        Ok(ws) => ws,
+        Err(err) => {
+            debug!("ASGI websocket rejected: {}", err);
+            // closing before accepting is how ASGI rejects with a 403
+            return channel.send(|py| asgi_message(py, "websocket.close")).await;
+        }
+    };
+    channel
+        .send(|py| asgi_message(py, "websocket.accept"))
+        .await?;
+
+    // receive() runs in its own task: a select! that dropped a pending
+    // receive would lose the message it was about to deliver
+    let (tx, mut rx) = mpsc::channel(16);
+    let reader = {
+        let channel = channel.clone();
+        tokio::spawn(async move {
+            while let Ok(message) = channel.receive().await {
+                let message = Python::attach(|py| client_message(message.bind(py)));
+                let Ok(message) = message else { break };
+                let disconnect = matches!(message, ClientMessage::Disconnect);
+                if tx.send(message).await.is_err() || disconnect {
+                    break;
+                }
+            }
+        })
+    };
+
+    let result = relay_websocket(&mut ws, &channel, &mut rx).await;
+    reader.abort();
+    result
+}
+
+fn client_message(message: &Bound<'_, PyAny>) -> PyResult<ClientMessage> {
+    let py = message.py();
+    let kind: String = message.get_item(intern!(py, "type"))?.extract()?;
+    if kind != "websocket.receive" {
+        return Ok(ClientMessage::Disconnect);
+    }
+    if let Ok(text) = message.get_item(intern!(py, "text"))
+        && !text.is_none()
+    {
+        return Ok(ClientMessage::Text(text.extract()?));
+    }
+    let bytes = match message.get_item(intern!(py, "bytes")) {
+        Ok(bytes) if !bytes.is_none() => bytes.extract()?,
+        _ => Vec::new(),
+    };
+    Ok(ClientMessage::Binary(bytes))
+}
+
+async fn relay_websocket(
+    ws: &mut FragmentCollector<TokioIo<Upgraded>>,
+    channel: &AsgiChannel,
+    rx: &mut mpsc::Receiver<ClientMessage>,
+) -> PyResult<()> {
+    let ws_error = |err: fastwebsockets::WebSocketError| PyRuntimeError::new_err(err.to_string());
+
+    loop {
+        tokio::select! {
+            frame = ws.read_frame() => {
+                let frame = frame.map_err(ws_error)?;
+                match frame.opcode {
+                    OpCode::Text => {
+                        let text = String::from_utf8_lossy(&frame.payload).into_owned();
+                        channel
+                            .send(|py| {
+                                let message = asgi_message(py, "websocket.send")?;
+                                message.set_item(intern!(py, "text"), text)?;
+                                Ok(message)
+                            })
+                            .await?;
+                    }
+                    OpCode::Binary => {
+                        let bytes = frame.payload.to_vec();
+                        channel
+                            .send(|py| {
+                                let message = asgi_message(py, "websocket.send")?;
+                                message.set_item(intern!(py, "bytes"), PyBytes::new(py, &bytes))?;
+                                Ok(message)
+                            })
+                            .await?;
+                    }
+                    OpCode::Close => {
+                        let code = match frame.payload.get(..2) {
+                            Some(code) => u16::from_be_bytes([code[0], code[1]]),
+                            None => 1000,
+                        };
+                        return channel
+                            .send(|py| {
+                                let message = asgi_message(py, "websocket.close")?;
+                                message.set_item(intern!(py, "code"), code)?;
+                                Ok(message)
+                            })
+                            .await;
+                    }
+                    _ => {}
+                }
+            }
+
+            Some(message) = rx.recv() => {
+                match message {
+                    ClientMessage::Text(text) => {
+                        ws.write_frame(Frame::text(text.into_bytes().into()))
+                            .await
+                            .map_err(ws_error)?;
+                    }
+                    ClientMessage::Binary(bytes) => {
+                        ws.write_frame(Frame::binary(bytes.into()))
+                            .await
+                            .map_err(ws_error)?;
+                    }
+                    ClientMessage::Disconnect => {
+                        let _ = ws.write_frame(Frame::close(1000, b"")).await;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Drives the ASGI lifespan protocol with the same startup and shutdown
+/// phases `serve()` runs.
+async fn run_lifespan(
+    app: Py<FastrAPI>,
+    slot: Arc<AppSlot>,
+    channel: Arc<AsgiChannel>,
+) -> PyResult<()> {
+    loop {
+        let kind = message_type(&channel.receive().await?)?;
+        let (phase, done) = match kind.as_str() {
+            "lifespan.startup" => ("lifespan.startup", false),
+            "lifespan.shutdown" => ("lifespan.shutdown", true),
+            _ => continue,
+        };
+
+        let app = Python::attach(|py| app.clone_ref(py));
+        let slot = slot.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            Python::attach(|py| {
+                if done {
+                    slot.stop(py)
+                } else {
+                    slot.start(py, &app)
+                }
+            })
+        })
+        .await
+        .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+
+        match result {
+            Ok(()) => {
+                channel
+                    .send(|py| asgi_message(py, &format!("{phase}.complete")))
+                    .await?;
+            }
+            Err(err) => {
+                let reason = err.to_string();
+                channel
+                    .send(|py| {
+                        let message = asgi_message(py, &format!("{phase}.failed"))?;
+                        message.set_item(intern!(py, "message"), reason)?;
+                        Ok(message)
+                    })
+                    .await?;
+                return Ok(());
+            }
+        }
+
+        if done {
+            return Ok(());
+        }
+    }
+}
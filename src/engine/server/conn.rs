@@ -0,0 +1,260 @@
+use super::listener::PeerAddr;
+use super::tls::ReloadableTls;
+
+use axum::{
+    Router,
+    body::Body,
+    extract::ConnectInfo,
+    http::{HeaderValue, Request, Version, header::HOST},
+    serve::Listener,
+};
+use fastwebsockets::{FragmentCollector, handshake};
+use hyper::{body::Incoming, upgrade::Upgraded};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::{
+        conn::auto::Builder,
+        graceful::{GracefulShutdown, Watcher},
+    },
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{fmt::Debug, future::Future, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::Instant,
+};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+use tracing::{debug, warn};
+
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const IN_MEMORY_BUFFER_SIZE: usize = 64 * 1024;
+// RFC 9113 section 6.9.1
+const MAX_WINDOW_SIZE: u32 = (1 << 31) - 1;
+
+/// Request extension marking connections that were TLS-terminated by this server.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TlsConnection;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Http2Config {
+    pub(crate) max_concurrent_streams: Option<u32>,
+    pub(crate) initial_stream_window_size: Option<u32>,
+    pub(crate) initial_connection_window_size: Option<u32>,
+    pub(crate) keep_alive_interval: Option<Duration>,
+}
+
+pub(crate) fn parse_http2_params(
+    http2: bool,
+    max_concurrent_streams: Option<u32>,
+    initial_stream_window_size: Option<u32>,
+    initial_connection_window_size: Option<u32>,
+    keep_alive_interval: Option<f64>,
+) -> PyResult<Option<Http2Config>> {
+    if !http2 {
+        if max_concurrent_streams.is_some()
+            || initial_stream_window_size.is_some()
+            || initial_connection_window_size.is_some()
+            || keep_alive_interval.is_some()
+        {
+            return Err(PyValueError::new_err(
+                "http2_* tuning options require http2=True",
+            ));
+        }
+        return Ok(None);
+    }
+
+    for (name, size) in [
+        (
+            "http2_initial_stream_window_size",
+            initial_stream_window_size,
+        ),
+        (
+            "http2_initial_connection_window_size",
+            initial_connection_window_size,
+        ),
+    ] {
+        if size.is_some_and(|size| size > MAX_WINDOW_SIZE) {
+            return Err(PyValueError::new_err(format!(
+                "{name} must be at most {MAX_WINDOW_SIZE}"
+            )));
+        }
+    }
+
+    let keep_alive_interval = match keep_alive_interval {
+        Some(secs) if !secs.is_finite() || secs <= 0.0 => {
+            return Err(PyValueError::new_err(
+                "http2_keep_alive_interval must be a positive number of seconds",
+            ));
+        }
+        Some(secs) => Some(Duration::from_secs_f64(secs)),
+        None => None,
+    };
+
+    Ok(Some(Http2Config {
+        max_concurrent_streams,
+        initial_stream_window_size,
+        initial_connection_window_size,
+        keep_alive_interval,
+    }))
+}
+
+/// Without `http2` connections are served as HTTP/1.1 only. With it, the
+/// connection preface decides the protocol, which covers both prior-knowledge
+/// h2c and TLS clients that negotiated `h2` via ALPN. Websocket upgrades keep
+/// going through the HTTP/1.1 path since extended CONNECT is not advertised.
+fn connection_builder(http2: Option<&Http2Config>) -> Builder<TokioExecutor> {
+    let Some(config) = http2 else {
+        return Builder::new(TokioExecutor::new()).http1_only();
+    };
+
+    let mut builder = Builder::new(TokioExecutor::new());
+    let mut h2 = builder.http2();
+    h2.timer(TokioTimer::new())
+        .initial_stream_window_size(config.initial_stream_window_size)
+        .initial_connection_window_size(config.initial_connection_window_size)
+        .keep_alive_interval(config.keep_alive_interval);
+    // None would lift hyper's default cap of 200 streams rather than keep it
+    if let Some(max) = config.max_concurrent_streams {
+        h2.max_concurrent_streams(max);
+    }
+    builder
+}
+
+/// Serves until `shutdown` resolves, then stops accepting and gives in-flight
+/// connections `graceful_timeout` to finish before closing them. Returns the
+/// end of that grace period so callers can bound the rest of their draining.
+pub(crate) async fn serve_connections<L, F>(
+    mut listener: L,
+    router: Router,
+    tls: Option<Arc<ReloadableTls>>,
+    http2: Option<Http2Config>,
+    graceful_timeout: Duration,
+    shutdown: F,
+) -> Result<Instant, String>
+where
+    L: Listener,
+    L::Addr: PeerAddr + Debug + 'static,
+    F: Future<Output = ()> + Send,
+{
+    let graceful = GracefulShutdown::new();
+    let force_close = CancellationToken::new();
+    let builder = connection_builder(http2.as_ref());
+    tokio::pin!(shutdown);
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+        let remote_addr = addr.peer_addr();
+
+        let router = router.clone();
+        let builder = builder.clone();
+        let watcher = graceful.watcher();
+        let acceptor = tls.as_ref().map(|tls| tls.acceptor());
+        let force_close = force_close.clone();
+
+        tokio::spawn(async move {
+            let connection = async move {
+                let Some(acceptor) = acceptor else {
+                    return serve_io(builder, watcher, stream, router, remote_addr, false).await;
+                };
+                match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        serve_io(builder, watcher, stream, router, remote_addr, true).await
+                    }
+                    Ok(Err(err)) => debug!("TLS handshake with {:?} failed: {}", addr, err),
+                    Err(_) => debug!("TLS handshake with {:?} timed out", addr),
+                }
+            };
+
+            // dropping the connection future closes the socket
+            tokio::select! {
+                _ = connection => {}
+                _ = force_close.cancelled() => {}
+            }
+        });
+    }
+
+    drop(listener);
+    let deadline = Instant::now() + graceful_timeout;
+    if tokio::time::timeout_at(deadline, graceful.shutdown())
+        .await
+        .is_err()
+    {
+        warn!(
+            "Connections still open after {:.1}s, closing them",
+            graceful_timeout.as_secs_f64()
+        );
+        force_close.cancel();
+    }
+    Ok(deadline)
+}
+
+/// Opens a websocket to `router` over an in-memory pipe, for callers that have
+/// no socket of their own (the test client and the ASGI bridge). The upgrade
+/// needs a real HTTP/1.1 connection, so one is served on the other end.
+pub(crate) async fn connect_websocket(
+    router: Router,
+    request: Request<Body>,
+    remote_addr: Option<SocketAddr>,
+) -> Result<FragmentCollector<TokioIo<Upgraded>>, String> {
+    let (client_io, server_io) = tokio::io::duplex(IN_MEMORY_BUFFER_SIZE);
+
+    tokio::spawn(async move {
+        let service = hyper::service::service_fn(move |mut req: Request<Incoming>| {
+            if let Some(remote_addr) = remote_addr {
+                req.extensions_mut().insert(ConnectInfo(remote_addr));
+            }
+            router.clone().oneshot(req.map(Body::new))
+        });
+        let conn = Builder::new(TokioExecutor::new())
+            .http1_only()
+            .serve_connection_with_upgrades(TokioIo::new(server_io), service);
+        if let Err(err) = conn.await {
+            debug!("in-memory connection closed with error: {}", err);
+        }
+    });
+
+    let (ws, _response) = handshake::client(&TokioExecutor::new(), request, client_io)
+        .await
+        .map_err(|err| format!("websocket handshake failed: {err}"))?;
+    Ok(FragmentCollector::new(ws))
+}
+
+async fn serve_io<I>(
+    builder: Builder<TokioExecutor>,
+    watcher: Watcher,
+    io: I,
+    router: Router,
+    remote_addr: Option<SocketAddr>,
+    secure: bool,
+) where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = hyper::service::service_fn(move |mut req: Request<Incoming>| {
+        if let Some(remote_addr) = remote_addr {
+            req.extensions_mut().insert(ConnectInfo(remote_addr));
+        }
+        if secure {
+            req.extensions_mut().insert(TlsConnection);
+        }
+        // h2 carries the host in :authority; the host checks in the layer stack read `Host`
+        if req.version() == Version::HTTP_2
+            && !req.headers().contains_key(HOST)
+            && let Some(host) = req
+                .uri()
+                .authority()
+                .and_then(|authority| HeaderValue::from_str(authority.as_str()).ok())
+        {
+            req.headers_mut().insert(HOST, host);
+        }
+        router.clone().oneshot(req.map(Body::new))
+    });
+
+    let conn = builder.serve_connection_with_upgrades(TokioIo::new(io), service);
+    if let Err(err) = watcher.watch(conn).await {
+        debug!("connection closed with error: {}", err);
+    }
+}
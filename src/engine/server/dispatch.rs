@@ -1,47 +1,177 @@
 use super::payload::*;
 use super::rate_limit::*;
+use super::route_table::RouteTable;
 use super::serve::*;
+use super::telemetry::{in_child_span, record_route};
+use super::threads::acquire_sync_permit;
 
 use axum::{
+    Json,
+    body::{Body, HttpBody},
     extract::Request,
-    http::StatusCode,
+    http::{HeaderMap, Method, StatusCode, Uri, header},
     response::{IntoResponse, Response},
 };
 use smallvec::SmallVec;
-use std::sync::Arc;
+use sonic_rs::json;
+use std::{borrow::Cow, sync::Arc};
 
 use crate::{
-    ffi::py_handlers::{ExecutionMode, run_py_handler, run_py_handler_no_request},
+    ffi::py_handlers::{
+        ExecutionMode, run_method_not_allowed_handler, run_py_handler, run_py_handler_no_request,
+    },
+    http::request::PendingBody,
     routing::{
-        router::{FrozenRouter, RouteMatch},
-        types::{HttpMethod, PathParamRange},
+        router::{AllowedMethods, FrozenRouter, RouteMatch},
+        types::{
+            BodyPayload, HttpMethod, MatchedRoute, ParamValue, PathParamRange, RouteHandler,
+            decode_path_component,
+        },
     },
 };
 
-pub(crate) async fn dispatch(router: Arc<FrozenRouter>, state: AppState, req: Request) -> Response {
-    let Ok(method) = HttpMethod::try_from(req.method()) else {
-        return StatusCode::METHOD_NOT_ALLOWED.into_response();
-    };
-
+pub(crate) async fn dispatch(
+    router: Arc<FrozenRouter>,
+    host_params: SmallVec<[PathParamRange; 4]>,
+    state: AppState,
+    req: Request,
+) -> Response {
     let Some(path_str) = dispatch_path(&state, req.uri().path()) else {
         return StatusCode::NOT_FOUND.into_response();
     };
 
-    let route_match = match router.resolve(method, path_str) {
-        Some(v) => v,
-        None => return StatusCode::NOT_FOUND.into_response(),
+    let method = HttpMethod::try_from(req.method()).ok();
+    let mut route_match = match method {
+        Some(method) => router.resolve(method, path_str),
+        None => router.resolve_extension(req.method().as_str(), path_str),
+    };
+    // HEAD without its own registration runs the GET route
+    let implicit_head = route_match.is_none() && method == Some(HttpMethod::HEAD);
+    if implicit_head {
+        route_match = router.resolve(HttpMethod::GET, path_str);
+    }
+    let Some(route_match) = route_match else {
+        let allowed = router.allowed_methods(path_str);
+        if allowed.is_empty() {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        if method == Some(HttpMethod::OPTIONS) {
+            return (
+                StatusCode::NO_CONTENT,
+                [(header::ALLOW, allow_header(&allowed))],
+            )
+                .into_response();
+        }
+        return method_not_allowed(&state, &allowed, req).await;
+    };
+
+    let (handler, params_iter, route) = match route_match {
+        RouteMatch::Static(handler, route) => (handler, None, route),
+        RouteMatch::Params(handler, params, route) => (handler, Some(params), route),
+    };
+    record_route(req.method().as_str(), route, handler.execution_mode);
+    let route = state.track_routes.then(|| MatchedRoute(route.clone()));
+
+    let path_base = path_str.as_ptr() as usize;
+    let mut param_ranges: SmallVec<[PathParamRange; 4]> = if let Some(params) = params_iter {
+        // decoded once here; values without escapes stay slices of the path
+        let ranges = params
+            .iter()
+            .map(|(k, v)| -> Result<PathParamRange, std::str::Utf8Error> {
+                let start = v.as_ptr() as usize - path_base;
+                debug_assert!(
+                    start <= path_str.len(),
+                    "matchit returned a string outside the input path"
+                );
+                let value = match decode_path_component(v)? {
+                    Cow::Borrowed(_) => ParamValue::Span {
+                        start,
+                        end: start + v.len(),
+                    },
+                    Cow::Owned(decoded) => ParamValue::Decoded(decoded.into_boxed_str()),
+                };
+                Ok(PathParamRange {
+                    key: k.to_string(),
+                    value,
+                })
+            })
+            .collect::<Result<_, _>>();
+        match ranges {
+            Ok(ranges) => ranges,
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "detail": "Invalid percent-encoding in path" })),
+                )
+                    .into_response();
+            }
+        }
+    } else {
+        SmallVec::new()
     };
+    param_ranges.extend(host_params);
+
+    let mut response = run_route(state, handler, param_ranges, req).await;
+    if implicit_head {
+        response = strip_body(response);
+    }
+    if let Some(route) = route {
+        response.extensions_mut().insert(route);
+    }
+    response
+}
 
-    let (handler, params_iter) = match route_match {
-        RouteMatch::Static(handler) => (handler, None),
-        RouteMatch::Params(handler, params) => (handler, Some(params)),
+fn allow_header(allowed: &AllowedMethods<'_>) -> String {
+    allowed.join(", ")
+}
+
+/// Drops the body of a GET response answering a HEAD request, keeping the
+/// length it would have had.
+fn strip_body(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+    if let Some(len) = body.size_hint().exact()
+        && !parts.headers.contains_key(header::CONTENT_LENGTH)
+    {
+        parts.headers.insert(header::CONTENT_LENGTH, len.into());
+    }
+    Response::from_parts(parts, Body::empty())
+}
+
+/// A 405 for a path registered under other methods, through the app's 405
+/// exception handler when it has one.
+async fn method_not_allowed(
+    state: &AppState,
+    allowed: &AllowedMethods<'_>,
+    req: Request,
+) -> Response {
+    let allow = allow_header(allowed);
+    let Some(handler) = state.method_not_allowed_handler.clone() else {
+        return (
+            StatusCode::METHOD_NOT_ALLOWED,
+            [(header::ALLOW, allow)],
+            Json(json!({ "detail": "Method Not Allowed" })),
+        )
+            .into_response();
     };
+    let (parts, _) = req.into_parts();
+    run_method_not_allowed_handler(handler, state.async_loop.clone(), parts, allow).await
+}
 
+async fn run_route(
+    state: AppState,
+    handler: Arc<RouteHandler>,
+    param_ranges: SmallVec<[PathParamRange; 4]>,
+    req: Request,
+) -> Response {
     if let Some(limit) = handler.rate_limit_per_second
         && is_rate_limited(&req, Arc::as_ptr(&handler) as usize, limit)
     {
         return StatusCode::TOO_MANY_REQUESTS.into_response();
     }
+    let _permit = match acquire_sync_permit(&state, &handler).await {
+        Ok(permit) => permit,
+        Err(resp) => return resp,
+    };
 
     if matches!(
         handler.execution_mode,
@@ -56,43 +186,39 @@ pub(crate) async fn dispatch(router: Arc<FrozenRouter>, state: AppState, req: Re
         .await;
     }
 
-    let path_base = path_str.as_ptr() as usize;
-    let param_ranges: SmallVec<[PathParamRange; 4]> = if let Some(params) = params_iter {
-        params
-            .iter()
-            .map(|(k, v)| {
-                let start = v.as_ptr() as usize - path_base;
-                debug_assert!(
-                    start <= path_str.len(),
-                    "matchit returned a string outside the input path"
-                );
-                PathParamRange {
-                    key: k.to_string(),
-                    start,
-                    end: start + v.len(),
-                }
-            })
-            .collect()
-    } else {
-        SmallVec::new()
-    };
-
-    let (request_parts, body) = req.into_parts();
+    let (mut request_parts, body) = req.into_parts();
     let has_body_requirements = !handler.body_param_indices.is_empty();
 
     let payload = if has_body_requirements {
-        match extract_payload(&request_parts.headers, body, &handler, &state).await {
+        match in_child_span(
+            "extract_payload",
+            extract_payload(&request_parts.headers, body, &handler, &state),
+        )
+        .await
+        {
             Ok(p) => p,
             Err(resp) => return resp,
         }
     } else {
+        if handler.dependency_needs_request {
+            request_parts.extensions.insert(PendingBody::new(body));
+        }
         None
     };
+    // a JSON body parameter keeps the raw bytes, so `Request.body()` still works
+    if handler.dependency_needs_request
+        && let Some(BodyPayload::Json { raw, .. }) = &payload
+    {
+        request_parts
+            .extensions
+            .insert(PendingBody::new(Body::from(raw.clone())));
+    }
 
     run_py_handler(
         state.rt_handle,
         state.async_loop,
         state.sync_to_threadpool,
+        state.background_tasks,
         handler,
         request_parts,
         param_ranges,
@@ -113,18 +239,70 @@ pub(crate) fn dispatch_path<'a>(state: &AppState, original_path: &'a str) -> Opt
         None
     }
 }
-pub(crate) fn request_matches_router(
-    router: &FrozenRouter,
+
+/// The router with a route for `req`'s method, with the parameters of the
+/// host it matched: the first host router that has one and whose host
+/// matches, or else the router of routes without a host.
+pub(crate) fn select_router(
+    table: &RouteTable,
     state: &AppState,
     req: &Request,
-) -> bool {
-    let Ok(method) = HttpMethod::try_from(req.method()) else {
-        return true;
-    };
+) -> Option<(Arc<FrozenRouter>, SmallVec<[PathParamRange; 4]>)> {
+    let path = dispatch_path(state, req.uri().path())?;
 
-    let Some(path) = dispatch_path(state, req.uri().path()) else {
-        return false;
-    };
+    if !table.host_routers.is_empty()
+        && let Some(host) = request_host(req.headers(), req.uri())
+        && let Some((router, params)) = table.host_routers.iter().find_map(|(pattern, router)| {
+            if !answers_method(router, req.method(), path) {
+                return None;
+            }
+            pattern.matches(host).map(|params| (router, params))
+        })
+    {
+        return Some((router.clone(), params));
+    }
+    answers_method(&table.router, req.method(), path)
+        .then(|| (table.router.clone(), SmallVec::new()))
+}
+
+/// The router that has `req`'s path under other methods only, for a request
+/// no route or mount took; it answers with a 405 or the allowed methods.
+pub(crate) fn select_router_for_path(
+    table: &RouteTable,
+    state: &AppState,
+    req: &Request,
+) -> Option<Arc<FrozenRouter>> {
+    let path = dispatch_path(state, req.uri().path())?;
+
+    if !table.host_routers.is_empty()
+        && let Some(host) = request_host(req.headers(), req.uri())
+        && let Some((_, router)) = table
+            .host_routers
+            .iter()
+            .find(|(pattern, router)| router.has_path(path) && pattern.matches(host).is_some())
+    {
+        return Some(router.clone());
+    }
+    table.router.has_path(path).then(|| table.router.clone())
+}
+
+/// Whether `router` has a route for `method` at `path`; HEAD is answered by
+/// GET routes as well.
+fn answers_method(router: &FrozenRouter, method: &Method, path: &str) -> bool {
+    match HttpMethod::try_from(method) {
+        Ok(HttpMethod::HEAD) => {
+            router.resolve(HttpMethod::HEAD, path).is_some()
+                || router.resolve(HttpMethod::GET, path).is_some()
+        }
+        Ok(method) => router.resolve(method, path).is_some(),
+        Err(_) => router.resolve_extension(method.as_str(), path).is_some(),
+    }
+}
 
-    router.resolve(method, path).is_some()
+/// The `Host` header, or the authority HTTP/2 sends in its place.
+pub(crate) fn request_host<'a>(headers: &'a HeaderMap, uri: &'a Uri) -> Option<&'a str> {
+    headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| uri.authority().map(|authority| authority.as_str()))
 }
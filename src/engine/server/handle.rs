@@ -0,0 +1,207 @@
+use parking_lot::{Condvar, Mutex};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyTimeoutError, PyValueError},
+    prelude::*,
+};
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone, Debug)]
+pub(crate) enum ServerStatus {
+    Starting,
+    Running {
+        addr: Option<SocketAddr>,
+        url: String,
+    },
+    Stopped {
+        error: Option<String>,
+    },
+}
+
+/// Lifecycle of a server thread, shared between the thread and its handle.
+pub(crate) struct ServerState {
+    status: Mutex<ServerStatus>,
+    changed: Condvar,
+}
+
+impl ServerState {
+    pub(crate) fn new() -> Self {
+        Self {
+            status: Mutex::new(ServerStatus::Starting),
+            changed: Condvar::new(),
+        }
+    }
+
+    pub(crate) fn set_running(&self, addr: Option<SocketAddr>, url: String) {
+        *self.status.lock() = ServerStatus::Running { addr, url };
+        self.changed.notify_all();
+    }
+
+    pub(crate) fn set_stopped(&self, error: Option<String>) {
+        *self.status.lock() = ServerStatus::Stopped { error };
+        self.changed.notify_all();
+    }
+
+    pub(crate) fn status(&self) -> ServerStatus {
+        self.status.lock().clone()
+    }
+
+    /// Blocks until `done` accepts the status or the timeout elapses, returning
+    /// the last status seen either way.
+    fn wait_until(
+        &self,
+        timeout: Option<Duration>,
+        done: impl Fn(&ServerStatus) -> bool,
+    ) -> ServerStatus {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut status = self.status.lock();
+        while !done(&status) {
+            match deadline {
+                Some(deadline) => {
+                    if self.changed.wait_until(&mut status, deadline).timed_out() {
+                        break;
+                    }
+                }
+                None => self.changed.wait(&mut status),
+            }
+        }
+        status.clone()
+    }
+}
+
+/// Returned by `FastrAPI.start()`: the server keeps running on a background
+/// thread until `stop()` is called, which performs the same graceful shutdown
+/// and lifespan exit as interrupting `serve()`.
+#[pyclass(name = "ServerHandle", skip_from_py_object)]
+pub struct PyServerHandle {
+    state: Arc<ServerState>,
+    shutdown: CancellationToken,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl PyServerHandle {
+    pub(crate) fn new(
+        state: Arc<ServerState>,
+        shutdown: CancellationToken,
+        thread: JoinHandle<()>,
+    ) -> Self {
+        Self {
+            state,
+            shutdown,
+            thread: Mutex::new(Some(thread)),
+        }
+    }
+
+    /// Waits for the server thread to finish; used by the blocking `serve()`.
+    pub(crate) fn join(&self, py: Python<'_>) -> PyResult<()> {
+        let Some(thread) = self.thread.lock().take() else {
+            return Ok(());
+        };
+        py.detach(move || thread.join())
+            .map_err(|_| PyRuntimeError::new_err("Server thread panicked"))
+    }
+}
+
+#[pymethods]
+impl PyServerHandle {
+    /// Port the server is bound to, with `port=0` resolved; None until ready or
+    /// when listening on a unix socket.
+    #[getter]
+    fn port(&self) -> Option<u16> {
+        match self.state.status() {
+            ServerStatus::Running { addr, .. } => addr.map(|addr| addr.port()),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn host(&self) -> Option<String> {
+        match self.state.status() {
+            ServerStatus::Running { addr, .. } => addr.map(|addr| addr.ip().to_string()),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn url(&self) -> Option<String> {
+        match self.state.status() {
+            ServerStatus::Running { url, .. } => Some(url),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn running(&self) -> bool {
+        matches!(self.state.status(), ServerStatus::Running { .. })
+    }
+
+    /// Blocks until the startup phase finished and the listener is bound.
+    #[pyo3(signature = (timeout=None))]
+    fn wait_ready(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<()> {
+        let timeout = timeout_duration(timeout)?;
+        let state = self.state.clone();
+        let status = py.detach(move || {
+            state.wait_until(timeout, |status| !matches!(status, ServerStatus::Starting))
+        });
+
+        match status {
+            ServerStatus::Running { .. } => Ok(()),
+            ServerStatus::Starting => Err(PyTimeoutError::new_err(
+                "server did not become ready in time",
+            )),
+            ServerStatus::Stopped { error } => {
+                Err(PyRuntimeError::new_err(error.unwrap_or_else(|| {
+                    "server stopped before becoming ready".to_string()
+                })))
+            }
+        }
+    }
+
+    /// Triggers graceful shutdown and waits for the shutdown phase to finish.
+    #[pyo3(signature = (timeout=None))]
+    fn stop(&self, py: Python<'_>, timeout: Option<f64>) -> PyResult<()> {
+        let timeout = timeout_duration(timeout)?;
+        self.shutdown.cancel();
+
+        let state = self.state.clone();
+        let status = py.detach(move || {
+            state.wait_until(timeout, |status| {
+                matches!(status, ServerStatus::Stopped { .. })
+            })
+        });
+        if !matches!(status, ServerStatus::Stopped { .. }) {
+            return Err(PyTimeoutError::new_err("server did not stop in time"));
+        }
+
+        self.join(py)
+    }
+
+    fn __enter__(slf: Py<Self>, py: Python<'_>) -> PyResult<Py<Self>> {
+        slf.borrow(py).wait_ready(py, None)?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python<'_>, _args: &Bound<'_, pyo3::types::PyTuple>) -> PyResult<()> {
+        self.stop(py, None)
+    }
+}
+
+fn timeout_duration(timeout: Option<f64>) -> PyResult<Option<Duration>> {
+    timeout
+        .map(|seconds| {
+            if seconds.is_finite() && seconds >= 0.0 {
+                Ok(Duration::from_secs_f64(seconds))
+            } else {
+                Err(PyValueError::new_err(
+                    "timeout must be a non-negative number of seconds",
+                ))
+            }
+        })
+        .transpose()
+}
@@ -0,0 +1,226 @@
+use super::serve::AppState;
+
+use crate::ffi::py_handlers::schedule_python_coroutine;
+use axum::{
+    Json, Router,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use futures_util::future::join_all;
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    intern,
+    prelude::*,
+};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU8, Ordering},
+    },
+    time::Duration,
+};
+use tracing::warn;
+
+const STARTING: u8 = 0;
+const READY: u8 = 1;
+const DRAINING: u8 = 2;
+
+/// Where a served app is in its lifecycle, as reported by `ready_url`: ready
+/// once the startup phase has completed, and no longer ready as soon as
+/// graceful shutdown begins.
+#[derive(Default)]
+pub struct Readiness(AtomicU8);
+
+impl Readiness {
+    pub(crate) fn mark_ready(&self) {
+        let _ = self
+            .0
+            .compare_exchange(STARTING, READY, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    pub(crate) fn mark_draining(&self) {
+        self.0.store(DRAINING, Ordering::Release);
+    }
+}
+
+/// A Python callable registered with `readiness_check`. It fails by raising,
+/// returning `False` or running past its timeout.
+pub struct ReadinessCheck {
+    pub name: String,
+    pub check: Py<PyAny>,
+    pub is_async: bool,
+    pub timeout: Duration,
+}
+
+impl ReadinessCheck {
+    fn clone_ref(&self, py: Python<'_>) -> Self {
+        Self {
+            name: self.name.clone(),
+            check: self.check.clone_ref(py),
+            is_async: self.is_async,
+            timeout: self.timeout,
+        }
+    }
+}
+
+pub(crate) fn is_coroutine_function(py: Python<'_>, func: &Bound<'_, PyAny>) -> PyResult<bool> {
+    py.import(intern!(py, "inspect"))?
+        .call_method1(intern!(py, "iscoroutinefunction"), (func,))?
+        .is_truthy()
+}
+
+/// Body of both probes; `checks` only appears once readiness checks ran.
+#[derive(Serialize)]
+struct ProbeReport {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    checks: Option<BTreeMap<String, &'static str>>,
+}
+
+/// Adds the probe routes. They are added after every layer, so neither
+/// middlewares nor the access log see them.
+pub(crate) fn add_health_routes(
+    py: Python<'_>,
+    app: Router,
+    health_url: Option<&str>,
+    ready_url: Option<&str>,
+    checks: &[ReadinessCheck],
+    state: &AppState,
+) -> Router {
+    let mut app = app;
+    if let Some(health_url) = health_url {
+        app = app.route(
+            health_url,
+            get(|| async {
+                Json(ProbeReport {
+                    status: "ok",
+                    checks: None,
+                })
+            }),
+        );
+    }
+    if let Some(ready_url) = ready_url {
+        let checks: Arc<Vec<Arc<ReadinessCheck>>> = Arc::new(
+            checks
+                .iter()
+                .map(|check| Arc::new(check.clone_ref(py)))
+                .collect(),
+        );
+        let state = state.clone();
+        app = app.route(ready_url, get(move || ready(state.clone(), checks.clone())));
+    }
+    app
+}
+
+/// Rejects probe URLs that could not be routed: each must be a path, and
+/// neither may shadow the other or one of the app's built-in pages.
+pub(crate) fn validate_probe_urls(
+    health_url: Option<&str>,
+    ready_url: Option<&str>,
+    builtin_urls: &[(&str, Option<&str>)],
+) -> PyResult<()> {
+    let probes = [("health_url", health_url), ("ready_url", ready_url)];
+    for (name, url) in probes {
+        let Some(url) = url else { continue };
+        if !url.starts_with('/') {
+            return Err(PyValueError::new_err(format!(
+                "{name} must start with '/', got '{url}'"
+            )));
+        }
+        if let Some((builtin, _)) = builtin_urls
+            .iter()
+            .find(|(_, builtin)| *builtin == Some(url))
+        {
+            return Err(PyValueError::new_err(format!(
+                "{name} '{url}' is already used by {builtin}"
+            )));
+        }
+    }
+    if health_url.is_some() && health_url == ready_url {
+        return Err(PyValueError::new_err(
+            "health_url and ready_url must be different",
+        ));
+    }
+    Ok(())
+}
+
+async fn ready(state: AppState, checks: Arc<Vec<Arc<ReadinessCheck>>>) -> Response {
+    let status = match state.readiness.0.load(Ordering::Acquire) {
+        STARTING => "starting",
+        DRAINING => "shutting_down",
+        _ => "ready",
+    };
+    if status != "ready" || checks.is_empty() {
+        return probe_response(status, None);
+    }
+
+    let results = join_all(checks.iter().map(|check| run_check(&state, check.clone()))).await;
+    let status = if results.iter().all(|result| *result == "ok") {
+        "ready"
+    } else {
+        "unavailable"
+    };
+    let report = checks
+        .iter()
+        .map(|check| check.name.clone())
+        .zip(results)
+        .collect();
+    probe_response(status, Some(report))
+}
+
+fn probe_response(
+    status: &'static str,
+    checks: Option<BTreeMap<String, &'static str>>,
+) -> Response {
+    let code = if status == "ready" {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(ProbeReport { status, checks })).into_response()
+}
+
+async fn run_check(state: &AppState, check: Arc<ReadinessCheck>) -> &'static str {
+    let timeout = check.timeout;
+    let outcome = if check.is_async {
+        let future = Python::attach(|py| {
+            let coroutine = check.check.bind(py).call0()?;
+            schedule_python_coroutine(py, &state.async_loop, coroutine)
+        });
+        match future {
+            Ok(future) => tokio::time::timeout(timeout, future).await,
+            Err(err) => Ok(Err(err)),
+        }
+    } else {
+        // a sync check past its timeout keeps its blocking thread until it returns
+        let check = check.clone();
+        let call = state.rt_handle.spawn_blocking(move || {
+            Python::attach(|py| check.check.bind(py).call0().map(Bound::unbind))
+        });
+        tokio::time::timeout(timeout, call).await.map(|joined| {
+            joined.unwrap_or_else(|err| Err(PyRuntimeError::new_err(err.to_string())))
+        })
+    };
+
+    match outcome {
+        // only an explicit `False` fails; `None` and other values pass
+        Ok(Ok(result)) => {
+            if Python::attach(|py| matches!(result.bind(py).extract::<bool>(), Ok(false))) {
+                "failed"
+            } else {
+                "ok"
+            }
+        }
+        Ok(Err(err)) => {
+            warn!("readiness check '{}' raised: {}", check.name, err);
+            "failed"
+        }
+        Err(_) => {
+            warn!("readiness check '{}' timed out", check.name);
+            "timeout"
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{io, net::SocketAddr};
+use tokio::net::{TcpListener, TcpSocket, lookup_host};
+
+#[cfg(unix)]
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+#[cfg(unix)]
+use std::{
+    os::unix::{
+        fs::{FileTypeExt, PermissionsExt},
+        io::{FromRawFd, IntoRawFd, RawFd},
+    },
+    path::PathBuf,
+};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+const LISTEN_BACKLOG: u32 = 1024;
+// sd_listen_fds(3)
+#[cfg(unix)]
+const SD_LISTEN_FDS_START: RawFd = 3;
+#[cfg(unix)]
+const UNIX_URL_PATH: &AsciiSet = &NON_ALPHANUMERIC.remove(b'.').remove(b'-').remove(b'_');
+
+pub(crate) enum BindTarget {
+    Tcp {
+        addr: String,
+        reuse_port: bool,
+    },
+    #[cfg(unix)]
+    Unix(UdsConfig),
+    #[cfg(unix)]
+    Fd(RawFd),
+}
+
+#[cfg(unix)]
+pub(crate) struct UdsConfig {
+    pub(crate) path: PathBuf,
+    pub(crate) mode: Option<u32>,
+    pub(crate) cleanup: bool,
+}
+
+pub(crate) enum BoundListener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix {
+        listener: UnixListener,
+        cleanup: Option<UnixSocketCleanup>,
+    },
+}
+
+/// Removes the socket file once the server is done with it.
+#[cfg(unix)]
+pub(crate) struct UnixSocketCleanup(PathBuf);
+
+#[cfg(unix)]
+impl Drop for UnixSocketCleanup {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Peer address recorded as `ConnectInfo`; unix socket peers have none.
+pub(crate) trait PeerAddr {
+    fn peer_addr(&self) -> Option<SocketAddr>;
+}
+
+impl PeerAddr for SocketAddr {
+    #[inline]
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        Some(*self)
+    }
+}
+
+#[cfg(unix)]
+impl PeerAddr for tokio::net::unix::SocketAddr {
+    #[inline]
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+}
+
+impl BoundListener {
+    pub(crate) fn local_addr(&self) -> Option<SocketAddr> {
+        match self {
+            BoundListener::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            BoundListener::Unix { .. } => None,
+        }
+    }
+
+    /// Base URL for log lines: `http://127.0.0.1:8000` for TCP and the
+    /// `http+unix://%2Frun%2Fapp.sock` form understood by httpie/requests-unixsocket
+    /// for unix sockets.
+    pub(crate) fn base_url(&self, scheme: &str) -> String {
+        match self {
+            BoundListener::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => format!("{scheme}://{addr}"),
+                Err(_) => format!("{scheme}://<unknown>"),
+            },
+            #[cfg(unix)]
+            BoundListener::Unix { listener, .. } => {
+                let addr = listener.local_addr().ok();
+                match addr.as_ref().and_then(|addr| addr.as_pathname()) {
+                    Some(path) => format!(
+                        "{scheme}+unix://{}",
+                        utf8_percent_encode(&path.to_string_lossy(), UNIX_URL_PATH)
+                    ),
+                    None => format!("{scheme}+unix://<unnamed>"),
+                }
+            }
+        }
+    }
+}
+
+/// `configured_host` and `configured_port` come from fastrapi.toml, pyproject
+/// or the environment; they rank below every listener asked for, including a
+/// socket inherited through systemd.
+pub(crate) fn parse_bind_params(
+    host: Option<String>,
+    port: Option<u16>,
+    configured_host: Option<String>,
+    configured_port: Option<u16>,
+    uds: Option<String>,
+    uds_mode: Option<u32>,
+    uds_cleanup: bool,
+    fd: Option<i32>,
+    reuse_port: bool,
+) -> PyResult<BindTarget> {
+    if uds.is_some() && fd.is_some() {
+        return Err(PyValueError::new_err("uds and fd cannot be combined"));
+    }
+    if (uds.is_some() || fd.is_some()) && (host.is_some() || port.is_some()) {
+        return Err(PyValueError::new_err(
+            "host/port cannot be combined with uds or fd",
+        ));
+    }
+    if uds.is_none() && uds_mode.is_some() {
+        return Err(PyValueError::new_err("uds_mode requires uds"));
+    }
+
+    #[cfg(unix)]
+    {
+        if let Some(path) = uds {
+            return Ok(BindTarget::Unix(UdsConfig {
+                path: PathBuf::from(path),
+                mode: uds_mode,
+                cleanup: uds_cleanup,
+            }));
+        }
+        if let Some(fd) = fd {
+            if fd < 0 {
+                return Err(PyValueError::new_err("fd must be a non-negative integer"));
+            }
+            return Ok(BindTarget::Fd(fd));
+        }
+        if host.is_none()
+            && port.is_none()
+            && let Some(fd) = systemd_listen_fd()
+        {
+            return Ok(BindTarget::Fd(fd));
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = uds_cleanup;
+        if uds.is_some() || fd.is_some() {
+            return Err(PyValueError::new_err(
+                "uds and fd listeners are only supported on unix",
+            ));
+        }
+    }
+
+    let host = host
+        .or(configured_host)
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+    let port = port.or(configured_port).unwrap_or(8000);
+    Ok(BindTarget::Tcp {
+        addr: format!("{}:{}", host, port),
+        reuse_port,
+    })
+}
+
+/// Socket activation hands us descriptors starting at 3 when `LISTEN_PID`
+/// names this process. Worker processes inherit the supervisor's environment
+/// and descriptors, so they also accept a `LISTEN_PID` naming their parent.
+#[cfg(unix)]
+fn systemd_listen_fd() -> Option<RawFd> {
+    let pid: u32 = std::env::var("LISTEN_PID").ok()?.parse().ok()?;
+    let fds: u32 = std::env::var("LISTEN_FDS").ok()?.parse().ok()?;
+    let is_worker = std::env::var_os(super::workers::WORKER_ENV).is_some();
+
+    let ours =
+        pid == std::process::id() || (is_worker && pid == std::os::unix::process::parent_id());
+    (ours && fds >= 1).then_some(SD_LISTEN_FDS_START)
+}
+
+pub(crate) async fn bind_listener(target: BindTarget) -> io::Result<BoundListener> {
+    match target {
+        BindTarget::Tcp { addr, reuse_port } => {
+            bind_tcp(&addr, reuse_port).await.map(BoundListener::Tcp)
+        }
+        #[cfg(unix)]
+        BindTarget::Unix(config) => bind_unix(config),
+        #[cfg(unix)]
+        BindTarget::Fd(fd) => adopt_fd(fd),
+    }
+}
+
+/// `reuse_port` lets every worker process bind its own listener on the same
+/// address and have the kernel balance incoming connections between them.
+async fn bind_tcp(addr: &str, reuse_port: bool) -> io::Result<TcpListener> {
+    let addr = lookup_host(addr).await?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            format!("could not resolve {addr}"),
+        )
+    })?;
+    let socket = if addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+
+    #[cfg(unix)]
+    {
+        socket.set_reuseaddr(true)?;
+        socket.set_reuseport(reuse_port)?;
+    }
+    #[cfg(not(unix))]
+    let _ = reuse_port;
+
+    socket.bind(addr)?;
+    socket.listen(LISTEN_BACKLOG)
+}
+
+#[cfg(unix)]
+fn bind_unix(config: UdsConfig) -> io::Result<BoundListener> {
+    let path = config.path;
+    if config.cleanup && is_stale_socket(&path) {
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)
+        .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+    if let Some(mode) = config.mode {
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+    }
+
+    Ok(BoundListener::Unix {
+        listener,
+        cleanup: config.cleanup.then(|| UnixSocketCleanup(path)),
+    })
+}
+
+/// A socket file nobody is accepting on, typically left behind by a crash.
+#[cfg(unix)]
+fn is_stale_socket(path: &std::path::Path) -> bool {
+    std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket())
+        && std::os::unix::net::UnixStream::connect(path).is_err()
+}
+
+#[cfg(unix)]
+fn adopt_fd(fd: RawFd) -> io::Result<BoundListener> {
+    // SAFETY: the descriptor was handed to us (fd=N or socket activation) and
+    // nothing else in this process owns it.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+    if listener.local_addr().is_ok() {
+        listener.set_nonblocking(true)?;
+        return TcpListener::from_std(listener).map(BoundListener::Tcp);
+    }
+
+    // SAFETY: ownership moves straight from the TCP wrapper above.
+    let listener = unsafe { std::os::unix::net::UnixListener::from_raw_fd(listener.into_raw_fd()) };
+    listener.local_addr().map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("fd {fd} is not a listening TCP or unix socket: {err}"),
+        )
+    })?;
+    listener.set_nonblocking(true)?;
+    Ok(BoundListener::Unix {
+        listener: UnixListener::from_std(listener)?,
+        cleanup: None,
+    })
+}
@@ -0,0 +1,253 @@
+use pyo3::{exceptions::PyValueError, intern, prelude::*, types::PyDict};
+use sonic_rs::JsonValueTrait;
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{
+    EnvFilter, Layer,
+    filter::LevelFilter,
+    fmt::{FmtContext, FormatEvent, FormatFields, format::Writer},
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone)]
+pub(crate) struct LogConfig {
+    pub(crate) level: LevelFilter,
+    pub(crate) format: LogFormat,
+    pub(crate) filter: Option<String>,
+    pub(crate) to_python: bool,
+}
+
+/// `log_filter` takes `RUST_LOG` syntax (`info,fastrapi::python=debug`) and
+/// falls back to the `RUST_LOG` environment variable; `log_level` is the
+/// default for targets no directive mentions.
+pub(crate) fn parse_log_params(
+    log_level: Option<String>,
+    log_format: String,
+    log_filter: Option<String>,
+    log_to_python: bool,
+) -> PyResult<LogConfig> {
+    let level = match log_level.as_deref().map(str::to_ascii_lowercase).as_deref() {
+        None | Some("info") => LevelFilter::INFO,
+        Some("trace") => LevelFilter::TRACE,
+        Some("debug") => LevelFilter::DEBUG,
+        Some("warn" | "warning") => LevelFilter::WARN,
+        Some("error" | "critical") => LevelFilter::ERROR,
+        Some("off") => LevelFilter::OFF,
+        Some(other) => {
+            return Err(PyValueError::new_err(format!(
+                "log_level must be one of trace, debug, info, warning, error, critical or off, got '{other}'"
+            )));
+        }
+    };
+    let format = match log_format.as_str() {
+        "text" => LogFormat::Text,
+        "json" => LogFormat::Json,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "log_format must be 'text' or 'json', got '{other}'"
+            )));
+        }
+    };
+    let filter = log_filter.or_else(|| std::env::var("RUST_LOG").ok());
+    if let Some(filter) = &filter {
+        EnvFilter::builder()
+            .parse(filter)
+            .map_err(|err| PyValueError::new_err(format!("invalid log_filter: {err}")))?;
+    }
+
+    Ok(LogConfig {
+        level,
+        format,
+        filter,
+        to_python: log_to_python,
+    })
+}
+
+/// Installs the process-wide subscriber. The first server to start wins; later
+/// calls keep whatever is already installed.
+pub(crate) fn init_logging(config: &LogConfig) {
+    let filter = EnvFilter::builder()
+        .with_default_directive(config.level.into())
+        .parse_lossy(config.filter.as_deref().unwrap_or_default());
+
+    let console = !config.to_python;
+    let text_layer = (console && config.format == LogFormat::Text)
+        .then(|| tracing_subscriber::fmt::layer().with_target(false));
+    let json_layer = (console && config.format == LogFormat::Json)
+        .then(|| tracing_subscriber::fmt::layer().event_format(JsonFormat));
+    let python_layer = config.to_python.then_some(PythonLogLayer);
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(text_layer)
+        .with(json_layer)
+        .with(python_layer)
+        .try_init()
+        .ok();
+}
+
+/// An event's fields by name, with `message` kept apart.
+#[derive(Default)]
+struct EventFields {
+    message: String,
+    fields: BTreeMap<&'static str, sonic_rs::Value>,
+}
+
+impl EventFields {
+    fn from_event(event: &Event<'_>) -> Self {
+        let mut fields = Self::default();
+        event.record(&mut fields);
+        fields
+    }
+}
+
+impl Visit for EventFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        let value = format!("{value:?}");
+        if field.name() == "message" {
+            self.message = value;
+        } else {
+            self.fields.insert(field.name(), sonic_rs::json!(value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_owned();
+        } else {
+            self.fields.insert(field.name(), sonic_rs::json!(value));
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name(), sonic_rs::json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name(), sonic_rs::json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.insert(field.name(), sonic_rs::json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name(), sonic_rs::json!(value));
+    }
+}
+
+/// One JSON object per line: `timestamp`, `level`, `target`, `message` and the
+/// event's own fields under `fields`.
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let EventFields { message, fields } = EventFields::from_event(event);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs_f64())
+            .unwrap_or_default();
+        let metadata = event.metadata();
+        let line = sonic_rs::json!({
+            "timestamp": timestamp,
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "message": message,
+            "fields": sonic_rs::to_value(&fields).unwrap_or_else(|_| sonic_rs::json!({})),
+        });
+
+        writeln!(
+            writer,
+            "{}",
+            sonic_rs::to_string(&line).map_err(|_| fmt::Error)?
+        )
+    }
+}
+
+thread_local! {
+    static FORWARDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Forwards events to `logging.getLogger(target)` with `::` turned into `.`,
+/// so `fastrapi::python` tracebacks land on the `fastrapi.python` logger.
+struct PythonLogLayer;
+
+impl<S: Subscriber> Layer<S> for PythonLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // a Python handler that ends up logging through tracing again would
+        // otherwise recurse
+        if FORWARDING.with(|forwarding| forwarding.replace(true)) {
+            return;
+        }
+
+        let EventFields { message, fields } = EventFields::from_event(event);
+        let metadata = event.metadata();
+        let level = match *metadata.level() {
+            Level::TRACE => 5,
+            Level::DEBUG => 10,
+            Level::INFO => 20,
+            Level::WARN => 30,
+            _ => 40,
+        };
+        let logger_name = metadata.target().replace("::", ".");
+
+        Python::try_attach(|py| {
+            let forwarded = (|| -> PyResult<()> {
+                let logger = py
+                    .import(intern!(py, "logging"))?
+                    .call_method1(intern!(py, "getLogger"), (logger_name,))?;
+                if !logger
+                    .call_method1(intern!(py, "isEnabledFor"), (level,))?
+                    .is_truthy()?
+                {
+                    return Ok(());
+                }
+
+                let extra = PyDict::new(py);
+                let field_values = PyDict::new(py);
+                for (name, value) in &fields {
+                    match value.as_str() {
+                        Some(text) => field_values.set_item(*name, text)?,
+                        None => field_values
+                            .set_item(*name, sonic_rs::to_string(value).unwrap_or_default())?,
+                    }
+                }
+                extra.set_item(intern!(py, "fields"), field_values)?;
+                let kwargs = PyDict::new(py);
+                kwargs.set_item(intern!(py, "extra"), extra)?;
+                logger.call_method(intern!(py, "log"), (level, message), Some(&kwargs))?;
+                Ok(())
+            })();
+            if let Err(err) = forwarded {
+                eprintln!("failed to forward log record to Python: {err}");
+            }
+        });
+
+        FORWARDING.with(|forwarding| forwarding.set(false));
+    }
+}
@@ -1,10 +1,25 @@
 pub mod serve;
 pub(crate) use serve::*;
+pub mod asgi;
+pub mod conn;
 pub mod dispatch;
 pub mod files;
+pub mod handle;
+pub mod health;
 pub mod lifecycle;
+pub mod listener;
+pub mod logging;
+pub mod mounts;
 pub mod payload;
 pub mod rate_limit;
 pub mod reload;
+pub mod route_table;
 pub mod routes;
+pub mod signals;
+pub mod telemetry;
+pub mod testclient;
+pub mod threads;
+pub mod tls;
+#[cfg(unix)]
+pub mod workers;
 
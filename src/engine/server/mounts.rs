@@ -0,0 +1,571 @@
+use super::conn::TlsConnection;
+use super::serve::AppState;
+
+use crate::engine::types::AsgiMount;
+use crate::ffi::py_handlers::schedule_python_coroutine;
+use axum::{
+    Router,
+    body::Body,
+    extract::{ConnectInfo, OriginalUri, Request},
+    http::{
+        HeaderName, HeaderValue, StatusCode, Version,
+        header::{SEC_WEBSOCKET_PROTOCOL, UPGRADE},
+    },
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use fastwebsockets::{FragmentCollector, Frame, OpCode, upgrade};
+use futures_util::StreamExt;
+use percent_encoding::percent_decode_str;
+use pyo3::{
+    exceptions::PyValueError,
+    intern,
+    prelude::*,
+    types::{PyBytes, PyDict, PyList},
+};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::{
+    sync::{Mutex, mpsc},
+    task::JoinHandle,
+};
+use tower::service_fn;
+use tracing::{debug, error};
+
+type AppTask = JoinHandle<PyResult<Py<PyAny>>>;
+
+/// Messages handed to the mounted app's `receive()`.
+enum Incoming {
+    Request { body: Bytes, more_body: bool },
+    Connect,
+    Text(String),
+    Binary(Bytes),
+    Disconnect(u16),
+}
+
+/// Messages the mounted app passed to `send()`.
+enum Outgoing {
+    ResponseStart {
+        status: u16,
+        headers: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+    ResponseBody {
+        body: Bytes,
+        more_body: bool,
+    },
+    Accept {
+        subprotocol: Option<String>,
+        headers: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+    Text(String),
+    Binary(Bytes),
+    Close {
+        code: u16,
+        reason: String,
+    },
+}
+
+/// `receive` callable given to mounted apps. Once the server side is done with
+/// the connection the channel closes and every call reports a disconnect.
+#[pyclass(name = "ASGIReceive", skip_from_py_object)]
+pub struct PyAsgiReceive {
+    rx: Arc<Mutex<mpsc::Receiver<Incoming>>>,
+    websocket: bool,
+}
+
+#[pymethods]
+impl PyAsgiReceive {
+    fn __call__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let rx = self.rx.clone();
+        let websocket = self.websocket;
+
+        rsloop::rust_async::future_into_py(py, async move {
+            let incoming = rx.lock().await.recv().await;
+            Python::attach(|py| {
+                let message = PyDict::new(py);
+                match incoming {
+                    Some(Incoming::Request { body, more_body }) => {
+                        message.set_item(intern!(py, "type"), "http.request")?;
+                        message.set_item(intern!(py, "body"), PyBytes::new(py, &body))?;
+                        message.set_item(intern!(py, "more_body"), more_body)?;
+                    }
+                    Some(Incoming::Connect) => {
+                        message.set_item(intern!(py, "type"), "websocket.connect")?;
+                    }
+                    Some(Incoming::Text(text)) => {
+                        message.set_item(intern!(py, "type"), "websocket.receive")?;
+                        message.set_item(intern!(py, "text"), text)?;
+                    }
+                    Some(Incoming::Binary(bytes)) => {
+                        message.set_item(intern!(py, "type"), "websocket.receive")?;
+                        message.set_item(intern!(py, "bytes"), PyBytes::new(py, &bytes))?;
+                    }
+                    Some(Incoming::Disconnect(code)) => {
+                        message.set_item(intern!(py, "type"), "websocket.disconnect")?;
+                        message.set_item(intern!(py, "code"), code)?;
+                    }
+                    None if websocket => {
+                        message.set_item(intern!(py, "type"), "websocket.disconnect")?;
+                        message.set_item(intern!(py, "code"), 1006)?;
+                    }
+                    None => {
+                        message.set_item(intern!(py, "type"), "http.disconnect")?;
+                    }
+                }
+                Ok(message.into_any().unbind())
+            })
+        })
+    }
+}
+
+/// `send` callable given to mounted apps. Malformed messages raise in the app;
+/// messages sent after the client went away are dropped.
+#[pyclass(name = "ASGISend", skip_from_py_object)]
+pub struct PyAsgiSend {
+    tx: mpsc::Sender<Outgoing>,
+}
+
+#[pymethods]
+impl PyAsgiSend {
+    fn __call__<'py>(
+        &self,
+        py: Python<'py>,
+        message: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let outgoing = parse_outgoing(message)?;
+        let tx = self.tx.clone();
+
+        rsloop::rust_async::future_into_py(py, async move {
+            let _ = tx.send(outgoing).await;
+            Ok(())
+        })
+    }
+}
+
+fn parse_outgoing(message: &Bound<'_, PyAny>) -> PyResult<Outgoing> {
+    let py = message.py();
+    let optional = |key| message_item(message, key);
+
+    let kind: String = message.get_item(intern!(py, "type"))?.extract()?;
+    match kind.as_str() {
+        "http.response.start" => Ok(Outgoing::ResponseStart {
+            status: message.get_item(intern!(py, "status"))?.extract()?,
+            headers: message_headers(message)?,
+        }),
+        "http.response.body" => Ok(Outgoing::ResponseBody {
+            body: match optional("body") {
+                Some(body) => Bytes::from(body.extract::<Vec<u8>>()?),
+                None => Bytes::new(),
+            },
+            more_body: match optional("more_body") {
+                Some(more_body) => more_body.extract()?,
+                None => false,
+            },
+        }),
+        "websocket.accept" => Ok(Outgoing::Accept {
+            subprotocol: optional("subprotocol")
+                .map(|subprotocol| subprotocol.extract())
+                .transpose()?,
+            headers: message_headers(message)?,
+        }),
+        "websocket.send" => {
+            if let Some(text) = optional("text") {
+                return Ok(Outgoing::Text(text.extract()?));
+            }
+            match optional("bytes") {
+                Some(bytes) => Ok(Outgoing::Binary(Bytes::from(bytes.extract::<Vec<u8>>()?))),
+                None => Err(PyValueError::new_err(
+                    "websocket.send needs either 'text' or 'bytes'",
+                )),
+            }
+        }
+        "websocket.close" => Ok(Outgoing::Close {
+            code: match optional("code") {
+                Some(code) => code.extract()?,
+                None => 1000,
+            },
+            reason: match optional("reason") {
+                Some(reason) => reason.extract()?,
+                None => String::new(),
+            },
+        }),
+        other => Err(PyValueError::new_err(format!(
+            "unexpected ASGI message type: {other}"
+        ))),
+    }
+}
+
+fn message_item<'py>(message: &Bound<'py, PyAny>, key: &str) -> Option<Bound<'py, PyAny>> {
+    message.get_item(key).ok().filter(|value| !value.is_none())
+}
+
+fn message_headers(message: &Bound<'_, PyAny>) -> PyResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    match message_item(message, "headers") {
+        Some(headers) => headers
+            .try_iter()?
+            .map(|header| header?.extract())
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Nests the mounted app under its path; `/` mounts are served from the
+/// router fallback instead, since axum cannot nest at the root.
+pub(crate) fn add_asgi_mount(app: Router, mount: AsgiMount, state: AppState) -> Router {
+    let mount_path = mount.path.trim_end_matches('/').to_string();
+    let mount = Arc::new(mount);
+    let service = service_fn(move |req: Request| {
+        let mount = mount.clone();
+        let state = state.clone();
+        async move { Ok::<_, std::convert::Infallible>(call_asgi_mount(mount, state, req).await) }
+    });
+
+    app.nest_service(&mount_path, service)
+}
+
+pub(crate) async fn call_asgi_mount(
+    mount: Arc<AsgiMount>,
+    state: AppState,
+    req: Request,
+) -> Response {
+    let websocket = req
+        .headers()
+        .get(UPGRADE)
+        .is_some_and(|upgrade| upgrade.as_bytes().eq_ignore_ascii_case(b"websocket"));
+    let result = if websocket {
+        serve_websocket(&mount, &state, req).await
+    } else {
+        serve_http(&mount, &state, req).await
+    };
+
+    result.unwrap_or_else(|err| {
+        error!("Mounted app at {} failed: {}", mount.path, err);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })
+}
+
+/// Scope as Starlette's `Mount` would pass it: `path` is the full request path
+/// and `root_path` ends where the mounted app's own routes begin.
+fn build_scope<'py>(
+    py: Python<'py>,
+    mount: &AsgiMount,
+    state: &AppState,
+    req: &Request,
+    websocket: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let uri = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or(req.uri(), |OriginalUri(uri)| uri);
+    let app_root = state.root_path.trim_end_matches('/');
+    let mount_root = if mount.path == "/" {
+        ""
+    } else {
+        mount.path.as_str()
+    };
+    let path = percent_decode_str(uri.path()).decode_utf8_lossy();
+    let tls = req.extensions().get::<TlsConnection>().is_some();
+
+    let scope = PyDict::new(py);
+    let asgi = PyDict::new(py);
+    asgi.set_item(intern!(py, "version"), "3.0")?;
+    asgi.set_item(intern!(py, "spec_version"), "2.4")?;
+    scope.set_item(intern!(py, "asgi"), asgi)?;
+    scope.set_item(
+        intern!(py, "http_version"),
+        match req.version() {
+            Version::HTTP_10 => "1.0",
+            Version::HTTP_2 => "2",
+            _ => "1.1",
+        },
+    )?;
+    scope.set_item(intern!(py, "path"), format!("{app_root}{path}"))?;
+    scope.set_item(
+        intern!(py, "raw_path"),
+        PyBytes::new(py, format!("{app_root}{}", uri.path()).as_bytes()),
+    )?;
+    scope.set_item(intern!(py, "root_path"), format!("{app_root}{mount_root}"))?;
+    scope.set_item(
+        intern!(py, "query_string"),
+        PyBytes::new(py, uri.query().unwrap_or_default().as_bytes()),
+    )?;
+
+    let headers = PyList::empty(py);
+    for (name, value) in req.headers() {
+        headers.append((
+            PyBytes::new(py, name.as_str().as_bytes()),
+            PyBytes::new(py, value.as_bytes()),
+        ))?;
+    }
+    scope.set_item(intern!(py, "headers"), headers)?;
+
+    let client = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| (addr.ip().to_string(), addr.port()));
+    scope.set_item(intern!(py, "client"), client)?;
+    scope.set_item(intern!(py, "server"), py.None())?;
+
+    if websocket {
+        scope.set_item(intern!(py, "type"), "websocket")?;
+        scope.set_item(intern!(py, "scheme"), if tls { "wss" } else { "ws" })?;
+        let subprotocols: Vec<String> = req
+            .headers()
+            .get_all(SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|protocol| protocol.trim().to_string())
+            .filter(|protocol| !protocol.is_empty())
+            .collect();
+        scope.set_item(intern!(py, "subprotocols"), subprotocols)?;
+    } else {
+        scope.set_item(intern!(py, "type"), "http")?;
+        scope.set_item(intern!(py, "scheme"), if tls { "https" } else { "http" })?;
+        scope.set_item(intern!(py, "method"), req.method().as_str())?;
+    }
+
+    Ok(scope)
+}
+
+/// Starts `app(scope, receive, send)` on the app's event loop.
+fn start_app(
+    mount: &AsgiMount,
+    state: &AppState,
+    req: &Request,
+    websocket: bool,
+    receive: mpsc::Receiver<Incoming>,
+    send: mpsc::Sender<Outgoing>,
+) -> Result<AppTask, String> {
+    let app_future = Python::attach(|py| {
+        let scope = build_scope(py, mount, state, req, websocket)?;
+        let receive = PyAsgiReceive {
+            rx: Arc::new(Mutex::new(receive)),
+            websocket,
+        };
+        let send = PyAsgiSend { tx: send };
+        let coroutine = mount.app.bind(py).call1((scope, receive, send))?;
+        schedule_python_coroutine(py, &state.async_loop, coroutine)
+    })
+    .map_err(|err| err.to_string())?;
+
+    Ok(tokio::spawn(app_future))
+}
+
+fn app_failure(result: Result<PyResult<Py<PyAny>>, tokio::task::JoinError>) -> Option<String> {
+    match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(err) => Some(err.to_string()),
+    }
+}
+
+fn insert_headers(response: &mut Response, headers: Vec<(Vec<u8>, Vec<u8>)>) {
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(&name),
+            HeaderValue::from_bytes(&value),
+        ) {
+            response.headers_mut().append(name, value);
+        }
+    }
+}
+
+async fn serve_http(mount: &AsgiMount, state: &AppState, req: Request) -> Result<Response, String> {
+    let (receive_tx, receive_rx) = mpsc::channel(4);
+    let (send_tx, mut send_rx) = mpsc::channel(16);
+    let mut app_task = start_app(mount, state, &req, false, receive_rx, send_tx)?;
+
+    // the body is forwarded as the app reads it; the clone kept by the
+    // response stream holds off http.disconnect until the response is done
+    let body_tx = receive_tx.clone();
+    let mut body = req.into_body().into_data_stream();
+    tokio::spawn(async move {
+        loop {
+            let message = match body.next().await {
+                Some(Ok(chunk)) => Incoming::Request {
+                    body: chunk,
+                    more_body: true,
+                },
+                Some(Err(_)) => break,
+                None => Incoming::Request {
+                    body: Bytes::new(),
+                    more_body: false,
+                },
+            };
+            let last = matches!(
+                message,
+                Incoming::Request {
+                    more_body: false,
+                    ..
+                }
+            );
+            if body_tx.send(message).await.is_err() || last {
+                break;
+            }
+        }
+    });
+
+    let (status, headers) = tokio::select! {
+        biased;
+        message = send_rx.recv() => match message {
+            Some(Outgoing::ResponseStart { status, headers }) => (status, headers),
+            Some(_) => return Err("expected http.response.start".to_string()),
+            None => return Err("app returned without sending a response".to_string()),
+        },
+        result = &mut app_task => {
+            return Err(app_failure(result)
+                .unwrap_or_else(|| "app returned without sending a response".to_string()));
+        }
+    };
+
+    let body = async_stream::stream! {
+        let _receive_tx = receive_tx;
+        loop {
+            tokio::select! {
+                biased;
+                message = send_rx.recv() => match message {
+                    Some(Outgoing::ResponseBody { body, more_body }) => {
+                        if !body.is_empty() {
+                            yield Ok(body);
+                        }
+                        if !more_body {
+                            break;
+                        }
+                    }
+                    Some(_) => {}
+                    None => break,
+                },
+                result = &mut app_task => {
+                    // a failure after the headers went out can only abort the body
+                    if let Some(err) = app_failure(result) {
+                        yield Err(std::io::Error::other(err));
+                    }
+                    break;
+                }
+            }
+        }
+    };
+
+    let mut response = Response::new(Body::from_stream(body));
+    *response.status_mut() = StatusCode::from_u16(status).map_err(|err| err.to_string())?;
+    insert_headers(&mut response, headers);
+    Ok(response)
+}
+
+async fn serve_websocket(
+    mount: &AsgiMount,
+    state: &AppState,
+    mut req: Request,
+) -> Result<Response, String> {
+    let (receive_tx, receive_rx) = mpsc::channel(16);
+    let (send_tx, mut send_rx) = mpsc::channel(16);
+    let mut app_task = start_app(mount, state, &req, true, receive_rx, send_tx)?;
+    let (response, upgrade) = upgrade::upgrade(&mut req).map_err(|err| err.to_string())?;
+    let _ = receive_tx.send(Incoming::Connect).await;
+
+    // the handshake only completes once the app accepts
+    let (subprotocol, headers) = tokio::select! {
+        biased;
+        message = send_rx.recv() => match message {
+            Some(Outgoing::Accept { subprotocol, headers }) => (subprotocol, headers),
+            _ => return Ok(StatusCode::FORBIDDEN.into_response()),
+        },
+        result = &mut app_task => {
+            if let Some(err) = app_failure(result) {
+                error!("Mounted app at {} failed: {}", mount.path, err);
+            }
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+    };
+
+    let mut response = response.map(Body::new);
+    if let Some(subprotocol) = subprotocol
+        && let Ok(value) = HeaderValue::from_str(&subprotocol)
+    {
+        response.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, value);
+    }
+    insert_headers(&mut response, headers);
+
+    tokio::spawn(async move {
+        let mut ws = match upgrade.await {
+            Ok(ws) => FragmentCollector::new(ws),
+            Err(err) => {
+                debug!("Websocket upgrade for mounted app failed: {}", err);
+                return;
+            }
+        };
+        relay_websocket(&mut ws, receive_tx, send_rx, app_task).await;
+    });
+
+    Ok(response)
+}
+
+async fn relay_websocket(
+    ws: &mut FragmentCollector<hyper_util::rt::TokioIo<hyper::upgrade::Upgraded>>,
+    receive_tx: mpsc::Sender<Incoming>,
+    mut send_rx: mpsc::Receiver<Outgoing>,
+    mut app_task: AppTask,
+) {
+    loop {
+        tokio::select! {
+            biased;
+            message = send_rx.recv() => {
+                let written = match message {
+                    Some(Outgoing::Text(text)) => {
+                        ws.write_frame(Frame::text(text.into_bytes().into())).await
+                    }
+                    Some(Outgoing::Binary(bytes)) => {
+                        ws.write_frame(Frame::binary(bytes.to_vec().into())).await
+                    }
+                    Some(Outgoing::Close { code, reason }) => {
+                        let _ = ws.write_frame(Frame::close(code, reason.as_bytes())).await;
+                        break;
+                    }
+                    Some(_) => Ok(()),
+                    None => break,
+                };
+                if let Err(err) = written {
+                    debug!("Websocket write to client failed: {}", err);
+                    let _ = receive_tx.send(Incoming::Disconnect(1006)).await;
+                    break;
+                }
+            }
+
+            frame = ws.read_frame() => {
+                let message = match frame {
+                    Ok(frame) => match frame.opcode {
+                        OpCode::Text => {
+                            Incoming::Text(String::from_utf8_lossy(&frame.payload).into_owned())
+                        }
+                        OpCode::Binary => Incoming::Binary(Bytes::from(frame.payload.to_vec())),
+                        OpCode::Close => {
+                            let code = match frame.payload.get(..2) {
+                                Some(code) => u16::from_be_bytes([code[0], code[1]]),
+                                None => 1005,
+                            };
+                            let _ = receive_tx.send(Incoming::Disconnect(code)).await;
+                            break;
+                        }
+                        _ => continue,
+                    },
+                    Err(err) => {
+                        debug!("Websocket read from client failed: {}", err);
+                        let _ = receive_tx.send(Incoming::Disconnect(1006)).await;
+                        break;
+                    }
+                };
+                if receive_tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+
+            result = &mut app_task => {
+                if let Some(err) = app_failure(result) {
+                    error!("Mounted websocket app failed: {}", err);
+                }
+                let _ = ws.write_frame(Frame::close(1000, b"")).await;
+                break;
+            }
+        }
+    }
+}
@@ -1,19 +1,28 @@
 use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
 use notify::{Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher};
 use std::{
+    collections::BTreeSet,
     path::{Path, PathBuf},
     process::{Child, Command, Stdio},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
+// on top of graceful_timeout, for the child's shutdown phase to run
+const CHILD_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+const CHILD_EXIT_POLL: Duration = Duration::from_millis(50);
+// changed files listed by name before the summary falls back to a count
+const RELOAD_SUMMARY_LIMIT: usize = 5;
+
 #[derive(Clone)]
 pub(crate) struct ReloadConfig {
     pub(crate) watch_dirs: Vec<PathBuf>,
+    pub(crate) include_patterns: Vec<String>,
     pub(crate) ignore_dirs: Vec<PathBuf>,
     pub(crate) ignore_patterns: Vec<String>,
     pub(crate) ignore_paths: Vec<PathBuf>,
     pub(crate) tick_ms: u64,
     pub(crate) ignore_worker_failure: bool,
+    pub(crate) graceful_timeout: Duration,
 }
 
 pub(crate) fn run_reload_supervisor(
@@ -21,6 +30,7 @@ pub(crate) fn run_reload_supervisor(
     argv: &[String],
     config: ReloadConfig,
 ) -> Result<(), String> {
+    let include_globs = build_reload_include_globs(&config)?;
     let ignore_globs = build_reload_ignore_globs(&config)?;
     let (tx, rx) = std::sync::mpsc::channel();
     let mut watcher = RecommendedWatcher::new(
@@ -52,12 +62,36 @@ pub(crate) fn run_reload_supervisor(
 
         match rx.recv_timeout(debounce) {
             Ok(Ok(event)) => {
-                if !reload_event_matches(&event, &config, &ignore_globs) {
+                let mut changed = BTreeSet::new();
+                changed.extend(reload_event_paths(
+                    &event,
+                    &config,
+                    &include_globs,
+                    &ignore_globs,
+                ));
+                if changed.is_empty() {
                     continue;
                 }
+                // editors often write several files at once; restart once for all of them
+                let settle_until = Instant::now() + debounce;
+                while let Some(wait) = settle_until.checked_duration_since(Instant::now()) {
+                    match rx.recv_timeout(wait) {
+                        Ok(Ok(event)) => changed.extend(reload_event_paths(
+                            &event,
+                            &config,
+                            &include_globs,
+                            &ignore_globs,
+                        )),
+                        Ok(Err(err)) => return Err(err.to_string()),
+                        Err(_) => break,
+                    }
+                }
                 println!("");
-                println!("Python file change detected, restarting server...");
-                stop_child(&mut child);
+                println!(
+                    "FastrAPI reload: {}; restarting server...",
+                    reload_summary(&changed, &config.watch_dirs)
+                );
+                stop_child_gracefully(&mut child, config.graceful_timeout + CHILD_SHUTDOWN_GRACE);
                 child = spawn_reload_child(executable, argv).map_err(|err| err.to_string())?;
                 while rx.try_recv().is_ok() {}
             }
@@ -70,10 +104,18 @@ pub(crate) fn run_reload_supervisor(
     }
 }
 pub(crate) fn spawn_reload_child(executable: &str, argv: &[String]) -> std::io::Result<Child> {
+    spawn_child(executable, argv, &[("FASTRAPI_RELOAD_CHILD", "1")])
+}
+
+pub(crate) fn spawn_child(
+    executable: &str,
+    argv: &[String],
+    envs: &[(&str, &str)],
+) -> std::io::Result<Child> {
     let mut command = Command::new(executable);
     command
         .args(argv)
-        .env("FASTRAPI_RELOAD_CHILD", "1")
+        .envs(envs.iter().copied())
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit());
@@ -88,15 +130,75 @@ pub(crate) fn stop_child(child: &mut Child) {
     let _ = child.wait();
 }
 
-pub(crate) fn reload_event_matches(
-    event: &notify::Event,
-    config: &ReloadConfig,
-    ignore_globs: &Option<GlobSet>,
-) -> bool {
-    event.paths.iter().any(|path| {
-        path.extension().and_then(|ext| ext.to_str()) == Some("py")
-            && !is_reload_ignored(path, config, ignore_globs)
-    })
+/// Sends SIGTERM so the child runs its shutdown phase and releases the port,
+/// and only kills it if it is still running after `grace`.
+pub(crate) fn stop_child_gracefully(child: &mut Child, grace: Duration) {
+    if child.try_wait().ok().flatten().is_some() {
+        return;
+    }
+
+    #[cfg(unix)]
+    {
+        // SAFETY: kill(2) has no memory-safety requirements; the pid belongs to a
+        // child we have not reaped yet, so it cannot have been recycled.
+        unsafe {
+            libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+        }
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            if !matches!(child.try_wait(), Ok(None)) {
+                return;
+            }
+            std::thread::sleep(CHILD_EXIT_POLL);
+        }
+        eprintln!("FastrAPI reload: child did not exit within {grace:?}; killing it");
+    }
+    #[cfg(not(unix))]
+    let _ = grace;
+
+    stop_child(child);
+}
+
+/// The changed files in `event` that should trigger a restart.
+pub(crate) fn reload_event_paths<'a>(
+    event: &'a notify::Event,
+    config: &'a ReloadConfig,
+    include_globs: &'a GlobSet,
+    ignore_globs: &'a Option<GlobSet>,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    event
+        .paths
+        .iter()
+        .filter(|path| {
+            include_globs.is_match(path) && !is_reload_ignored(path, config, ignore_globs)
+        })
+        .cloned()
+}
+
+fn reload_summary(changed: &BTreeSet<PathBuf>, watch_dirs: &[PathBuf]) -> String {
+    let names: Vec<String> = changed
+        .iter()
+        .take(RELOAD_SUMMARY_LIMIT)
+        .map(|path| {
+            watch_dirs
+                .iter()
+                .find_map(|dir| path.strip_prefix(dir).ok())
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        })
+        .collect();
+    let more = changed.len().saturating_sub(RELOAD_SUMMARY_LIMIT);
+    let noun = if changed.len() == 1 { "file" } else { "files" };
+    if more > 0 {
+        format!(
+            "{} {noun} changed ({} and {more} more)",
+            changed.len(),
+            names.join(", ")
+        )
+    } else {
+        format!("{} {noun} changed ({})", changed.len(), names.join(", "))
+    }
 }
 
 pub(crate) fn is_reload_ignored(
@@ -140,19 +242,26 @@ pub(crate) fn is_default_reload_ignored_dir(path: &Path) -> bool {
         .is_some_and(|name| matches!(name, ".git" | ".venv" | "__pycache__" | "target"))
 }
 
+/// Python sources always trigger a restart; `include_patterns` adds to them.
+pub(crate) fn build_reload_include_globs(config: &ReloadConfig) -> Result<GlobSet, String> {
+    let patterns =
+        std::iter::once("*.py").chain(config.include_patterns.iter().map(String::as_str));
+    build_reload_globs(patterns)
+}
+
 pub(crate) fn build_reload_ignore_globs(config: &ReloadConfig) -> Result<Option<GlobSet>, String> {
     if config.ignore_patterns.is_empty() {
         return Ok(None);
     }
 
+    build_reload_globs(config.ignore_patterns.iter().map(String::as_str)).map(Some)
+}
+
+fn build_reload_globs<'a>(patterns: impl Iterator<Item = &'a str>) -> Result<GlobSet, String> {
     let mut builder = GlobSetBuilder::new();
-    for pattern in config
-        .ignore_patterns
-        .iter()
-        .filter(|pattern| !pattern.is_empty())
-    {
+    for pattern in patterns.filter(|pattern| !pattern.is_empty()) {
         let pattern = if pattern.contains(['*', '?', '[', ']']) {
-            pattern.clone()
+            pattern.to_string()
         } else {
             format!("**/*{pattern}*")
         };
@@ -163,7 +272,7 @@ pub(crate) fn build_reload_ignore_globs(config: &ReloadConfig) -> Result<Option<
         builder.add(glob);
     }
 
-    builder.build().map(Some).map_err(|err| err.to_string())
+    builder.build().map_err(|err| err.to_string())
 }
 
 pub(crate) fn resolve_reload_dirs(
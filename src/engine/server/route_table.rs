@@ -0,0 +1,222 @@
+use super::dispatch::*;
+use super::files::*;
+use super::mounts::*;
+use super::serve::AppState;
+
+use crate::{
+    engine::types::{AsgiMount, FastrAPI, FrontendMount},
+    routing::{
+        host::HostPattern,
+        router::{FrozenRouter, FrozenRouterBuilder},
+        types::{RouteEntry, WebSocketEntry},
+    },
+    utils::openapi::build_openapi_spec,
+};
+use arc_swap::ArcSwap;
+use axum::{
+    extract::Request,
+    http::{Method, StatusCode},
+    response::{IntoResponse, Response},
+};
+use pyo3::prelude::*;
+use smallvec::SmallVec;
+use sonic_rs::Value as JsonValue;
+use std::sync::{
+    Arc, Weak,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+};
+
+pub(crate) type FlatRoutes = Arc<(Vec<RouteEntry>, Vec<WebSocketEntry>)>;
+
+/// The routes dispatched through the fallback and the OpenAPI schema
+/// describing them, replaced as a whole when routes change while serving.
+/// Routes included under a host get a router per host pattern, tried in the
+/// order they were included before `router`.
+pub(crate) struct RouteTable {
+    pub(crate) router: Arc<FrozenRouter>,
+    pub(crate) host_routers: Vec<(Arc<HostPattern>, Arc<FrozenRouter>)>,
+    pub(crate) openapi_json: Arc<JsonValue>,
+    version: u64,
+}
+
+pub(crate) fn build_route_table(py: Python<'_>, app_config: &FastrAPI) -> (RouteTable, FlatRoutes) {
+    // read first, so a route registered while building forces another rebuild
+    let version = app_config.live_routes.version();
+    let flat = app_config
+        .router
+        .bind(py)
+        .borrow()
+        .flatten(py, &app_config.live_routes);
+
+    let mut builder = FrozenRouterBuilder::new();
+    let mut host_builders: Vec<(Arc<HostPattern>, FrozenRouterBuilder)> = Vec::new();
+    flat.0.iter().for_each(|route| {
+        let builder = match &route.host {
+            None => &mut builder,
+            Some(host) => {
+                let index = host_builders
+                    .iter()
+                    .position(|(existing, _)| existing.source == host.source)
+                    .unwrap_or_else(|| {
+                        host_builders.push((host.clone(), FrozenRouterBuilder::new()));
+                        host_builders.len() - 1
+                    });
+                &mut host_builders[index].1
+            }
+        };
+        builder.add_route(&route.method, route.path.clone(), route.handler.clone());
+    });
+    let table = RouteTable {
+        router: Arc::new(builder.build()),
+        host_routers: host_builders
+            .into_iter()
+            .map(|(host, builder)| (host, Arc::new(builder.build())))
+            .collect(),
+        openapi_json: Arc::new(build_openapi_spec(py, app_config)),
+        version,
+    };
+    (table, flat)
+}
+
+/// Every route table currently serving an app, so `reload_routes` reaches the
+/// server, background servers and test clients alike. `version` is bumped
+/// whenever a router the app serves gains a route.
+#[derive(Default)]
+pub(crate) struct LiveRouteTables {
+    tables: parking_lot::Mutex<Vec<Weak<LiveTable>>>,
+    version: AtomicU64,
+}
+
+impl LiveRouteTables {
+    fn register(&self, table: &Arc<LiveTable>) {
+        let mut tables = self.tables.lock();
+        tables.retain(|table| table.strong_count() > 0);
+        tables.push(Arc::downgrade(table));
+    }
+
+    pub(crate) fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn swap(&self, table: RouteTable) {
+        let table = Arc::new(table);
+        for live in self.tables.lock().iter().filter_map(Weak::upgrade) {
+            live.table.store(table.clone());
+        }
+    }
+
+    /// Rebuilds right away on the thread that changed the routes, so requests
+    /// keep using the old table until the new one is swapped in.
+    pub(crate) fn routes_changed(&self, py: Python<'_>) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+        let live = self.tables.lock().iter().find_map(Weak::upgrade);
+        if let Some(live) = live {
+            live.rebuild(py);
+        }
+    }
+}
+
+/// A route table in use by one server or test client of `app`.
+struct LiveTable {
+    app: Py<FastrAPI>,
+    routes: Arc<LiveRouteTables>,
+    table: ArcSwap<RouteTable>,
+    rebuilding: AtomicBool,
+}
+
+impl LiveTable {
+    fn rebuild(&self, py: Python<'_>) {
+        // the app is only borrowed mutably while Python sets an attribute;
+        // the next request retries then
+        if let Ok(app_config) = self.app.bind(py).try_borrow() {
+            let (table, _) = build_route_table(py, &app_config);
+            self.routes.swap(table);
+        }
+    }
+}
+
+/// Serves everything axum's own routes do not: the swappable route table,
+/// then a root ASGI mount, then frontend mounts.
+#[derive(Clone)]
+pub(crate) struct DynamicRoutes {
+    live: Arc<LiveTable>,
+    state: AppState,
+    frontend_mounts: Arc<Vec<FrontendMount>>,
+    root_asgi_mount: Option<Arc<AsgiMount>>,
+}
+
+impl DynamicRoutes {
+    pub(crate) fn new(
+        app: Py<FastrAPI>,
+        app_config: &FastrAPI,
+        table: Arc<RouteTable>,
+        state: AppState,
+    ) -> Self {
+        let live = Arc::new(LiveTable {
+            app,
+            routes: app_config.live_routes.clone(),
+            table: ArcSwap::new(table),
+            rebuilding: AtomicBool::new(false),
+        });
+        app_config.live_routes.register(&live);
+        Self {
+            live,
+            state,
+            frontend_mounts: Arc::new(app_config.frontend_mounts.clone()),
+            root_asgi_mount: app_config
+                .asgi_mounts
+                .iter()
+                .find(|mount| mount.path == "/")
+                .cloned()
+                .map(Arc::new),
+        }
+    }
+
+    /// The table in use. Changes are normally rebuilt by whoever made them;
+    /// one that could not be is rebuilt on a blocking thread meanwhile.
+    pub(crate) fn current(&self) -> Arc<RouteTable> {
+        let table = self.live.table.load_full();
+        if table.version != self.live.routes.version()
+            && !self.live.rebuilding.swap(true, Ordering::AcqRel)
+        {
+            let live = self.live.clone();
+            tokio::task::spawn_blocking(move || {
+                Python::attach(|py| live.rebuild(py));
+                live.rebuilding.store(false, Ordering::Release);
+            });
+        }
+        table
+    }
+
+    /// Whether `built` is still the table in use; routes axum serves directly
+    /// were derived from it and go stale once it is replaced.
+    #[inline(always)]
+    pub(crate) fn is_current(&self, built: &Arc<RouteTable>) -> bool {
+        Arc::ptr_eq(&self.live.table.load(), built) && built.version == self.live.routes.version()
+    }
+
+    pub(crate) async fn handle(self, req: Request) -> Response {
+        let table = self.current();
+        if let Some((router, host_params)) = select_router(&table, &self.state, &req) {
+            return dispatch(router, host_params, self.state, req).await;
+        }
+
+        // a mount that takes the request wins over a route registered only
+        // under other methods, so a path can move over one method at a time
+        let frontend = frontend_match(&self.frontend_mounts, req.uri().path()).is_some();
+        if let Some(mount) = self.root_asgi_mount
+            && !frontend
+        {
+            return call_asgi_mount(mount, self.state, req).await;
+        }
+        // frontend mounts only serve GET and HEAD
+        let frontend = frontend && matches!(*req.method(), Method::GET | Method::HEAD);
+        if !frontend && let Some(router) = select_router_for_path(&table, &self.state, &req) {
+            return dispatch(router, SmallVec::new(), self.state, req).await;
+        }
+
+        serve_frontend_mounts(self.frontend_mounts, req)
+            .await
+            .unwrap_or_else(|| StatusCode::NOT_FOUND.into_response())
+    }
+}
@@ -1,7 +1,13 @@
-use super::dispatch::*;
+use super::conn::TlsConnection;
+use super::dispatch::request_host;
 use super::files::*;
+use super::health::add_health_routes;
 use super::lifecycle::*;
+use super::mounts::*;
+use super::route_table::*;
 use super::serve::*;
+use super::telemetry::*;
+use super::threads::acquire_sync_permit;
 
 use crate::engine::types::FastrAPI;
 use ahash::{AHashMap, AHashSet};
@@ -9,11 +15,12 @@ use axum::{
     Json, Router,
     body::{Body, to_bytes},
     extract::Request,
-    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header::CONTENT_TYPE},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, Uri, header::CONTENT_TYPE},
     middleware::{self as axum_middleware, Next},
     response::{Html, IntoResponse, Response},
     routing::{MethodRouter, *},
 };
+use fastwebsockets::upgrade::IncomingUpgrade;
 use pyo3::prelude::*;
 use std::time::Instant;
 use std::{sync::Arc, time::Duration};
@@ -27,7 +34,7 @@ use tower_http::{
     trace::TraceLayer,
 };
 use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer, cookie::Key};
-use tracing::info;
+use tracing::{error, info};
 
 use crate::{
     ffi::py_handlers::{
@@ -37,18 +44,19 @@ use crate::{
     globals::{MIDDLEWARES, PYTHON_RUNTIME},
     http::{
         middleware::{
-            CORSMiddleware, GZipMiddleware, HTTPSRedirectMiddleware, SessionMiddleware,
-            TrustedHostMiddleware, build_cors_layer, parse_cors_params, parse_gzip_params,
+            AccessLogMiddleware, AccessLogger, CORSMiddleware, GZipMiddleware,
+            HTTPSRedirectMiddleware, SessionMiddleware, TrustedHostMiddleware, access_log,
+            build_cors_layer, parse_access_log_params, parse_cors_params, parse_gzip_params,
             parse_https_redirect_params, parse_session_params, parse_trusted_host_params,
         },
         websocket::ws_handler,
     },
     routing::{
+        host::HostPattern,
         prometheus::prometheus_handle,
-        router::FrozenRouterBuilder,
-        types::{HttpMethod, RouteHandler},
+        types::{HttpMethod, RouteHandler, RouteMethod},
     },
-    utils::{local_guard, openapi::build_openapi_spec, py_any_to_json},
+    utils::{local_guard, py_any_to_json},
 };
 
 macro_rules! match_method_router {
@@ -67,9 +75,10 @@ macro_rules! match_method_router {
 
 pub(crate) fn build_router(
     py: Python,
+    py_app: Py<FastrAPI>,
     app_state: AppState,
     docs_url: Option<String>,
-    openapi_url: String,
+    openapi_url: Option<String>,
     app_config: &FastrAPI,
 ) -> Router {
     let mut app = Router::new();
@@ -79,6 +88,7 @@ pub(crate) fn build_router(
     let mut cors_config = app_config.cors_config.clone();
     let mut trusted_host_config = app_config.trusted_host_config.clone();
     let mut https_redirect_config = app_config.https_redirect_config.clone();
+    let mut access_log_config = app_config.access_log_config.clone();
 
     merge_declared_middlewares(
         py,
@@ -88,91 +98,138 @@ pub(crate) fn build_router(
         &mut https_redirect_config,
         &mut gzip_config,
         &mut session_config,
+        &mut access_log_config,
     );
 
-    let base_router = app_config.router.bind(py);
-    let base_ref = base_router.borrow();
-    base_ref.freeze(py);
-    let flat = base_ref.flatten(py);
-
-    let mut frozen_router_builder = FrozenRouterBuilder::new();
-    flat.0.iter().for_each(|route| {
-        frozen_router_builder.add_route(route.method, route.path.clone(), route.handler.clone());
-    });
-
-    let frozen_router = Arc::new(frozen_router_builder.build());
-    let frontend_mounts = Arc::new(app_config.frontend_mounts.clone());
+    // dispatch only reports the matched route template when something reads it
+    let app_state = AppState {
+        track_routes: access_log_config.is_some(),
+        ..app_state
+    };
 
-    let mut cached_routes: AHashMap<String, MethodRouter> = AHashMap::new();
+    let (table, flat) = build_route_table(py, app_config);
+    let table = Arc::new(table);
+    let dynamic = DynamicRoutes::new(py_app, app_config, Arc::clone(&table), app_state.clone());
+
+    // cached and no-request routes skip the fallback, so each checks that the
+    // table it was derived from has not been swapped out since; they would
+    // also skip host routing, so a path a host router serves is dispatched
+    let under_host = |path: &str| {
+        table
+            .host_routers
+            .iter()
+            .any(|(_, router)| router.has_path(path))
+    };
+    let mut direct_routes: AHashMap<String, MethodRouter> = AHashMap::new();
+    // axum answers HEAD with a direct GET route unless HEAD is routed too
+    let mut pending_heads: AHashSet<&str> = flat
+        .0
+        .iter()
+        .filter(|route| route.method == RouteMethod::Standard(HttpMethod::HEAD))
+        .map(|route| route.path.as_str())
+        .collect();
     flat.0
         .iter()
         .filter(|route| {
-            route.handler.cache_response
+            route.host.is_none()
+                && route.handler.cache_response
                 && !route.path.contains('{')
                 && matches!(route.handler.execution_mode, ExecutionMode::SyncNoArgs)
+                && !under_host(&route.path)
         })
         .filter_map(|route| {
+            let method = route.method.standard()?;
             precompute_const_response(py, &route.handler)
-                .map(|cached| (route.path.clone(), route.method, cached))
+                .map(|cached| (route.path.clone(), method, cached))
         })
         .for_each(|(path, method, cached)| {
+            if method == HttpMethod::HEAD {
+                pending_heads.remove(path.as_str());
+            }
             let method_router = cached_method_router(method, cached);
-            cached_routes
+            direct_routes
                 .entry(path)
                 .and_modify(|existing| *existing = existing.clone().merge(method_router.clone()))
                 .or_insert(method_router);
         });
 
-    app = cached_routes
-        .into_iter()
-        .fold(app, |current_app, (path, method_router)| {
-            current_app.route(&path, method_router)
-        });
-
-    let mut direct_no_request_routes: AHashMap<String, MethodRouter> = AHashMap::new();
     flat.0
         .iter()
         .filter(|route| {
-            !route.handler.cache_response
+            route.host.is_none()
+                && !route.handler.cache_response
                 && !route.path.contains('{')
                 && matches!(
                     route.handler.execution_mode,
                     ExecutionMode::SyncNoArgs | ExecutionMode::AsyncNoArgs
                 )
+                && !under_host(&route.path)
         })
-        .for_each(|route| {
+        .filter_map(|route| Some((route, route.method.standard()?)))
+        .for_each(|(route, method)| {
+            if method == HttpMethod::HEAD {
+                pending_heads.remove(route.path.as_str());
+            }
             let method_router =
-                no_request_method_router(route.method, route.handler.clone(), app_state.clone());
-            direct_no_request_routes
+                no_request_method_router(method, route.handler.clone(), app_state.clone());
+            direct_routes
                 .entry(route.path.clone())
                 .and_modify(|existing| *existing = existing.clone().merge(method_router.clone()))
                 .or_insert(method_router);
         });
 
-    app = direct_no_request_routes
+    app = direct_routes
         .into_iter()
         .fold(app, |current_app, (path, method_router)| {
-            current_app.route(&path, method_router)
+            let method_router = if pending_heads.contains(path.as_str()) {
+                let dynamic = dynamic.clone();
+                method_router.head(move |req: Request| dynamic.handle(req))
+            } else {
+                method_router
+            };
+            current_app.route(&path, guard_direct_route(method_router, &dynamic, &table))
         });
 
-    app = flat.1.iter().fold(app, |current_app, ws| {
-        let path = ws.path.clone();
-        let handler = Arc::new(ws.handler.clone_ref(py));
-        let rt_handle = app_state.rt_handle.clone();
-        let async_loop = app_state.async_loop.clone();
-
-        current_app.route(
-            &path,
-            axum::routing::get(move |ws_upgrade| {
-                ws_handler(
-                    ws_upgrade,
-                    axum::Extension(handler.clone()),
-                    axum::Extension(rt_handle.clone()),
-                    axum::Extension(async_loop.clone()),
-                )
-            }),
-        )
-    });
+    // a path may have a websocket route per host, so axum gets one route per
+    // path that picks the handler by host
+    let mut ws_routes: AHashMap<&str, Vec<WebSocketTarget>> = AHashMap::new();
+    for ws in &flat.1 {
+        ws_routes
+            .entry(ws.path.as_str())
+            .or_default()
+            .push((ws.host.clone(), Arc::new(ws.handler.clone_ref(py))));
+    }
+    app = ws_routes
+        .into_iter()
+        .fold(app, |current_app, (path, targets)| {
+            let targets = Arc::new(targets);
+            let rt_handle = app_state.rt_handle.clone();
+            let async_loop = app_state.async_loop.clone();
+
+            current_app.route(
+                path,
+                axum::routing::get(
+                    move |headers: HeaderMap, uri: Uri, ws_upgrade: IncomingUpgrade| {
+                        let handler = select_ws_handler(&targets, request_host(&headers, &uri));
+                        let rt_handle = rt_handle.clone();
+                        let async_loop = async_loop.clone();
+                        async move {
+                            let Some(handler) = handler else {
+                                return StatusCode::NOT_FOUND.into_response();
+                            };
+                            ws_handler(
+                                ws_upgrade,
+                                axum::Extension(handler),
+                                axum::Extension(rt_handle),
+                                axum::Extension(async_loop),
+                            )
+                            .await
+                            .into_response()
+                        }
+                    },
+                ),
+            )
+        });
 
     app = app_config
         .static_mounts
@@ -182,18 +239,27 @@ pub(crate) fn build_router(
             add_static_mount(current_app, mount)
         });
 
-    let openapi_json = Arc::new(build_openapi_spec(py, app_config));
+    app = app_config
+        .asgi_mounts
+        .iter()
+        .filter(|mount| mount.path != "/")
+        .cloned()
+        .fold(app, |current_app, mount| {
+            add_asgi_mount(current_app, mount, app_state.clone())
+        });
 
-    app = app.route(
-        &openapi_url,
-        get({
-            let json = openapi_json.clone();
-            move || {
-                let json = json.clone();
-                async move { Json(json.as_ref().clone()) }
-            }
-        }),
-    );
+    if let Some(openapi) = openapi_url {
+        app = app.route(
+            &openapi,
+            get({
+                let dynamic = dynamic.clone();
+                move || {
+                    let dynamic = dynamic.clone();
+                    async move { Json(dynamic.current().openapi_json.as_ref().clone()) }
+                }
+            }),
+        );
+    }
     if let Some(docs) = docs_url {
         let mut swagger_html = include_str!("../../../static/swagger-ui.html").to_string();
         if let Some(params) = &app_config.swagger_ui_parameters {
@@ -244,20 +310,7 @@ pub(crate) fn build_router(
         );
     }
 
-    app = app.fallback({
-        let router = frozen_router.clone();
-        let state = app_state.clone();
-        let frontend_mounts = frontend_mounts.clone();
-        axum::routing::any(move |req: Request| async move {
-            if request_matches_router(&router, &state, &req) {
-                return dispatch(router, state, req).await;
-            }
-
-            serve_frontend_mounts(frontend_mounts, req)
-                .await
-                .unwrap_or_else(|| StatusCode::NOT_FOUND.into_response())
-        })
-    });
+    app = app.fallback(axum::routing::any(move |req: Request| dynamic.handle(req)));
 
     // L1: Sessions
     if let Some(config) = session_config {
@@ -321,7 +374,7 @@ pub(crate) fn build_router(
                 let uri = req.uri().clone();
                 let headers = req.headers().clone();
 
-                let mut is_https = false;
+                let mut is_https = req.extensions().get::<TlsConnection>().is_some();
                 if let Some(scheme) = uri.scheme() {
                     if scheme == &axum::http::uri::Scheme::HTTPS {
                         is_https = true;
@@ -438,11 +491,45 @@ pub(crate) fn build_router(
         app = app.layer(CatchPanicLayer::new());
     }
 
+    if let Some(config) = access_log_config {
+        let request_id_header = app_config
+            .request_id_header
+            .as_deref()
+            .and_then(parse_header_name);
+        match AccessLogger::new(&config, request_id_header) {
+            Ok(logger) => {
+                info!("Layer: AccessLog ({})", config.format);
+                let logger = Arc::new(logger);
+                app = app.layer(axum_middleware::from_fn(move |req, next| {
+                    access_log(logger.clone(), req, next)
+                }));
+            }
+            Err(err) => log_python_error("access log setup failed", err),
+        }
+    }
+
+    if let Some(endpoint) = &app_config.otlp_endpoint {
+        match init_tracing(endpoint, &app_config.title) {
+            Ok(()) => {
+                info!("Layer: OpenTelemetry (exporting to {})", endpoint);
+                app = app.layer(axum_middleware::from_fn(trace_request));
+            }
+            Err(err) => error!("{}", err),
+        }
+    }
+
     if app_config.redirect_slashes {
         app = app.layer(NormalizePathLayer::trim_trailing_slash());
     }
 
-    app
+    add_health_routes(
+        py,
+        app,
+        app_config.health_url.as_deref(),
+        app_config.ready_url.as_deref(),
+        &app_config.readiness_checks,
+        &app_state,
+    )
 }
 
 pub(crate) fn merge_declared_middlewares(
@@ -453,6 +540,7 @@ pub(crate) fn merge_declared_middlewares(
     https_redirect_config: &mut Option<HTTPSRedirectMiddleware>,
     gzip_config: &mut Option<GZipMiddleware>,
     session_config: &mut Option<SessionMiddleware>,
+    access_log_config: &mut Option<AccessLogMiddleware>,
 ) {
     let Some(middlewares) = &app_config.middleware else {
         return;
@@ -472,6 +560,7 @@ pub(crate) fn merge_declared_middlewares(
             https_redirect_config,
             gzip_config,
             session_config,
+            access_log_config,
         ) {
             log_python_error("middleware setup failed", err);
         }
@@ -486,6 +575,7 @@ pub(crate) fn apply_declared_middleware(
     https_redirect_config: &mut Option<HTTPSRedirectMiddleware>,
     gzip_config: &mut Option<GZipMiddleware>,
     session_config: &mut Option<SessionMiddleware>,
+    access_log_config: &mut Option<AccessLogMiddleware>,
 ) -> PyResult<()> {
     if let Ok(config) = middleware_item.extract::<PyRef<'_, CORSMiddleware>>() {
         *cors_config = Some(config.clone());
@@ -507,6 +597,10 @@ pub(crate) fn apply_declared_middleware(
         *session_config = Some(config.clone());
         return Ok(());
     }
+    if let Ok(config) = middleware_item.extract::<PyRef<'_, AccessLogMiddleware>>() {
+        *access_log_config = Some(config.clone());
+        return Ok(());
+    }
 
     let Ok(cls) = middleware_item.getattr("cls") else {
         return Ok(());
@@ -531,12 +625,58 @@ pub(crate) fn apply_declared_middleware(
         }
         "GZipMiddleware" => *gzip_config = Some(parse_gzip_params(kwargs)?),
         "SessionMiddleware" => *session_config = Some(parse_session_params(kwargs)?),
+        "AccessLogMiddleware" => *access_log_config = Some(parse_access_log_params(kwargs)?),
         _ => {}
     }
 
     Ok(())
 }
 
+/// Serves `method_router` while `table` is current and hands requests to the
+/// dynamic routes otherwise, as well as any method the route does not cover.
+fn guard_direct_route(
+    method_router: MethodRouter,
+    dynamic: &DynamicRoutes,
+    table: &Arc<RouteTable>,
+) -> MethodRouter {
+    let fallback = {
+        let dynamic = dynamic.clone();
+        move |req: Request| dynamic.handle(req)
+    };
+    let dynamic = dynamic.clone();
+    let table = table.clone();
+    method_router
+        .fallback(fallback)
+        .layer(axum_middleware::from_fn(move |req: Request, next: Next| {
+            let dynamic = dynamic.clone();
+            let table = table.clone();
+            async move {
+                if dynamic.is_current(&table) {
+                    next.run(req).await
+                } else {
+                    dynamic.handle(req).await
+                }
+            }
+        }))
+}
+
+/// A websocket handler and the host it is served under.
+type WebSocketTarget = (Option<Arc<HostPattern>>, Arc<Py<PyAny>>);
+
+/// The handler of the first route on a websocket path whose host matches
+/// `host`, or else of the one without a host.
+fn select_ws_handler(targets: &[WebSocketTarget], host: Option<&str>) -> Option<Arc<Py<PyAny>>> {
+    targets
+        .iter()
+        .find(|(pattern, _)| {
+            pattern
+                .as_ref()
+                .is_some_and(|pattern| host.is_some_and(|host| pattern.matches(host).is_some()))
+        })
+        .or_else(|| targets.iter().find(|(pattern, _)| pattern.is_none()))
+        .map(|(_, handler)| handler.clone())
+}
+
 pub(crate) fn cached_method_router(
     method: HttpMethod,
     cached: Arc<CachedResponse>,
@@ -566,6 +706,10 @@ pub(crate) fn no_request_method_router(
             let handler = handler.clone();
             let state = state.clone();
             async move {
+                let _permit = match acquire_sync_permit(&state, &handler).await {
+                    Ok(permit) => permit,
+                    Err(resp) => return resp,
+                };
                 run_py_handler_no_request(
                     state.rt_handle,
                     state.async_loop,
@@ -1,134 +1,188 @@
+use super::conn::*;
+use super::handle::*;
+use super::health::*;
 use super::lifecycle::*;
+use super::listener::*;
+use super::logging::*;
 use super::reload::*;
 use super::routes::*;
+use super::signals::*;
+use super::telemetry::*;
+use super::threads::*;
+use super::tls::*;
+#[cfg(unix)]
+use super::workers::*;
 
 use crate::engine::types::FastrAPI;
-use axum::serve::ListenerExt;
-use pyo3::{exceptions::PyRuntimeError, intern, prelude::*};
-use std::{path::PathBuf, sync::Arc};
-use tokio::net::TcpListener;
-use tracing::{Level, error, info};
+use crate::ffi::exceptions::PyHTTPException;
+use axum::{Router, serve::ListenerExt};
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    intern,
+    prelude::*,
+};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::time::Instant;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{error, info, warn};
 
-use crate::globals::PYTHON_RUNTIME;
+use crate::globals::{PYTHON_RUNTIME, RuntimeOptions, configure_runtime};
 
 #[derive(Clone)]
 pub struct AppState {
     pub rt_handle: tokio::runtime::Handle,
     pub async_loop: Arc<Py<PyAny>>,
     pub sync_to_threadpool: bool,
+    pub sync_executor: Option<Arc<SyncExecutor>>,
+    pub readiness: Arc<Readiness>,
+    /// Background tasks still running after their response was sent;
+    /// shutdown waits on these before running the shutdown phase.
+    pub background_tasks: TaskTracker,
+    pub method_not_allowed_handler: Option<Arc<Py<PyAny>>>,
     pub max_body_size: Option<usize>,
     pub max_field_size: Option<usize>,
     pub max_file_size: Option<usize>,
     pub reject_unknown_multipart_fields: bool,
     pub root_path: String,
+    pub track_routes: bool,
 }
+
+pub(crate) struct ServeOptions {
+    pub(crate) bind: BindTarget,
+    pub(crate) tls: Option<TlsConfig>,
+    pub(crate) http2: Option<Http2Config>,
+    pub(crate) graceful_timeout: Duration,
+    pub(crate) logging: LogConfig,
+    pub(crate) runtime: RuntimeOptions,
+}
+
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub fn serve(
+pub(crate) fn serve(py: Python<'_>, options: ServeOptions, app: Py<FastrAPI>) -> PyResult<()> {
+    spawn_server(py, options, app, true)?.join(py)
+}
+
+/// Starts the server on its own thread. With `handle_signals` the process
+/// signals trigger shutdown as well; either way cancelling the handle does.
+pub(crate) fn spawn_server(
     py: Python<'_>,
-    host: Option<String>,
-    port: Option<u16>,
+    options: ServeOptions,
     app: Py<FastrAPI>,
-) -> PyResult<()> {
+    handle_signals: bool,
+) -> PyResult<PyServerHandle> {
     println!("running on FastRAPI v{}", VERSION);
 
-    tracing_subscriber::fmt()
-        .with_max_level(Level::DEBUG)
-        .with_target(false)
-        .try_init()
-        .ok();
+    init_logging(&options.logging);
+    if let Err(err) = configure_runtime(options.runtime) {
+        warn!("{err}");
+    }
 
-    let host: String = host.unwrap_or_else(|| "127.0.0.1".to_string());
-    let port = port.unwrap_or(8000);
-    let rt_handle = PYTHON_RUNTIME.handle().clone();
-    let async_loop = Arc::new(start_background_asyncio_loop(py)?);
-    let async_loop_for_shutdown = async_loop.clone();
-    let app_bound = app.bind(py);
-    let app_config = app_bound.borrow();
-    let app_state = AppState {
-        rt_handle,
-        async_loop,
-        sync_to_threadpool: app_config.sync_to_threadpool,
-        max_body_size: app_config.max_body_size,
-        max_field_size: app_config.max_field_size,
-        max_file_size: app_config.max_file_size,
-        reject_unknown_multipart_fields: app_config.reject_unknown_multipart_fields,
-        root_path: app_config.root_path.clone(),
-    };
+    let bind = options.bind;
+    let http2 = options.http2;
+    let graceful_timeout = options.graceful_timeout;
+    let tls = options
+        .tls
+        .map(ReloadableTls::new)
+        .transpose()
+        .map_err(PyValueError::new_err)?
+        .map(Arc::new);
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    let PreparedApp {
+        router,
+        async_loop: async_loop_for_shutdown,
+        readiness,
+        background_tasks,
+        lifespan,
+        on_startup,
+        on_shutdown,
+    } = prepare_app(py, &app)?;
 
-    let docs_url = app_config.docs_url.clone();
-    let openapi_url = app_config.openapi_url.clone();
-    let docs_url_for_log = docs_url.clone();
-    let on_startup = app_config
-        .on_startup
-        .as_ref()
-        .map(|handler| handler.clone_ref(py));
-    let on_shutdown = app_config
-        .on_shutdown
-        .as_ref()
-        .map(|handler| handler.clone_ref(py));
-    let lifespan = app_config
-        .lifespan
-        .as_ref()
-        .map(|handler| handler.clone_ref(py));
-    let app = app.clone_ref(py);
+    let app_config = app.bind(py).borrow();
+    let docs_url_for_log = app_config.docs_url.clone();
     let redoc_url = app_config.redoc_url.clone();
     let scalar_url = app_config.scalar_url.clone();
     let elements_url = app_config.elements_url.clone();
-    let router = build_router(py, app_state.clone(), docs_url, openapi_url, &app_config);
     drop(app_config);
 
+    let state = Arc::new(ServerState::new());
+    let state_for_thread = state.clone();
+    let shutdown = CancellationToken::new();
+    let shutdown_requested = shutdown.clone();
+
     let server_thread = std::thread::spawn(move || {
+        let state = state_for_thread;
         let entered_lifespan =
             Python::attach(|py| run_startup_phase(py, app, lifespan, on_startup));
 
         let entered_lifespan = match entered_lifespan {
             Ok(entered) => entered,
             Err(err) => {
+                let message = format!("startup failed: {}", err);
                 log_python_error("startup failed", err);
+                Python::attach(|py| stop_background_asyncio_loop(py, &async_loop_for_shutdown));
+                state.set_stopped(Some(message));
                 return;
             }
         };
-        let addr = format!("{}:{}", host, port);
+        readiness.mark_ready();
+        let state_for_server = state.clone();
         let server_result = PYTHON_RUNTIME.block_on(async move {
-            let listener = TcpListener::bind(&addr)
-                .await
-                .map_err(|err| err.to_string())?;
+            let listener = bind_listener(bind).await.map_err(|err| err.to_string())?;
+            let base_url = listener.base_url(scheme);
+            state_for_server.set_running(listener.local_addr(), base_url.clone());
+            let shutdown = async move {
+                if handle_signals {
+                    tokio::select! {
+                        _ = shutdown_signal() => {}
+                        _ = shutdown_requested.cancelled() => {}
+                    }
+                } else {
+                    shutdown_requested.cancelled().await;
+                }
+                readiness.mark_draining();
+            };
 
-            info!("🚀 FastrAPI running at http://{}", addr);
+            info!("🚀 FastrAPI running at {}", base_url);
             if let Some(docs) = &docs_url_for_log {
-                info!("📚 Swagger UI at http://{}{}", addr, docs);
+                info!("📚 Swagger UI at {}{}", base_url, docs);
             }
             if let Some(redoc_docs) = &redoc_url {
-                info!("📚 ReDoc UI at http://{}{}", addr, redoc_docs);
+                info!("📚 ReDoc UI at {}{}", base_url, redoc_docs);
             }
             if let Some(scalar_docs) = &scalar_url {
-                info!("📚 Scalar UI at http://{}{}", addr, scalar_docs);
+                info!("📚 Scalar UI at {}{}", base_url, scalar_docs);
             }
             if let Some(elements_docs) = &elements_url {
-                info!("📚 Elements UI at http://{}{}", addr, elements_docs);
+                info!("📚 Elements UI at {}{}", base_url, elements_docs);
             }
 
-            let listener = listener.tap_io(|stream| {
-                let _ = stream.set_nodelay(true);
-            });
-
-            let service = router.into_make_service_with_connect_info::<std::net::SocketAddr>();
-            let server = axum::serve(listener, service);
+            if let Some(tls) = &tls {
+                tokio::spawn(reload_tls_on_sighup(tls.clone()));
+            }
 
-            server
-                .with_graceful_shutdown(async {
-                    tokio::signal::ctrl_c()
-                        .await
-                        .expect("Failed to install Ctrl+C handler");
+            let deadline = match listener {
+                BoundListener::Tcp(listener) => {
+                    let listener = listener.tap_io(|stream| {
+                        let _ = stream.set_nodelay(true);
+                    });
+                    serve_connections(listener, router, tls, http2, graceful_timeout, shutdown)
+                        .await?
+                }
+                #[cfg(unix)]
+                BoundListener::Unix { listener, cleanup } => {
+                    let deadline =
+                        serve_connections(listener, router, tls, http2, graceful_timeout, shutdown)
+                            .await;
+                    drop(cleanup);
+                    deadline?
+                }
+            };
 
-                    info!("Shutting down...");
-                })
-                .await
-                .map_err(|err| err.to_string())
+            drain_background_tasks(&background_tasks, deadline).await;
+            Ok(())
         });
 
-        if let Err(err) = server_result {
+        if let Err(err) = &server_result {
             error!("Server error: {}", err);
         }
 
@@ -139,37 +193,264 @@ pub fn serve(
                 log_python_error("shutdown failed", err);
             }
         });
+        flush_tracing();
+        state.set_stopped(server_result.err());
     });
 
-    py.detach(move || server_thread.join())
-        .map_err(|_| PyRuntimeError::new_err("Server thread panicked"))?;
+    Ok(PyServerHandle::new(state, shutdown, server_thread))
+}
+
+/// Router, python event loop and lifecycle hooks built from the app's current
+/// configuration; shared by the server and the in-process test client.
+pub(crate) struct PreparedApp {
+    pub(crate) router: Router,
+    pub(crate) async_loop: Arc<Py<PyAny>>,
+    pub(crate) readiness: Arc<Readiness>,
+    pub(crate) background_tasks: TaskTracker,
+    pub(crate) lifespan: Option<Py<PyAny>>,
+    pub(crate) on_startup: Option<Py<PyAny>>,
+    pub(crate) on_shutdown: Option<Py<PyAny>>,
+}
+
+pub(crate) fn prepare_app(py: Python<'_>, app: &Py<FastrAPI>) -> PyResult<PreparedApp> {
+    let rt_handle = PYTHON_RUNTIME.handle().clone();
+    let async_loop = Arc::new(start_background_asyncio_loop(py)?);
+    let app_config = app.bind(py).borrow();
+    let readiness = Arc::new(Readiness::default());
+    let background_tasks = TaskTracker::new();
+    let sync_executor = app_config
+        .max_sync_workers
+        .map(|workers| SyncExecutor::new(workers, app_config.sync_queue_size))
+        .transpose()?
+        .map(Arc::new);
+    let app_state = AppState {
+        rt_handle,
+        async_loop: async_loop.clone(),
+        // bounded sync handlers always run off the runtime's worker threads
+        sync_to_threadpool: app_config.sync_to_threadpool || sync_executor.is_some(),
+        sync_executor,
+        readiness: readiness.clone(),
+        background_tasks: background_tasks.clone(),
+        method_not_allowed_handler: method_not_allowed_handler(py, &app_config)?.map(Arc::new),
+        max_body_size: app_config.max_body_size,
+        max_field_size: app_config.max_field_size,
+        max_file_size: app_config.max_file_size,
+        reject_unknown_multipart_fields: app_config.reject_unknown_multipart_fields,
+        root_path: app_config.root_path.clone(),
+        track_routes: false,
+    };
+
+    let clone_handler =
+        |handler: &Option<Py<PyAny>>| handler.as_ref().map(|handler| handler.clone_ref(py));
+    let router = build_router(
+        py,
+        app.clone_ref(py),
+        app_state,
+        app_config.docs_url.clone(),
+        app_config.openapi_url.clone(),
+        &app_config,
+    );
+
+    Ok(PreparedApp {
+        router,
+        async_loop,
+        readiness,
+        background_tasks,
+        lifespan: clone_handler(&app_config.lifespan),
+        on_startup: clone_handler(&app_config.on_startup),
+        on_shutdown: clone_handler(&app_config.on_shutdown),
+    })
+}
+
+/// The exception handler a 405 goes through: one registered for the status
+/// code itself, else one registered for `HTTPException`.
+fn method_not_allowed_handler(py: Python<'_>, app: &FastrAPI) -> PyResult<Option<Py<PyAny>>> {
+    let Some(handlers) = &app.exception_handlers else {
+        return Ok(None);
+    };
+    let handlers = handlers.bind(py);
+    for key in [
+        405u16.into_pyobject(py)?.into_any(),
+        py.get_type::<PyHTTPException>().into_any(),
+    ] {
+        if let Ok(handler) = handlers.get_item(key) {
+            return Ok(Some(handler.unbind()));
+        }
+    }
+    Ok(None)
+}
+
+/// A prepared app kept between calls by the test client and the ASGI entry
+/// point. `entered` holds the shutdown half once the startup phase has run.
+struct RunningApp {
+    router: Router,
+    async_loop: Arc<Py<PyAny>>,
+    readiness: Arc<Readiness>,
+    entered: Option<(Option<EnteredLifespan>, Option<Py<PyAny>>)>,
+}
+
+impl RunningApp {
+    fn shutdown(self, py: Python<'_>) -> PyResult<()> {
+        self.readiness.mark_draining();
+        stop_background_asyncio_loop(py, &self.async_loop);
+        flush_tracing();
+        match self.entered {
+            Some((entered_lifespan, on_shutdown)) => {
+                run_shutdown_phase(py, entered_lifespan, on_shutdown)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Holds at most one running app. The router is built on first use, or by
+/// `start`, which also runs the startup phase.
+#[derive(Default)]
+pub(crate) struct AppSlot(parking_lot::Mutex<Option<RunningApp>>);
 
-    Ok(())
+impl AppSlot {
+    /// Building runs Python, so the lock is not held meanwhile; a racing
+    /// thread that got there first wins.
+    pub(crate) fn router(&self, py: Python<'_>, app: &Py<FastrAPI>) -> PyResult<Router> {
+        if let Some(running) = self.0.lock().as_ref() {
+            return Ok(running.router.clone());
+        }
+
+        let PreparedApp {
+            router,
+            async_loop,
+            readiness,
+            ..
+        } = prepare_app(py, app)?;
+        let mut slot = self.0.lock();
+        if let Some(running) = slot.as_ref() {
+            let router = running.router.clone();
+            drop(slot);
+            stop_background_asyncio_loop(py, &async_loop);
+            return Ok(router);
+        }
+        // without a startup phase to wait for, the app is ready right away
+        readiness.mark_ready();
+        *slot = Some(RunningApp {
+            router: router.clone(),
+            async_loop,
+            readiness,
+            entered: None,
+        });
+        Ok(router)
+    }
+
+    /// Replaces any running app with a fresh one that ran the startup phase.
+    pub(crate) fn start(&self, py: Python<'_>, app: &Py<FastrAPI>) -> PyResult<()> {
+        self.stop(py)?;
+
+        let PreparedApp {
+            router,
+            async_loop,
+            readiness,
+            lifespan,
+            on_startup,
+            on_shutdown,
+            ..
+        } = prepare_app(py, app)?;
+        let entered_lifespan = match run_startup_phase(py, app.clone_ref(py), lifespan, on_startup)
+        {
+            Ok(entered) => entered,
+            Err(err) => {
+                stop_background_asyncio_loop(py, &async_loop);
+                return Err(err);
+            }
+        };
+
+        readiness.mark_ready();
+        *self.0.lock() = Some(RunningApp {
+            router,
+            async_loop,
+            readiness,
+            entered: Some((entered_lifespan, on_shutdown)),
+        });
+        Ok(())
+    }
+
+    /// Runs the shutdown phase if `start` ran it up, and stops the python loop.
+    pub(crate) fn stop(&self, py: Python<'_>) -> PyResult<()> {
+        let running = self.0.lock().take();
+        match running {
+            Some(running) => running.shutdown(py),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for AppSlot {
+    fn drop(&mut self) {
+        if let Some(running) = self.0.get_mut().take() {
+            Python::attach(|py| stop_background_asyncio_loop(py, &running.async_loop));
+        }
+    }
+}
+
+/// Background tasks run Python on blocking threads and cannot be cancelled, so
+/// any still running at the deadline are left behind rather than awaited.
+async fn drain_background_tasks(background_tasks: &TaskTracker, deadline: Instant) {
+    background_tasks.close();
+    if tokio::time::timeout_at(deadline, background_tasks.wait())
+        .await
+        .is_err()
+    {
+        warn!(
+            "Abandoning {} background task(s) still running at shutdown",
+            background_tasks.len()
+        );
+    }
+}
+
+/// The interpreter and arguments that start this process again. `orig_argv`
+/// keeps interpreter options such as `-m package`, which `sys.argv` loses.
+fn relaunch_command(py: Python<'_>) -> PyResult<(String, Vec<String>, bool)> {
+    let sys = py.import(intern!(py, "sys"))?;
+    let executable: String = sys.getattr(intern!(py, "executable"))?.extract()?;
+    if let Ok(orig_argv) = sys.getattr(intern!(py, "orig_argv")) {
+        let orig_argv: Vec<String> = orig_argv.extract()?;
+        if orig_argv.len() > 1 {
+            let interpreter_args = &orig_argv[1..];
+            let via_module = interpreter_args
+                .iter()
+                .take_while(|arg| arg.starts_with('-'))
+                .any(|arg| arg == "-m");
+            return Ok((executable, interpreter_args.to_vec(), via_module));
+        }
+    }
+    let argv: Vec<String> = sys.getattr(intern!(py, "argv"))?.extract()?;
+    Ok((executable, argv, false))
 }
 
 pub fn serve_with_reload(
     py: Python<'_>,
     reload_dirs: Option<Vec<String>>,
+    reload_includes: Option<Vec<String>>,
     reload_ignore_dirs: Option<Vec<String>>,
     reload_ignore_patterns: Option<Vec<String>>,
     reload_ignore_paths: Option<Vec<String>>,
     reload_tick: u64,
     reload_ignore_worker_failure: bool,
+    graceful_timeout: Duration,
 ) -> PyResult<()> {
     println!("running on FastRAPI v{}", VERSION);
 
-    let sys = py.import(intern!(py, "sys"))?;
-    let executable: String = sys.getattr(intern!(py, "executable"))?.extract()?;
-    let argv: Vec<String> = sys.getattr(intern!(py, "argv"))?.extract()?;
+    let (executable, argv, via_module) = relaunch_command(py)?;
     if argv.is_empty() {
         return Err(PyRuntimeError::new_err(
             "reload=True requires running FastrAPI from a Python script",
         ));
     }
 
-    let watch_dirs = resolve_reload_dirs(&argv[0], reload_dirs);
+    // `python -m package` resolves the package from the working directory
+    let script_path = if via_module { "" } else { argv[0].as_str() };
+    let watch_dirs = resolve_reload_dirs(script_path, reload_dirs);
     let config = ReloadConfig {
         watch_dirs,
+        include_patterns: reload_includes.unwrap_or_default(),
         ignore_dirs: reload_ignore_dirs
             .unwrap_or_default()
             .into_iter()
@@ -183,7 +464,56 @@ pub fn serve_with_reload(
             .collect(),
         tick_ms: reload_tick.max(50),
         ignore_worker_failure: reload_ignore_worker_failure,
+        graceful_timeout,
     };
     py.detach(move || run_reload_supervisor(&executable, &argv, config))
         .map_err(PyRuntimeError::new_err)
 }
+
+#[cfg(unix)]
+pub fn serve_with_workers(
+    py: Python<'_>,
+    workers: usize,
+    graceful_timeout: Duration,
+) -> PyResult<()> {
+    println!("running on FastRAPI v{}", VERSION);
+
+    let (executable, argv, _) = relaunch_command(py)?;
+    if argv.is_empty() {
+        return Err(PyRuntimeError::new_err(
+            "workers > 1 requires running FastrAPI from a Python script",
+        ));
+    }
+
+    py.detach(move || {
+        PYTHON_RUNTIME.block_on(run_worker_supervisor(
+            &executable,
+            &argv,
+            workers,
+            graceful_timeout,
+        ))
+    })
+    .map_err(PyRuntimeError::new_err)
+}
+
+#[cfg(not(unix))]
+pub fn serve_with_workers(
+    _py: Python<'_>,
+    _workers: usize,
+    _graceful_timeout: Duration,
+) -> PyResult<()> {
+    Err(PyValueError::new_err(
+        "workers > 1 relies on SO_REUSEPORT and is only supported on unix",
+    ))
+}
+
+/// Whether this process is one of the workers `serve_with_workers` spawned.
+#[cfg(unix)]
+pub fn is_worker_process() -> bool {
+    std::env::var_os(WORKER_ENV).is_some()
+}
+
+#[cfg(not(unix))]
+pub fn is_worker_process() -> bool {
+    false
+}
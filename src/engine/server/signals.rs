@@ -0,0 +1,34 @@
+use tracing::info;
+
+/// Resolves on Ctrl+C, or SIGTERM/SIGQUIT on unix so process managers (and the
+/// worker supervisor) get the same graceful shutdown as an interactive interrupt.
+pub(crate) async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut terminate =
+            signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+        let mut quit = signal(SignalKind::quit()).expect("Failed to install SIGQUIT handler");
+        tokio::select! {
+            _ = terminate.recv() => "SIGTERM",
+            _ = quit.recv() => "SIGQUIT",
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<&str>();
+
+    let signal = tokio::select! {
+        _ = ctrl_c => "SIGINT",
+        signal = terminate => signal,
+    };
+
+    info!("Received {}, shutting down...", signal);
+}
@@ -0,0 +1,350 @@
+use crate::ffi::py_handlers::ExecutionMode;
+use axum::{
+    extract::{MatchedPath, Request},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    Context, ContextGuard, KeyValue,
+    context::FutureExt,
+    propagation::{Extractor, TextMapPropagator},
+    trace::{SpanContext, SpanKind, Status, TraceContextExt, Tracer, TracerProvider},
+};
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    Resource,
+    propagation::TraceContextPropagator,
+    trace::{SdkTracer, SdkTracerProvider},
+};
+use pyo3::{intern, prelude::*, types::PyModule};
+use std::{ffi::CStr, future::Future, sync::OnceLock};
+
+struct Telemetry {
+    provider: SdkTracerProvider,
+    tracer: SdkTracer,
+}
+
+static TELEMETRY: OnceLock<Telemetry> = OnceLock::new();
+
+/// Installs the OTLP exporter. Like the log subscriber this is process-wide:
+/// the first app to configure an endpoint wins.
+pub(crate) fn init_tracing(endpoint: &str, service_name: &str) -> Result<(), String> {
+    if TELEMETRY.get().is_some() {
+        return Ok(());
+    }
+
+    let endpoint = endpoint.trim_end_matches('/');
+    let endpoint = if endpoint.ends_with("/v1/traces") {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}/v1/traces")
+    };
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpJson)
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|err| format!("failed to build OTLP exporter: {err}"))?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build();
+    let tracer = provider.tracer("fastrapi");
+
+    let _ = TELEMETRY.set(Telemetry { provider, tracer });
+    Ok(())
+}
+
+/// Exports whatever spans are still buffered; called once the server stops.
+pub(crate) fn flush_tracing() {
+    if let Some(telemetry) = TELEMETRY.get()
+        && let Err(err) = telemetry.provider.force_flush()
+    {
+        tracing::warn!("Failed to flush spans: {}", err);
+    }
+}
+
+#[inline(always)]
+fn tracer() -> Option<&'static SdkTracer> {
+    TELEMETRY.get().map(|telemetry| &telemetry.tracer)
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Opens the server span for a request, continuing the trace from incoming
+/// `traceparent`/`tracestate` headers.
+pub(crate) async fn trace_request(req: Request, next: Next) -> Response {
+    let Some(tracer) = tracer() else {
+        return next.run(req).await;
+    };
+
+    let parent = TraceContextPropagator::new().extract(&HeaderExtractor(req.headers()));
+    let method = req.method().as_str().to_owned();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| matched.as_str().to_owned());
+    let mut attributes = vec![
+        KeyValue::new("http.request.method", method.clone()),
+        KeyValue::new("url.path", req.uri().path().to_owned()),
+    ];
+    if let Some(route) = &route {
+        attributes.push(KeyValue::new("http.route", route.clone()));
+    }
+    let span = tracer
+        .span_builder(match &route {
+            Some(route) => format!("{method} {route}"),
+            None => method,
+        })
+        .with_kind(SpanKind::Server)
+        .with_attributes(attributes)
+        .start_with_context(tracer, &parent);
+    let cx = parent.with_span(span);
+
+    let response = next.run(req).with_context(cx.clone()).await;
+
+    let span = cx.span();
+    let status = response.status();
+    span.set_attribute(KeyValue::new(
+        "http.response.status_code",
+        i64::from(status.as_u16()),
+    ));
+    if status.is_server_error() {
+        span.set_status(Status::error(status.to_string()));
+    }
+    span.end();
+    response
+}
+
+/// Names the request span after the route template the fallback router
+/// matched, which axum's `MatchedPath` does not know about.
+pub(crate) fn record_route(method: &str, route: &str, execution_mode: ExecutionMode) {
+    if tracer().is_none() {
+        return;
+    }
+    let cx = Context::current();
+    if !cx.has_active_span() {
+        return;
+    }
+    let span = cx.span();
+    span.update_name(format!("{method} {route}"));
+    span.set_attribute(KeyValue::new("http.route", route.to_owned()));
+    span.set_attribute(KeyValue::new(
+        "fastrapi.execution_mode",
+        format!("{execution_mode:?}"),
+    ));
+}
+
+fn start_child(name: &'static str) -> Option<Context> {
+    let tracer = tracer()?;
+    let parent = Context::current();
+    if !parent.has_active_span() {
+        return None;
+    }
+    let span = tracer.start_with_context(name, &parent);
+    Some(parent.with_span(span))
+}
+
+/// A child of the current span, current itself until dropped.
+pub(crate) struct ChildSpan {
+    cx: Context,
+    _guard: ContextGuard,
+}
+
+impl Drop for ChildSpan {
+    fn drop(&mut self) {
+        self.cx.span().end();
+    }
+}
+
+#[inline(always)]
+pub(crate) fn child_span(name: &'static str) -> Option<ChildSpan> {
+    start_child(name).map(|cx| ChildSpan {
+        _guard: cx.clone().attach(),
+        cx,
+    })
+}
+
+/// Ends a span when dropped, which also covers a future cancelled mid-await.
+struct EndSpan(Context);
+
+impl Drop for EndSpan {
+    fn drop(&mut self) {
+        self.0.span().end();
+    }
+}
+
+/// Runs `future` inside a child span of the current one.
+pub(crate) async fn in_child_span<F: Future>(name: &'static str, future: F) -> F::Output {
+    match start_child(name) {
+        Some(cx) => {
+            let _end = EndSpan(cx.clone());
+            future.with_context(cx).await
+        }
+        None => future.await,
+    }
+}
+
+/// Carries the current trace context onto a blocking thread.
+#[inline(always)]
+pub(crate) fn in_current_context<F, R>(f: F) -> impl FnOnce() -> R + Send + 'static
+where
+    F: FnOnce() -> R + Send + 'static,
+{
+    let cx = tracer().map(|_| Context::current());
+    move || {
+        let _guard = cx.map(Context::attach);
+        f()
+    }
+}
+
+/// The W3C trace context of a span, as seen from Python.
+#[pyclass(name = "SpanContext", frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct PySpanContext {
+    #[pyo3(get)]
+    trace_id: String,
+    #[pyo3(get)]
+    span_id: String,
+    #[pyo3(get)]
+    trace_flags: u8,
+    #[pyo3(get)]
+    tracestate: String,
+}
+
+impl PySpanContext {
+    fn from_span_context(span_context: &SpanContext) -> Self {
+        Self {
+            trace_id: span_context.trace_id().to_string(),
+            span_id: span_context.span_id().to_string(),
+            trace_flags: span_context.trace_flags().to_u8(),
+            tracestate: span_context.trace_state().header(),
+        }
+    }
+}
+
+#[pymethods]
+impl PySpanContext {
+    #[getter]
+    fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            self.trace_id, self.span_id, self.trace_flags
+        )
+    }
+
+    #[getter]
+    fn is_sampled(&self) -> bool {
+        self.trace_flags & 1 == 1
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SpanContext(traceparent='{}')", self.traceparent())
+    }
+}
+
+fn active_span_context() -> Option<PySpanContext> {
+    tracer()?;
+    let cx = Context::current();
+    cx.has_active_span()
+        .then(|| PySpanContext::from_span_context(cx.span().span_context()))
+}
+
+const CONTEXT_MODULE: &CStr = cr#"
+import contextvars
+
+current = contextvars.ContextVar("fastrapi_span_context", default=None)
+
+
+async def run_in_span(coroutine, span_context):
+    token = current.set(span_context)
+    try:
+        return await coroutine
+    finally:
+        current.reset(token)
+"#;
+
+static CONTEXT_HELPERS: OnceLock<Py<PyModule>> = OnceLock::new();
+
+fn context_helpers(py: Python<'_>) -> PyResult<&Bound<'_, PyModule>> {
+    if CONTEXT_HELPERS.get().is_none() {
+        let module = PyModule::from_code(
+            py,
+            CONTEXT_MODULE,
+            c"fastrapi_tracing.py",
+            c"fastrapi._tracing",
+        )?;
+        let _ = CONTEXT_HELPERS.set(module.unbind());
+    }
+    Ok(CONTEXT_HELPERS.get().unwrap().bind(py))
+}
+
+/// Coroutines run on the asyncio loop, away from the Rust context, so the
+/// span context travels with them in a contextvar instead.
+pub(crate) fn in_python_span<'py>(
+    py: Python<'py>,
+    coroutine: Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyAny>> {
+    match active_span_context() {
+        Some(span_context) => context_helpers(py)?
+            .getattr(intern!(py, "run_in_span"))?
+            .call1((coroutine, span_context)),
+        None => Ok(coroutine),
+    }
+}
+
+/// Returns the span of the request being handled, or `None` outside a traced
+/// request.
+#[pyfunction]
+fn current_span_context(py: Python<'_>) -> PyResult<Option<PySpanContext>> {
+    if let Some(span_context) = active_span_context() {
+        return Ok(Some(span_context));
+    }
+    if TELEMETRY.get().is_none() {
+        return Ok(None);
+    }
+    let current = context_helpers(py)?
+        .getattr(intern!(py, "current"))?
+        .call_method0(intern!(py, "get"))?;
+    if current.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(current.cast::<PySpanContext>()?.get().clone()))
+}
+
+pub fn create_tracing_submodule(parent: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = parent.py();
+    let tracing_module = PyModule::new(py, "tracing")?;
+    tracing_module.add_class::<PySpanContext>()?;
+    tracing_module.add_function(wrap_pyfunction!(current_span_context, &tracing_module)?)?;
+    parent.add_submodule(&tracing_module)?;
+
+    let parent_name: String = parent.getattr("__name__")?.extract()?;
+    let base_name = if parent_name.ends_with(".fastrapi") {
+        parent_name.strip_suffix(".fastrapi").unwrap().to_string()
+    } else {
+        parent_name
+    };
+    let full_name = format!("{}.tracing", base_name);
+    tracing_module.setattr("__name__", &full_name)?;
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item(&full_name, &tracing_module)?;
+
+    Ok(())
+}
@@ -0,0 +1,1007 @@
+use super::conn::{TlsConnection, connect_websocket};
+use super::serve::AppSlot;
+
+use crate::engine::types::FastrAPI;
+use crate::ffi::py_handlers::RequestTasks;
+use crate::globals::PYTHON_RUNTIME;
+use crate::utils::py_any_to_json;
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    extract::ConnectInfo,
+    http::{
+        HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode,
+        header::{
+            ACCEPT, CONNECTION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, HOST, LOCATION,
+            SEC_WEBSOCKET_KEY, SEC_WEBSOCKET_PROTOCOL, SEC_WEBSOCKET_VERSION, SET_COOKIE, UPGRADE,
+            USER_AGENT,
+        },
+        response::Parts,
+    },
+};
+use bytes::Bytes;
+use cookie::{Cookie, time::OffsetDateTime};
+use fastwebsockets::{FragmentCollector, Frame, OpCode, WebSocketError, handshake};
+use hyper::upgrade::Upgraded;
+use hyper_util::rt::TokioIo;
+use parking_lot::Mutex;
+use pyo3::{
+    exceptions::{PyConnectionError, PyKeyError, PyRuntimeError, PyTypeError, PyValueError},
+    intern,
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyList, PyString, PyTuple},
+};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+};
+use tokio_util::task::TaskTracker;
+use tower::ServiceExt;
+
+/// Peer address handlers see for test client requests.
+const TEST_CLIENT_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 50000);
+const MAX_REDIRECTS: usize = 20;
+
+/// Scheme, authority and origin-form target of a request URL.
+#[derive(Clone, Debug)]
+struct Target {
+    scheme: String,
+    authority: String,
+    path_and_query: String,
+}
+
+impl Target {
+    fn parse_absolute(url: &str) -> Option<Self> {
+        let (scheme, rest) = url.split_once("://")?;
+        let scheme = match scheme {
+            "ws" => "http",
+            "wss" => "https",
+            scheme => scheme,
+        };
+        let split = rest.find(['/', '?']).unwrap_or(rest.len());
+        let (authority, path_and_query) = rest.split_at(split);
+        let path_and_query = match path_and_query {
+            "" => "/".to_string(),
+            query if query.starts_with('?') => format!("/{query}"),
+            path => path.to_string(),
+        };
+        Some(Self {
+            scheme: scheme.to_string(),
+            authority: authority.to_string(),
+            path_and_query,
+        })
+    }
+
+    fn with_path(&self, path_and_query: String) -> Self {
+        Self {
+            scheme: self.scheme.clone(),
+            authority: self.authority.clone(),
+            path_and_query,
+        }
+    }
+
+    /// Resolves a `Location` header against this URL.
+    fn join(&self, location: &str) -> Self {
+        if let Some(target) = Self::parse_absolute(location) {
+            return target;
+        }
+        if location.starts_with('/') {
+            return self.with_path(location.to_string());
+        }
+        let path = self.path_and_query.split('?').next().unwrap_or_default();
+        let dir = &path[..path.rfind('/').map_or(0, |idx| idx + 1)];
+        self.with_path(format!("{dir}{location}"))
+    }
+
+    fn append_query(&mut self, pairs: &[(String, String)]) {
+        if pairs.is_empty() {
+            return;
+        }
+        let query = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish();
+        let separator = if self.path_and_query.contains('?') {
+            '&'
+        } else {
+            '?'
+        };
+        self.path_and_query.push(separator);
+        self.path_and_query.push_str(&query);
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{}://{}{}",
+            self.scheme, self.authority, self.path_and_query
+        )
+    }
+}
+
+/// Drives the app's router in-process: requests go through `Router::oneshot`
+/// without a listener, and websocket sessions run over an in-memory pipe. Use
+/// it as a context manager to run the startup and shutdown phases as well.
+#[pyclass(name = "TestClient", skip_from_py_object)]
+pub struct PyTestClient {
+    app: Py<FastrAPI>,
+    base: Target,
+    headers: HeaderMap,
+    cookies: Mutex<BTreeMap<String, String>>,
+    follow_redirects: bool,
+    running: AppSlot,
+}
+
+impl PyTestClient {
+    fn resolve(&self, url: &str) -> Target {
+        Target::parse_absolute(url).unwrap_or_else(|| {
+            let base_path = self.base.path_and_query.trim_end_matches('/');
+            self.base
+                .with_path(format!("{}/{}", base_path, url.trim_start_matches('/')))
+        })
+    }
+
+    /// Default headers, then the client's cookies, then per-request overrides.
+    fn request_headers(
+        &self,
+        target: &Target,
+        headers: Option<&Bound<'_, PyAny>>,
+        cookies: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<HeaderMap> {
+        let mut merged = HeaderMap::new();
+        merged.insert(HOST, header_value(&target.authority)?);
+        merged.insert(USER_AGENT, HeaderValue::from_static("testclient"));
+        merged.insert(ACCEPT, HeaderValue::from_static("*/*"));
+        merged.extend(self.headers.clone());
+
+        let mut jar = self.cookies.lock().clone();
+        if let Some(cookies) = cookies {
+            jar.extend(string_pairs(cookies)?);
+        }
+        if !jar.is_empty() {
+            let cookie = jar
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("; ");
+            merged.insert(COOKIE, header_value(&cookie)?);
+        }
+
+        if let Some(headers) = headers {
+            merged.extend(header_map(headers)?);
+        }
+        Ok(merged)
+    }
+
+    /// Stores `Set-Cookie` values in the jar and returns the ones this
+    /// response set.
+    fn store_cookies(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        let mut jar = self.cookies.lock();
+        let now = OffsetDateTime::now_utc();
+        headers
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|value| Cookie::parse(value.to_str().ok()?).ok())
+            .filter_map(|cookie| {
+                let expired = cookie
+                    .max_age()
+                    .is_some_and(|age| age.is_zero() || age.is_negative())
+                    || cookie.expires_datetime().is_some_and(|at| at <= now);
+                let (name, value) = (cookie.name().to_string(), cookie.value().to_string());
+                if expired {
+                    jar.remove(&name);
+                    return None;
+                }
+                jar.insert(name.clone(), value.clone());
+                Some((name, value))
+            })
+            .collect()
+    }
+}
+
+#[pymethods]
+impl PyTestClient {
+    #[new]
+    #[pyo3(signature = (app, base_url="http://testserver", *, headers=None, cookies=None, follow_redirects=true))]
+    fn new(
+        app: Py<FastrAPI>,
+        base_url: &str,
+        headers: Option<&Bound<'_, PyAny>>,
+        cookies: Option<&Bound<'_, PyAny>>,
+        follow_redirects: bool,
+    ) -> PyResult<Self> {
+        let base = Target::parse_absolute(base_url).ok_or_else(|| {
+            PyValueError::new_err("base_url must be absolute, e.g. http://testserver")
+        })?;
+        let headers = headers.map(header_map).transpose()?.unwrap_or_default();
+        let cookies = cookies
+            .map(string_pairs)
+            .transpose()?
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+
+        Ok(Self {
+            app,
+            base,
+            headers,
+            cookies: Mutex::new(cookies),
+            follow_redirects,
+            running: AppSlot::default(),
+        })
+    }
+
+    #[getter]
+    fn base_url(&self) -> String {
+        self.base.url()
+    }
+
+    #[getter]
+    fn get_cookies<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let cookies = PyDict::new(py);
+        for (name, value) in self.cookies.lock().iter() {
+            cookies.set_item(name, value)?;
+        }
+        Ok(cookies)
+    }
+
+    #[setter]
+    fn set_cookies(&self, cookies: &Bound<'_, PyAny>) -> PyResult<()> {
+        *self.cookies.lock() = string_pairs(cookies)?.into_iter().collect();
+        Ok(())
+    }
+
+    #[pyo3(signature = (
+        method,
+        url,
+        *,
+        params=None,
+        headers=None,
+        cookies=None,
+        content=None,
+        data=None,
+        files=None,
+        json=None,
+        follow_redirects=None
+    ))]
+    fn request(
+        &self,
+        py: Python<'_>,
+        method: &str,
+        url: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        headers: Option<&Bound<'_, PyAny>>,
+        cookies: Option<&Bound<'_, PyAny>>,
+        content: Option<&Bound<'_, PyAny>>,
+        data: Option<&Bound<'_, PyAny>>,
+        files: Option<&Bound<'_, PyAny>>,
+        json: Option<&Bound<'_, PyAny>>,
+        follow_redirects: Option<bool>,
+    ) -> PyResult<PyTestResponse> {
+        let router = self.running.router(py, &self.app)?;
+        let mut method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        let mut target = self.resolve(url);
+        if let Some(params) = params {
+            target.append_query(&string_pairs(params)?);
+        }
+        let (mut body, mut content_type) = encode_body(py, content, data, files, json)?;
+        let follow_redirects = follow_redirects.unwrap_or(self.follow_redirects);
+
+        for _ in 0..=MAX_REDIRECTS {
+            let mut request_headers = self.request_headers(&target, headers, cookies)?;
+            if let Some(content_type) = &content_type
+                && !request_headers.contains_key(CONTENT_TYPE)
+            {
+                request_headers.insert(CONTENT_TYPE, header_value(content_type)?);
+            }
+            if !body.is_empty() {
+                request_headers.insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+            }
+
+            let mut request = Request::builder()
+                .method(method.clone())
+                .uri(target.path_and_query.as_str())
+                .body(Body::from(body.clone()))
+                .map_err(|err| PyValueError::new_err(err.to_string()))?;
+            *request.headers_mut() = request_headers;
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(TEST_CLIENT_ADDR));
+            if target.scheme == "https" {
+                request.extensions_mut().insert(TlsConnection);
+            }
+
+            let router = router.clone();
+            let (parts, response_body) = py
+                .detach(move || PYTHON_RUNTIME.block_on(send(router, request)))
+                .map_err(PyRuntimeError::new_err)?;
+            let set_cookies = self.store_cookies(&parts.headers);
+
+            let location = parts
+                .headers
+                .get(LOCATION)
+                .and_then(|location| location.to_str().ok());
+            match location {
+                Some(location) if follow_redirects && parts.status.is_redirection() => {
+                    // 303, and 301/302 after a POST, turn into a body-less GET
+                    if parts.status == StatusCode::SEE_OTHER
+                        || (method == Method::POST
+                            && matches!(
+                                parts.status,
+                                StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND
+                            ))
+                    {
+                        method = Method::GET;
+                        body = Bytes::new();
+                        content_type = None;
+                    }
+                    target = target.join(location);
+                }
+                _ => {
+                    return PyTestResponse::new(
+                        py,
+                        parts,
+                        response_body,
+                        target.url(),
+                        set_cookies,
+                    );
+                }
+            }
+        }
+
+        Err(PyRuntimeError::new_err(format!(
+            "exceeded {MAX_REDIRECTS} redirects"
+        )))
+    }
+
+    #[pyo3(signature = (url, **kwargs))]
+    fn get<'py>(
+        slf: &Bound<'py, Self>,
+        url: &str,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        slf.call_method(intern!(slf.py(), "request"), ("GET", url), kwargs)
+    }
+
+    #[pyo3(signature = (url, **kwargs))]
+    fn post<'py>(
+        slf: &Bound<'py, Self>,
+        url: &str,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        slf.call_method(intern!(slf.py(), "request"), ("POST", url), kwargs)
+    }
+
+    #[pyo3(signature = (url, **kwargs))]
+    fn put<'py>(
+        slf: &Bound<'py, Self>,
+        url: &str,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        slf.call_method(intern!(slf.py(), "request"), ("PUT", url), kwargs)
+    }
+
+    #[pyo3(signature = (url, **kwargs))]
+    fn patch<'py>(
+        slf: &Bound<'py, Self>,
+        url: &str,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        slf.call_method(intern!(slf.py(), "request"), ("PATCH", url), kwargs)
+    }
+
+    #[pyo3(signature = (url, **kwargs))]
+    fn delete<'py>(
+        slf: &Bound<'py, Self>,
+        url: &str,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        slf.call_method(intern!(slf.py(), "request"), ("DELETE", url), kwargs)
+    }
+
+    #[pyo3(signature = (url, **kwargs))]
+    fn head<'py>(
+        slf: &Bound<'py, Self>,
+        url: &str,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        slf.call_method(intern!(slf.py(), "request"), ("HEAD", url), kwargs)
+    }
+
+    #[pyo3(signature = (url, **kwargs))]
+    fn options<'py>(
+        slf: &Bound<'py, Self>,
+        url: &str,
+        kwargs: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        slf.call_method(intern!(slf.py(), "request"), ("OPTIONS", url), kwargs)
+    }
+
+    /// Opens a websocket session. The upgrade needs a real HTTP/1.1
+    /// connection, so this one runs over an in-memory duplex pipe.
+    #[pyo3(signature = (url, *, headers=None, cookies=None, subprotocols=None))]
+    fn websocket_connect(
+        &self,
+        py: Python<'_>,
+        url: &str,
+        headers: Option<&Bound<'_, PyAny>>,
+        cookies: Option<&Bound<'_, PyAny>>,
+        subprotocols: Option<Vec<String>>,
+    ) -> PyResult<PyWebSocketTestSession> {
+        let router = self.running.router(py, &self.app)?;
+        let target = self.resolve(url);
+
+        let mut request_headers = self.request_headers(&target, headers, cookies)?;
+        request_headers.insert(UPGRADE, HeaderValue::from_static("websocket"));
+        request_headers.insert(CONNECTION, HeaderValue::from_static("Upgrade"));
+        request_headers.insert(SEC_WEBSOCKET_VERSION, HeaderValue::from_static("13"));
+        request_headers.insert(SEC_WEBSOCKET_KEY, header_value(&handshake::generate_key())?);
+        if let Some(subprotocols) = subprotocols.filter(|protocols| !protocols.is_empty()) {
+            request_headers.insert(
+                SEC_WEBSOCKET_PROTOCOL,
+                header_value(&subprotocols.join(", "))?,
+            );
+        }
+
+        let mut request = Request::builder()
+            .method(Method::GET)
+            .uri(target.path_and_query.as_str())
+            .body(Body::empty())
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        *request.headers_mut() = request_headers;
+
+        let ws = py
+            .detach(move || {
+                PYTHON_RUNTIME.block_on(connect_websocket(router, request, Some(TEST_CLIENT_ADDR)))
+            })
+            .map_err(PyConnectionError::new_err)?;
+        Ok(PyWebSocketTestSession {
+            ws: Mutex::new(Some(ws)),
+        })
+    }
+
+    /// Runs the startup phase (lifespan or `on_startup`) on a freshly built router.
+    fn __enter__(slf: Py<Self>, py: Python<'_>) -> PyResult<Py<Self>> {
+        let client = slf.borrow(py);
+        client.running.start(py, &client.app)?;
+        drop(client);
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        self.close(py)
+    }
+
+    /// Runs the shutdown phase if the client was entered and stops its loop.
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        self.running.stop(py)
+    }
+}
+
+async fn send(router: Router, mut request: Request<Body>) -> Result<(Parts, Bytes), String> {
+    // background tasks have finished by the time a test looks at their effects;
+    // only this request's are waited on, so concurrent requests don't block it
+    let background_tasks = TaskTracker::new();
+    request
+        .extensions_mut()
+        .insert(RequestTasks(background_tasks.clone()));
+    let response = router
+        .oneshot(request)
+        .await
+        .unwrap_or_else(|err| match err {});
+    let (parts, body) = response.into_parts();
+    let content = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    background_tasks.close();
+    background_tasks.wait().await;
+
+    Ok((parts, content))
+}
+
+fn header_value(value: &str) -> PyResult<HeaderValue> {
+    HeaderValue::from_str(value).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn header_map(headers: &Bound<'_, PyAny>) -> PyResult<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in string_pairs(headers)? {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        map.append(name, header_value(&value)?);
+    }
+    Ok(map)
+}
+
+/// Flattens a mapping or a sequence of pairs into string pairs, repeating the
+/// key for list values the way httpx encodes params and form data.
+fn string_pairs(value: &Bound<'_, PyAny>) -> PyResult<Vec<(String, String)>> {
+    let items = match value.cast::<PyDict>() {
+        Ok(dict) => dict.items().into_any(),
+        Err(_) => value.clone(),
+    };
+
+    let mut pairs = Vec::new();
+    for item in items.try_iter()? {
+        let (key, value): (Bound<'_, PyAny>, Bound<'_, PyAny>) = item?.extract()?;
+        let key = to_param_string(&key)?;
+        if value.is_instance_of::<PyList>() || value.is_instance_of::<PyTuple>() {
+            for value in value.try_iter()? {
+                pairs.push((key.clone(), to_param_string(&value?)?));
+            }
+        } else {
+            pairs.push((key, to_param_string(&value)?));
+        }
+    }
+    Ok(pairs)
+}
+
+fn to_param_string(value: &Bound<'_, PyAny>) -> PyResult<String> {
+    if value.is_none() {
+        return Ok(String::new());
+    }
+    if let Ok(flag) = value.cast::<PyBool>() {
+        return Ok(if flag.is_true() { "true" } else { "false" }.to_string());
+    }
+    Ok(value.str()?.to_string())
+}
+
+/// Bytes of a `content=`/file value: bytes, str, or anything with `read()`.
+fn read_content(value: &Bound<'_, PyAny>) -> PyResult<Vec<u8>> {
+    if let Ok(bytes) = value.cast::<PyBytes>() {
+        return Ok(bytes.as_bytes().to_vec());
+    }
+    if value.is_instance_of::<PyString>() {
+        return Ok(value.extract::<String>()?.into_bytes());
+    }
+    if value.hasattr(intern!(value.py(), "read"))? {
+        return read_content(&value.call_method0(intern!(value.py(), "read"))?);
+    }
+    Err(PyTypeError::new_err(
+        "content must be bytes, str or a file-like object",
+    ))
+}
+
+fn encode_body(
+    py: Python<'_>,
+    content: Option<&Bound<'_, PyAny>>,
+    data: Option<&Bound<'_, PyAny>>,
+    files: Option<&Bound<'_, PyAny>>,
+    json: Option<&Bound<'_, PyAny>>,
+) -> PyResult<(Bytes, Option<String>)> {
+    if let Some(json) = json {
+        let encoded = sonic_rs::to_string(&py_any_to_json(py, json))
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        return Ok((Bytes::from(encoded), Some("application/json".to_string())));
+    }
+
+    if let Some(files) = files {
+        let fields = data.map(string_pairs).transpose()?.unwrap_or_default();
+        return encode_multipart(py, fields, files);
+    }
+
+    if let Some(data) = data {
+        if data.is_instance_of::<PyBytes>() || data.is_instance_of::<PyString>() {
+            return Ok((Bytes::from(read_content(data)?), None));
+        }
+        let encoded = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(string_pairs(data)?)
+            .finish();
+        return Ok((
+            Bytes::from(encoded),
+            Some("application/x-www-form-urlencoded".to_string()),
+        ));
+    }
+
+    match content {
+        Some(content) => Ok((Bytes::from(read_content(content)?), None)),
+        None => Ok((Bytes::new(), None)),
+    }
+}
+
+/// `files` maps field names to bytes, file objects, or `(filename, content)`
+/// / `(filename, content, content_type)` tuples, as a dict or list of pairs.
+fn encode_multipart(
+    py: Python<'_>,
+    fields: Vec<(String, String)>,
+    files: &Bound<'_, PyAny>,
+) -> PyResult<(Bytes, Option<String>)> {
+    let boundary = uuid::Uuid::new_v4().simple().to_string();
+    let quote = |value: &str| value.replace('"', "%22");
+    let mut body = Vec::new();
+
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{value}\r\n",
+                quote(&name)
+            )
+            .as_bytes(),
+        );
+    }
+
+    let items = match files.cast::<PyDict>() {
+        Ok(dict) => dict.items().into_any(),
+        Err(_) => files.clone(),
+    };
+    let mimetypes = py.import(intern!(py, "mimetypes"))?;
+    for item in items.try_iter()? {
+        let (name, value): (String, Bound<'_, PyAny>) = item?.extract()?;
+        let (filename, content, content_type) = match value.cast::<PyTuple>() {
+            Ok(tuple) => (
+                tuple.get_item(0)?.extract::<String>()?,
+                read_content(&tuple.get_item(1)?)?,
+                match tuple.len() {
+                    3.. => tuple.get_item(2)?.extract::<Option<String>>()?,
+                    _ => None,
+                },
+            ),
+            Err(_) => {
+                let filename = value
+                    .getattr(intern!(py, "name"))
+                    .and_then(|path| path.extract::<String>())
+                    .ok()
+                    .and_then(|path| {
+                        std::path::Path::new(&path)
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                    })
+                    .unwrap_or_else(|| name.clone());
+                (filename, read_content(&value)?, None)
+            }
+        };
+        let content_type = match content_type {
+            Some(content_type) => content_type,
+            None => mimetypes
+                .call_method1(intern!(py, "guess_type"), (&filename,))?
+                .get_item(0)?
+                .extract::<Option<String>>()?
+                .unwrap_or_else(|| "application/octet-stream".to_string()),
+        };
+
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {content_type}\r\n\r\n",
+                quote(&name),
+                quote(&filename)
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&content);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+    Ok((
+        Bytes::from(body),
+        Some(format!("multipart/form-data; boundary={boundary}")),
+    ))
+}
+
+fn json_loads<'py>(py: Python<'py>, content: &[u8]) -> PyResult<Bound<'py, PyAny>> {
+    py.import(intern!(py, "json"))?
+        .call_method1(intern!(py, "loads"), (PyBytes::new(py, content),))
+}
+
+/// Case-insensitive, multi-valued response headers in the shape of
+/// `httpx.Headers`.
+#[pyclass(name = "Headers", mapping, skip_from_py_object)]
+pub struct PyTestHeaders {
+    items: Vec<(String, String)>,
+}
+
+impl PyTestHeaders {
+    fn values<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.items
+            .iter()
+            .filter(move |(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn joined(&self, key: &str) -> Option<String> {
+        let values = self.values(key).collect::<Vec<_>>();
+        (!values.is_empty()).then(|| values.join(", "))
+    }
+
+    fn distinct_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = Vec::new();
+        for (name, _) in &self.items {
+            if !keys.contains(name) {
+                keys.push(name.clone());
+            }
+        }
+        keys
+    }
+}
+
+#[pymethods]
+impl PyTestHeaders {
+    fn __getitem__(&self, key: &str) -> PyResult<String> {
+        self.joined(key)
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.values(key).next().is_some()
+    }
+
+    fn __len__(&self) -> usize {
+        self.distinct_keys().len()
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(PyList::new(py, self.distinct_keys())?
+            .try_iter()?
+            .into_any())
+    }
+
+    #[pyo3(signature = (key, default=None))]
+    fn get(&self, key: &str, default: Option<String>) -> Option<String> {
+        self.joined(key).or(default)
+    }
+
+    fn get_list(&self, key: &str) -> Vec<String> {
+        self.values(key).map(str::to_string).collect()
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.distinct_keys()
+    }
+
+    fn items(&self) -> Vec<(String, String)> {
+        self.distinct_keys()
+            .into_iter()
+            .filter_map(|key| self.joined(&key).map(|value| (key, value)))
+            .collect()
+    }
+
+    fn multi_items(&self) -> Vec<(String, String)> {
+        self.items.clone()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Headers({:?})", self.items)
+    }
+}
+
+#[pyclass(name = "Response", skip_from_py_object)]
+pub struct PyTestResponse {
+    status: StatusCode,
+    headers: Py<PyTestHeaders>,
+    content: Bytes,
+    url: String,
+    cookies: Vec<(String, String)>,
+}
+
+impl PyTestResponse {
+    fn new(
+        py: Python<'_>,
+        parts: Parts,
+        content: Bytes,
+        url: String,
+        cookies: Vec<(String, String)>,
+    ) -> PyResult<Self> {
+        let items = parts
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        Ok(Self {
+            status: parts.status,
+            headers: Py::new(py, PyTestHeaders { items })?,
+            content,
+            url,
+            cookies,
+        })
+    }
+}
+
+#[pymethods]
+impl PyTestResponse {
+    #[getter]
+    fn status_code(&self) -> u16 {
+        self.status.as_u16()
+    }
+
+    #[getter]
+    fn reason_phrase(&self) -> &'static str {
+        self.status.canonical_reason().unwrap_or("")
+    }
+
+    #[getter]
+    fn headers(&self, py: Python<'_>) -> Py<PyTestHeaders> {
+        self.headers.clone_ref(py)
+    }
+
+    #[getter]
+    fn content<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.content)
+    }
+
+    #[getter]
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.content).into_owned()
+    }
+
+    #[getter]
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    #[getter]
+    fn cookies<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let cookies = PyDict::new(py);
+        for (name, value) in &self.cookies {
+            cookies.set_item(name, value)?;
+        }
+        Ok(cookies)
+    }
+
+    #[getter]
+    fn is_success(&self) -> bool {
+        self.status.is_success()
+    }
+
+    #[getter]
+    fn is_redirect(&self, py: Python<'_>) -> bool {
+        self.status.is_redirection() && self.headers.borrow(py).__contains__("location")
+    }
+
+    #[getter]
+    fn is_error(&self) -> bool {
+        self.status.is_client_error() || self.status.is_server_error()
+    }
+
+    fn json<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_loads(py, &self.content)
+    }
+
+    fn raise_for_status(slf: Py<Self>, py: Python<'_>) -> PyResult<Py<Self>> {
+        let response = slf.borrow(py);
+        if response.is_error() {
+            let kind = if response.status.is_client_error() {
+                "Client error"
+            } else {
+                "Server error"
+            };
+            return Err(PyRuntimeError::new_err(format!(
+                "{kind} '{}' for url '{}'",
+                response.status, response.url
+            )));
+        }
+        drop(response);
+        Ok(slf)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Response [{}]>", self.status)
+    }
+}
+
+enum Message {
+    Text(Vec<u8>),
+    Binary(Vec<u8>),
+}
+
+/// Client side of a websocket opened by `TestClient.websocket_connect`.
+#[pyclass(name = "WebSocketTestSession", skip_from_py_object)]
+pub struct PyWebSocketTestSession {
+    ws: Mutex<Option<FragmentCollector<TokioIo<Upgraded>>>>,
+}
+
+impl PyWebSocketTestSession {
+    fn write(&self, py: Python<'_>, frame: Frame<'static>) -> PyResult<()> {
+        let mut ws = self.ws.lock();
+        let socket = ws
+            .as_mut()
+            .ok_or_else(|| PyConnectionError::new_err("WebSocket closed"))?;
+        py.detach(|| PYTHON_RUNTIME.block_on(socket.write_frame(frame)))
+            .map_err(|err| PyConnectionError::new_err(err.to_string()))
+    }
+
+    fn receive(&self, py: Python<'_>) -> PyResult<Message> {
+        let mut ws = self.ws.lock();
+        let socket = ws
+            .as_mut()
+            .ok_or_else(|| PyConnectionError::new_err("WebSocket closed"))?;
+        let message = py.detach(|| PYTHON_RUNTIME.block_on(next_message(socket)));
+
+        match message {
+            Ok(Some(message)) => Ok(message),
+            Ok(None) => {
+                *ws = None;
+                Err(PyConnectionError::new_err("WebSocket closed"))
+            }
+            Err(err) => {
+                *ws = None;
+                Err(PyConnectionError::new_err(err.to_string()))
+            }
+        }
+    }
+}
+
+/// Next data message; `None` once the server closed the socket. Pings are
+/// answered by the collector itself.
+async fn next_message(
+    socket: &mut FragmentCollector<TokioIo<Upgraded>>,
+) -> Result<Option<Message>, WebSocketError> {
+    loop {
+        let frame = socket.read_frame().await?;
+        match frame.opcode {
+            OpCode::Text => return Ok(Some(Message::Text(frame.payload.to_vec()))),
+            OpCode::Binary => return Ok(Some(Message::Binary(frame.payload.to_vec()))),
+            OpCode::Close => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+#[pymethods]
+impl PyWebSocketTestSession {
+    fn send_text(&self, py: Python<'_>, data: String) -> PyResult<()> {
+        self.write(py, Frame::text(data.into_bytes().into()))
+    }
+
+    fn send_bytes(&self, py: Python<'_>, data: Vec<u8>) -> PyResult<()> {
+        self.write(py, Frame::binary(data.into()))
+    }
+
+    fn send_json(&self, py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<()> {
+        let json_str = py_any_to_json(py, data).to_string();
+        self.send_text(py, json_str)
+    }
+
+    fn receive_text(&self, py: Python<'_>) -> PyResult<String> {
+        match self.receive(py)? {
+            Message::Text(bytes) => {
+                String::from_utf8(bytes).map_err(|err| PyValueError::new_err(err.to_string()))
+            }
+            Message::Binary(_) => Err(PyTypeError::new_err("Expected text, got binary")),
+        }
+    }
+
+    fn receive_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        match self.receive(py)? {
+            Message::Binary(bytes) => Ok(PyBytes::new(py, &bytes)),
+            Message::Text(_) => Err(PyTypeError::new_err("Expected binary, got text")),
+        }
+    }
+
+    fn receive_json<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self.receive(py)? {
+            Message::Text(bytes) => json_loads(py, &bytes),
+            Message::Binary(_) => Err(PyTypeError::new_err("Expected text, got binary")),
+        }
+    }
+
+    #[pyo3(signature = (code=1000, reason=""))]
+    fn close(&self, py: Python<'_>, code: u16, reason: &str) -> PyResult<()> {
+        if self.ws.lock().is_none() {
+            return Ok(());
+        }
+        let result = self.write(py, Frame::close(code, reason.as_bytes()));
+        *self.ws.lock() = None;
+        result
+    }
+
+    fn __enter__(slf: Py<Self>) -> Py<Self> {
+        slf
+    }
+
+    #[pyo3(signature = (*_args))]
+    fn __exit__(&self, py: Python<'_>, _args: &Bound<'_, PyTuple>) -> PyResult<()> {
+        self.close(py, 1000, "").or_else(|err| {
+            if err.is_instance_of::<PyConnectionError>(py) {
+                Ok(())
+            } else {
+                Err(err)
+            }
+        })
+    }
+}
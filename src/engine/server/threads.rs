@@ -0,0 +1,105 @@
+use super::serve::AppState;
+
+use crate::{globals::RuntimeOptions, routing::types::RouteHandler};
+use axum::{
+    http::{HeaderValue, StatusCode, header::RETRY_AFTER},
+    response::{IntoResponse, Response},
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+pub(crate) fn parse_runtime_params(
+    worker_threads: Option<usize>,
+    max_blocking_threads: Option<usize>,
+    thread_keep_alive: Option<f64>,
+    thread_name: Option<String>,
+) -> PyResult<RuntimeOptions> {
+    if worker_threads == Some(0) {
+        return Err(PyValueError::new_err("worker_threads must be at least 1"));
+    }
+    if max_blocking_threads == Some(0) {
+        return Err(PyValueError::new_err(
+            "max_blocking_threads must be at least 1",
+        ));
+    }
+    let thread_keep_alive = thread_keep_alive
+        .map(|seconds| {
+            if seconds.is_finite() && seconds >= 0.0 {
+                Ok(Duration::from_secs_f64(seconds))
+            } else {
+                Err(PyValueError::new_err(
+                    "thread_keep_alive must be a non-negative number of seconds",
+                ))
+            }
+        })
+        .transpose()?;
+
+    Ok(RuntimeOptions {
+        worker_threads,
+        max_blocking_threads,
+        thread_keep_alive,
+        thread_name,
+    })
+}
+
+/// Bounds the sync handlers of one app: at most `workers` run at once and at
+/// most `queue_size` more wait for a turn; anything beyond that gets a 503.
+pub struct SyncExecutor {
+    running: Arc<Semaphore>,
+    admitted: Arc<Semaphore>,
+}
+
+/// Held for as long as a sync handler runs.
+pub(crate) struct SyncPermit {
+    _running: OwnedSemaphorePermit,
+    _admitted: OwnedSemaphorePermit,
+}
+
+impl SyncExecutor {
+    pub(crate) fn new(workers: usize, queue_size: usize) -> PyResult<Self> {
+        if workers == 0 {
+            return Err(PyValueError::new_err("max_sync_workers must be at least 1"));
+        }
+        Ok(Self {
+            running: Arc::new(Semaphore::new(workers)),
+            admitted: Arc::new(Semaphore::new(workers.saturating_add(queue_size))),
+        })
+    }
+
+    pub(crate) async fn acquire(&self) -> Result<SyncPermit, Response> {
+        let Ok(admitted) = self.admitted.clone().try_acquire_owned() else {
+            return Err(saturated());
+        };
+        let running = self
+            .running
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| saturated())?;
+        Ok(SyncPermit {
+            _running: running,
+            _admitted: admitted,
+        })
+    }
+}
+
+/// Admits a sync handler into the app's executor, if it has one; async
+/// handlers are never limited.
+pub(crate) async fn acquire_sync_permit(
+    state: &AppState,
+    handler: &RouteHandler,
+) -> Result<Option<SyncPermit>, Response> {
+    match &state.sync_executor {
+        Some(executor) if !handler.is_async => executor.acquire().await.map(Some),
+        _ => Ok(None),
+    }
+}
+
+fn saturated() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(RETRY_AFTER, HeaderValue::from_static("1"))],
+    )
+        .into_response()
+}
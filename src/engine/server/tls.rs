@@ -0,0 +1,193 @@
+use arc_swap::ArcSwap;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rustls::{
+    RootCertStore, ServerConfig,
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ClientCertMode {
+    None,
+    Optional,
+    Required,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct TlsConfig {
+    pub(crate) certfile: PathBuf,
+    pub(crate) keyfile: PathBuf,
+    pub(crate) ca_certs: Option<PathBuf>,
+    pub(crate) client_cert_mode: ClientCertMode,
+    pub(crate) http2: bool,
+}
+
+/// Holds the active rustls config; SIGHUP swaps in a freshly loaded one while
+/// already-accepted connections keep the config they were handshaken with.
+pub(crate) struct ReloadableTls {
+    config: TlsConfig,
+    current: ArcSwap<ServerConfig>,
+}
+
+impl ReloadableTls {
+    pub(crate) fn new(config: TlsConfig) -> Result<Self, String> {
+        let server_config = load_server_config(&config)?;
+        Ok(Self {
+            config,
+            current: ArcSwap::from(server_config),
+        })
+    }
+
+    #[inline]
+    pub(crate) fn acceptor(&self) -> TlsAcceptor {
+        TlsAcceptor::from(self.current.load_full())
+    }
+
+    pub(crate) fn reload(&self) -> Result<(), String> {
+        let server_config = load_server_config(&self.config)?;
+        self.current.store(server_config);
+        Ok(())
+    }
+}
+
+/// `ssl_cert_reqs` follows the values of Python's `ssl.CERT_NONE`,
+/// `ssl.CERT_OPTIONAL` and `ssl.CERT_REQUIRED` so uvicorn-style configs carry over.
+pub(crate) fn parse_tls_params(
+    ssl_certfile: Option<String>,
+    ssl_keyfile: Option<String>,
+    ssl_ca_certs: Option<String>,
+    ssl_cert_reqs: u8,
+    http2: bool,
+) -> PyResult<Option<TlsConfig>> {
+    let (certfile, keyfile) = match (ssl_certfile, ssl_keyfile) {
+        (None, None) => {
+            if ssl_ca_certs.is_some() {
+                return Err(PyValueError::new_err(
+                    "ssl_ca_certs requires ssl_certfile and ssl_keyfile",
+                ));
+            }
+            return Ok(None);
+        }
+        (Some(certfile), Some(keyfile)) => (certfile, keyfile),
+        _ => {
+            return Err(PyValueError::new_err(
+                "ssl_certfile and ssl_keyfile must be provided together",
+            ));
+        }
+    };
+
+    let client_cert_mode = match ssl_cert_reqs {
+        0 => ClientCertMode::None,
+        1 => ClientCertMode::Optional,
+        2 => ClientCertMode::Required,
+        other => {
+            return Err(PyValueError::new_err(format!(
+                "ssl_cert_reqs must be 0 (CERT_NONE), 1 (CERT_OPTIONAL) or 2 (CERT_REQUIRED), got {other}"
+            )));
+        }
+    };
+
+    if client_cert_mode != ClientCertMode::None && ssl_ca_certs.is_none() {
+        return Err(PyValueError::new_err(
+            "client certificate verification requires ssl_ca_certs",
+        ));
+    }
+
+    Ok(Some(TlsConfig {
+        certfile: PathBuf::from(certfile),
+        keyfile: PathBuf::from(keyfile),
+        ca_certs: ssl_ca_certs.map(PathBuf::from),
+        client_cert_mode,
+        http2,
+    }))
+}
+
+pub(crate) fn load_server_config(config: &TlsConfig) -> Result<Arc<ServerConfig>, String> {
+    let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+    let certs = load_certs(&config.certfile)?;
+    let key = load_private_key(&config.keyfile)?;
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|err| err.to_string())?;
+
+    let builder = match (&config.ca_certs, config.client_cert_mode) {
+        (Some(ca_certs), mode) if mode != ClientCertMode::None => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_certs)? {
+                roots
+                    .add(cert)
+                    .map_err(|err| format!("{}: {}", ca_certs.display(), err))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if mode == ClientCertMode::Optional {
+                verifier.allow_unauthenticated()
+            } else {
+                verifier
+            };
+            builder.with_client_cert_verifier(verifier.build().map_err(|err| err.to_string())?)
+        }
+        _ => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder
+        .with_single_cert(certs, key)
+        .map_err(|err| err.to_string())?;
+    server_config.alpn_protocols = if config.http2 {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    } else {
+        vec![b"http/1.1".to_vec()]
+    };
+
+    Ok(Arc::new(server_config))
+}
+
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+
+    if certs.is_empty() {
+        return Err(format!("{}: no certificates found", path.display()));
+    }
+    Ok(certs)
+}
+
+fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|err| format!("{}: {}", path.display(), err))?
+        .ok_or_else(|| format!("{}: no private key found", path.display()))
+}
+
+#[cfg(unix)]
+pub(crate) async fn reload_tls_on_sighup(tls: Arc<ReloadableTls>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(err) => {
+            error!("Failed to install SIGHUP handler: {}", err);
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        match tls.reload() {
+            Ok(()) => info!("🔐 Reloaded TLS certificates"),
+            Err(err) => error!("TLS reload failed, keeping previous certificates: {}", err),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn reload_tls_on_sighup(_tls: Arc<ReloadableTls>) {}
@@ -0,0 +1,168 @@
+use super::reload::{spawn_child, stop_child};
+use super::signals::shutdown_signal;
+
+use std::{
+    process::Child,
+    time::{Duration, Instant},
+};
+use tokio::signal::unix::{SignalKind, signal};
+
+pub(crate) const WORKER_ENV: &str = "FASTRAPI_WORKER";
+
+const SUPERVISOR_TICK: Duration = Duration::from_millis(200);
+const RESTART_BACKOFF_BASE: Duration = Duration::from_millis(500);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+// a worker that stayed up this long is considered healthy again and its backoff resets
+const HEALTHY_UPTIME: Duration = Duration::from_secs(10);
+// on top of graceful_timeout, for the workers' shutdown phase to run
+const WORKER_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+struct Worker {
+    id: usize,
+    child: Option<Child>,
+    started_at: Instant,
+    failures: u32,
+    restart_at: Option<Instant>,
+}
+
+/// Runs `count` copies of the current script, each binding the same address
+/// with SO_REUSEPORT. Crashed workers are restarted with exponential backoff;
+/// SIGINT/SIGTERM are forwarded as SIGTERM so every worker drains and runs its
+/// shutdown phase, and SIGHUP is forwarded so workers reload TLS certificates.
+pub(crate) async fn run_worker_supervisor(
+    executable: &str,
+    argv: &[String],
+    count: usize,
+    graceful_timeout: Duration,
+) -> Result<(), String> {
+    let mut workers: Vec<Worker> = Vec::with_capacity(count);
+    for id in 0..count {
+        match spawn_worker(executable, argv, id) {
+            Ok(child) => workers.push(Worker {
+                id,
+                child: Some(child),
+                started_at: Instant::now(),
+                failures: 0,
+                restart_at: None,
+            }),
+            Err(err) => {
+                for worker in &mut workers {
+                    if let Some(child) = &mut worker.child {
+                        stop_child(child);
+                    }
+                }
+                return Err(err.to_string());
+            }
+        }
+    }
+    println!("FastrAPI workers: started {count} worker processes");
+
+    let mut hangup = signal(SignalKind::hangup()).map_err(|err| err.to_string())?;
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    let mut tick = tokio::time::interval(SUPERVISOR_TICK);
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = hangup.recv() => {
+                signal_workers(&workers, libc::SIGHUP);
+                continue;
+            }
+            _ = tick.tick() => {}
+        }
+
+        let now = Instant::now();
+        for worker in &mut workers {
+            match &mut worker.child {
+                Some(child) => {
+                    let Some(status) = child.try_wait().map_err(|err| err.to_string())? else {
+                        continue;
+                    };
+                    if now.duration_since(worker.started_at) >= HEALTHY_UPTIME {
+                        worker.failures = 0;
+                    }
+                    let delay = restart_backoff(worker.failures);
+                    worker.failures = worker.failures.saturating_add(1);
+                    worker.child = None;
+                    worker.restart_at = Some(now + delay);
+                    eprintln!(
+                        "FastrAPI workers: worker {} exited with status {status}; restarting in {:.1}s",
+                        worker.id,
+                        delay.as_secs_f64()
+                    );
+                }
+                None if worker.restart_at.is_some_and(|at| at <= now) => {
+                    match spawn_worker(executable, argv, worker.id) {
+                        Ok(child) => {
+                            worker.child = Some(child);
+                            worker.started_at = now;
+                            worker.restart_at = None;
+                        }
+                        Err(err) => {
+                            let delay = restart_backoff(worker.failures);
+                            worker.failures = worker.failures.saturating_add(1);
+                            worker.restart_at = Some(now + delay);
+                            eprintln!(
+                                "FastrAPI workers: failed to restart worker {}: {err}",
+                                worker.id
+                            );
+                        }
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    println!(
+        "FastrAPI workers: stopping {} worker processes",
+        workers.len()
+    );
+    signal_workers(&workers, libc::SIGTERM);
+
+    let deadline = Instant::now() + graceful_timeout + WORKER_SHUTDOWN_GRACE;
+    while workers.iter_mut().any(is_running) {
+        if Instant::now() >= deadline {
+            eprintln!("FastrAPI workers: shutdown timed out; killing remaining workers");
+            for child in workers
+                .iter_mut()
+                .filter_map(|worker| worker.child.as_mut())
+            {
+                stop_child(child);
+            }
+            break;
+        }
+        tokio::time::sleep(SUPERVISOR_TICK).await;
+    }
+
+    Ok(())
+}
+
+fn spawn_worker(executable: &str, argv: &[String], id: usize) -> std::io::Result<Child> {
+    let id = id.to_string();
+    spawn_child(executable, argv, &[(WORKER_ENV, id.as_str())])
+}
+
+fn restart_backoff(failures: u32) -> Duration {
+    RESTART_BACKOFF_BASE
+        .saturating_mul(1u32.checked_shl(failures).unwrap_or(u32::MAX))
+        .min(RESTART_BACKOFF_MAX)
+}
+
+fn is_running(worker: &mut Worker) -> bool {
+    worker
+        .child
+        .as_mut()
+        .is_some_and(|child| matches!(child.try_wait(), Ok(None)))
+}
+
+fn signal_workers(workers: &[Worker], signal: libc::c_int) {
+    for child in workers.iter().filter_map(|worker| worker.child.as_ref()) {
+        // SAFETY: kill(2) has no memory-safety requirements; the pid belongs to a
+        // child we have not reaped yet, so it cannot have been recycled.
+        unsafe {
+            libc::kill(child.id() as libc::pid_t, signal);
+        }
+    }
+}
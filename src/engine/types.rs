@@ -1,11 +1,13 @@
 use pyo3::prelude::*;
 use pyo3::types::PyAny;
+use std::sync::Arc;
 
 use crate::{
     decorators::PyAPIRouter,
+    engine::server::{health::ReadinessCheck, route_table::LiveRouteTables, serve::AppSlot},
     http::middleware::{
-        CORSMiddleware, GZipMiddleware, HTTPSRedirectMiddleware, SessionMiddleware,
-        TrustedHostMiddleware,
+        AccessLogMiddleware, CORSMiddleware, GZipMiddleware, HTTPSRedirectMiddleware,
+        SessionMiddleware, TrustedHostMiddleware,
     },
 };
 
@@ -18,6 +20,14 @@ pub struct StaticMount {
     pub name: Option<String>,
 }
 
+/// Any other ASGI callable passed to `mount()`, bridged per request.
+#[derive(Clone)]
+pub struct AsgiMount {
+    pub path: String,
+    pub app: Arc<Py<PyAny>>,
+    pub name: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct FrontendMount {
     pub path: String,
@@ -36,8 +46,6 @@ pub struct FastrAPI {
     #[pyo3(get, set)]
     pub debug: bool,
     #[pyo3(get, set)]
-    pub routes: Option<Py<PyAny>>,
-    #[pyo3(get, set)]
     pub title: String,
     #[pyo3(get, set)]
     pub summary: Option<String>,
@@ -46,7 +54,7 @@ pub struct FastrAPI {
     #[pyo3(get, set)]
     pub version: String,
     #[pyo3(get, set)]
-    pub openapi_url: String,
+    pub openapi_url: Option<String>,
     #[pyo3(get, set)]
     pub openapi_tags: Option<Py<PyAny>>,
     #[pyo3(get, set)]
@@ -112,6 +120,10 @@ pub struct FastrAPI {
     #[pyo3(get, set)]
     pub sync_to_threadpool: bool,
     #[pyo3(get, set)]
+    pub max_sync_workers: Option<usize>,
+    #[pyo3(get, set)]
+    pub sync_queue_size: usize,
+    #[pyo3(get, set)]
     pub max_body_size: Option<usize>,
     #[pyo3(get, set)]
     pub max_field_size: Option<usize>,
@@ -128,9 +140,17 @@ pub struct FastrAPI {
     #[pyo3(get, set)]
     pub request_id_header: Option<String>,
     #[pyo3(get, set)]
+    pub otlp_endpoint: Option<String>,
+    #[pyo3(get, set)]
     pub powered_by_header: Option<String>,
+    #[pyo3(get, set)]
+    pub health_url: Option<String>,
+    #[pyo3(get, set)]
+    pub ready_url: Option<String>,
+    pub readiness_checks: Vec<ReadinessCheck>,
     pub static_mounts: Vec<StaticMount>,
     pub frontend_mounts: Vec<FrontendMount>,
+    pub asgi_mounts: Vec<AsgiMount>,
     pub prometheus_config: Option<PrometheusConfig>,
 
     // CORS for rust side of things
@@ -139,6 +159,12 @@ pub struct FastrAPI {
     pub https_redirect_config: Option<HTTPSRedirectMiddleware>,
     pub gzip_config: Option<GZipMiddleware>,
     pub session_config: Option<SessionMiddleware>,
+    pub access_log_config: Option<AccessLogMiddleware>,
+
+    // router and lifespan state when served through `__call__`
+    pub(crate) asgi_app: Arc<AppSlot>,
+    // route tables of running servers, swapped by `reload_routes`
+    pub(crate) live_routes: Arc<LiveRouteTables>,
 
     #[pyo3(get)]
     pub router: Py<PyAPIRouter>,
@@ -1,5 +1,6 @@
+use crate::routing::converters::check_converters;
 use crate::routing::types::{
-    HttpMethod, ParameterSource, RouteEntry, SerializationHint, WebSocketEntry,
+    HttpMethod, ParameterSource, RouteEntry, RouteMethod, SerializationHint, WebSocketEntry,
 };
 use ahash::AHashSet;
 use hyper::StatusCode;
@@ -9,7 +10,7 @@ use pyo3::types::{PyString, PyStringMethods};
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
 
-use super::PyAPIRouter;
+use super::{PyAPIRouter, routing::routes_changed};
 
 impl PyAPIRouter {
     pub fn create_method_decorator_kw(
@@ -19,6 +20,51 @@ impl PyAPIRouter {
         path: String,
         kwargs: Option<&Bound<'_, PyDict>>,
     ) -> PyResult<Py<PyAny>> {
+        self.create_route_decorator(py, vec![method.into()], path, kwargs)
+    }
+
+    /// Backs `api_route`: the decorated function is registered once per name
+    /// in `methods`, a list or set that defaults to GET.
+    pub fn create_api_route_decorator(
+        &self,
+        py: Python<'_>,
+        path: String,
+        methods: Option<&Bound<'_, PyAny>>,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        let Some(methods) = methods.filter(|methods| !methods.is_none()) else {
+            return self.create_method_decorator_kw(py, HttpMethod::GET, path, kwargs);
+        };
+        if methods.is_instance_of::<PyString>() {
+            return Err(pyo3::exceptions::PyTypeError::new_err(
+                "methods must be a list or set of method names",
+            ));
+        }
+        let mut parsed: Vec<RouteMethod> = Vec::new();
+        for name in methods.try_iter()? {
+            let method = RouteMethod::parse(&name?.extract::<String>()?)
+                .map_err(pyo3::exceptions::PyValueError::new_err)?;
+            if !parsed.contains(&method) {
+                parsed.push(method);
+            }
+        }
+        if parsed.is_empty() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "api_route needs at least one method",
+            ));
+        }
+        self.create_route_decorator(py, parsed, path, kwargs)
+    }
+
+    fn create_route_decorator(
+        &self,
+        py: Python<'_>,
+        methods: Vec<RouteMethod>,
+        path: String,
+        kwargs: Option<&Bound<'_, PyDict>>,
+    ) -> PyResult<Py<PyAny>> {
+        check_converters(&path).map_err(pyo3::exceptions::PyValueError::new_err)?;
+
         let extract_opt = |key: &str| -> Option<Py<PyAny>> {
             kwargs
                 .and_then(|kw| kw.get_item(key).ok())
@@ -72,6 +118,10 @@ impl PyAPIRouter {
             .and_then(|kw| kw.get_item("operation_id").ok())
             .and_then(|x| x.extract().ok());
 
+        let name: Option<String> = kwargs
+            .and_then(|kw| kw.get_item("name").ok())
+            .and_then(|x| x.extract().ok());
+
         let responses = kwargs
             .and_then(|kw| kw.get_item("responses").ok())
             .map(|d| crate::utils::py_any_to_json(py, &d));
@@ -84,12 +134,6 @@ impl PyAPIRouter {
             .and_then(|kw| kw.get_item("callbacks").ok())
             .and_then(|x| crate::utils::openapi::parse_callbacks_to_json(py, &x));
 
-        if self.frozen.load(Ordering::Relaxed) {
-            return Err(pyo3::exceptions::PyRuntimeError::new_err(
-                "Cannot modify router after it has been frozen",
-            ));
-        }
-
         let default_status = status_code
             .map(|c| {
                 StatusCode::from_u16(c).map_err(|_| {
@@ -120,6 +164,8 @@ impl PyAPIRouter {
         let deprecated = deprecated.or(self.deprecated);
         let path_for_closure = path.clone();
         let routes = Arc::clone(&self.route_entries);
+        let frozen = Arc::clone(&self.frozen);
+        let served_by = Arc::clone(&self.served_by);
         let response_model_capture = response_model.clone();
         let response_class_capture = response_class.clone();
 
@@ -201,8 +247,9 @@ impl PyAPIRouter {
             let handler = Arc::new(handler);
 
             let entry = RouteEntry {
-                method,
+                method: methods[0].clone(),
                 path: path_for_closure.clone(),
+                name: name.clone(),
                 handler,
                 tags: merged_tags.clone(),
                 summary: summary.clone(),
@@ -214,9 +261,26 @@ impl PyAPIRouter {
                 callbacks: callbacks.clone(),
                 deprecated,
                 include_in_schema,
+                host: None,
             };
 
-            routes.lock().unwrap().push(entry);
+            let live = frozen.load(Ordering::Acquire);
+            {
+                let mut routes = routes.lock().unwrap();
+                if live {
+                    // registering again while serving replaces the old handler
+                    routes.retain(|existing| {
+                        !methods.contains(&existing.method) || existing.path != path_for_closure
+                    });
+                }
+                routes.extend(methods.iter().map(|method| RouteEntry {
+                    method: method.clone(),
+                    ..entry.clone()
+                }));
+            }
+            if live {
+                routes_changed(py, &served_by);
+            }
 
             Ok(func)
         };
@@ -227,7 +291,7 @@ impl PyAPIRouter {
     pub fn create_ws_decorator(&self, py: Python<'_>, path: String) -> PyResult<Py<PyAny>> {
         if self.frozen.load(Ordering::Relaxed) {
             return Err(pyo3::exceptions::PyRuntimeError::new_err(
-                "Cannot modify router after it has been frozen",
+                "Cannot add WebSocket routes after the app has started",
             ));
         }
         if !path.starts_with('/') {
@@ -244,6 +308,7 @@ impl PyAPIRouter {
             let entry = WebSocketEntry {
                 path: path.clone(),
                 handler: func.clone_ref(py),
+                host: None,
             };
             websockets.lock().unwrap().push(entry);
             Ok(func)
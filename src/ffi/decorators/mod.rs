@@ -2,13 +2,17 @@ mod init;
 mod requests;
 mod routing;
 
+use crate::engine::server::route_table::LiveRouteTables;
+use crate::routing::host::HostPattern;
+use crate::routing::introspection::route_objects;
 use crate::routing::types::{
     HttpMethod, RouteEntry, SubRouterMount, WebSocketEntry,
 };
 
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::{Py, PyAny, PyAnyMethods, PyResult, Python, pyclass, pymethods};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 
 #[pyclass(name = "APIRouter", skip_from_py_object)]
 #[derive(Clone)]
@@ -29,12 +33,14 @@ pub struct PyAPIRouter {
     pub default_response_class: Option<Py<PyAny>>,
     #[pyo3(get)]
     pub generate_unique_id_function: Option<Py<PyAny>>,
+    pub host: Option<Arc<HostPattern>>,
 
     pub route_entries: Arc<Mutex<Vec<RouteEntry>>>,
     pub websocket_entries: Arc<Mutex<Vec<WebSocketEntry>>>,
     pub sub_routers: Arc<Mutex<Vec<SubRouterMount>>>,
     pub frozen: Arc<AtomicBool>,
-    pub cached_flat: Arc<Mutex<Option<Arc<(Vec<RouteEntry>, Vec<WebSocketEntry>)>>>>,
+    pub cached_flat: Arc<Mutex<Option<(u64, Arc<(Vec<RouteEntry>, Vec<WebSocketEntry>)>)>>>,
+    pub served_by: Arc<Mutex<Vec<Weak<LiveRouteTables>>>>,
 }
 
 impl PyAPIRouter {
@@ -48,12 +54,14 @@ impl PyAPIRouter {
             include_in_schema: true,
             default_response_class: None,
             generate_unique_id_function: None,
+            host: None,
 
             route_entries: Arc::new(Mutex::new(Vec::new())),
             websocket_entries: Arc::new(Mutex::new(Vec::new())),
             sub_routers: Arc::new(Mutex::new(Vec::new())),
             frozen: Arc::new(AtomicBool::new(false)),
             cached_flat: Arc::new(Mutex::new(None)),
+            served_by: Arc::new(Mutex::new(Vec::new())),
         }
     }
 }
@@ -61,7 +69,7 @@ impl PyAPIRouter {
 #[pymethods]
 impl PyAPIRouter {
     #[new]
-    #[pyo3(signature = (*, prefix="".to_string(), tags=None, dependencies=None, responses=None, deprecated=None, include_in_schema=true, default_response_class=None, generate_unique_id_function=None))]
+    #[pyo3(signature = (*, prefix="".to_string(), tags=None, dependencies=None, responses=None, deprecated=None, include_in_schema=true, default_response_class=None, generate_unique_id_function=None, host=None))]
     fn new(
         prefix: String,
         tags: Option<Py<PyAny>>,
@@ -71,6 +79,7 @@ impl PyAPIRouter {
         include_in_schema: bool,
         default_response_class: Option<Py<PyAny>>,
         generate_unique_id_function: Option<Py<PyAny>>,
+        host: Option<&str>,
     ) -> PyResult<Self> {
         let tag_vec = Python::attach(|py| {
             if let Some(ref tags_obj) = tags {
@@ -93,14 +102,28 @@ impl PyAPIRouter {
             include_in_schema,
             default_response_class,
             generate_unique_id_function,
+            host: parse_host(host)?,
             route_entries: Arc::new(Mutex::new(Vec::new())),
             websocket_entries: Arc::new(Mutex::new(Vec::new())),
             sub_routers: Arc::new(Mutex::new(Vec::new())),
             frozen: Arc::new(AtomicBool::new(false)),
             cached_flat: Arc::new(Mutex::new(None)),
+            served_by: Arc::new(Mutex::new(Vec::new())),
         })
     }
 
+    /// The host pattern this router's routes are served under, if any.
+    #[getter]
+    fn host(&self) -> Option<&str> {
+        self.host.as_ref().map(|host| &*host.source)
+    }
+
+    /// Every route this router and its included routers serve.
+    #[getter]
+    fn routes(&self, py: Python<'_>) -> PyResult<Vec<Py<PyAny>>> {
+        route_objects(py, &self.list_routes(py))
+    }
+
     #[pyo3(signature = (path))]
     fn const_get(&self, py: Python<'_>, path: String) -> PyResult<Py<PyAny>> {
         self.create_method_decorator_kw(py, HttpMethod::GET, path, None)
@@ -111,7 +134,7 @@ impl PyAPIRouter {
         self.create_ws_decorator(py, path)
     }
 
-    #[pyo3(signature = (router, *, prefix="".to_string(), tags=None, dependencies=None, responses=None, deprecated=None, include_in_schema=true, default_response_class=None, generate_unique_id_function=None))]
+    #[pyo3(signature = (router, *, prefix="".to_string(), tags=None, dependencies=None, responses=None, deprecated=None, include_in_schema=true, default_response_class=None, generate_unique_id_function=None, host=None))]
     pub fn include_router(
         &self,
         py: Python<'_>,
@@ -124,12 +147,9 @@ impl PyAPIRouter {
         include_in_schema: bool,
         default_response_class: Option<Py<PyAny>>,
         generate_unique_id_function: Option<Py<PyAny>>,
+        host: Option<&str>,
     ) -> PyResult<()> {
-        if self.frozen.load(Ordering::Relaxed) {
-            return Err(pyo3::exceptions::PyRuntimeError::new_err(
-                "Cannot modify router after it has been frozen",
-            ));
-        }
+        let host = parse_host(host)?;
         let tag_vec: Vec<String> = if let Some(ref tags_obj) = tags {
             let tags_bound = tags_obj.bind(py);
             if let Ok(iter) = tags_bound.try_iter() {
@@ -151,7 +171,11 @@ impl PyAPIRouter {
             include_in_schema,
             default_response_class,
             generate_unique_id_function,
+            host,
         });
+        if self.frozen.load(Ordering::Acquire) {
+            routing::routes_changed(py, &self.served_by);
+        }
 
         Ok(())
     }
@@ -181,6 +205,13 @@ impl PyAPIRouter {
             include_in_schema,
             default_response_class,
             generate_unique_id_function,
+            None,
         )
     }
 }
+
+fn parse_host(host: Option<&str>) -> PyResult<Option<Arc<HostPattern>>> {
+    host.map(|host| HostPattern::parse(host).map(Arc::new))
+        .transpose()
+        .map_err(PyValueError::new_err)
+}
@@ -1,43 +1,98 @@
 use super::PyAPIRouter;
+use crate::engine::server::route_table::LiveRouteTables;
+use crate::routing::host::bind_host_params;
 use crate::routing::types::{RouteEntry, WebSocketEntry};
 use pyo3::prelude::Python;
-use std::sync::Arc;
 use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, Weak};
 
 impl PyAPIRouter {
-    pub fn mark_frozen(&self) {
+    /// Freezes the router and remembers that `app` serves it, so routes added
+    /// from now on rebuild that app's route tables.
+    pub fn mark_frozen(&self, app: &Arc<LiveRouteTables>) {
         self.frozen.store(true, Ordering::Release);
+        let mut served_by = self.served_by.lock().unwrap();
+        served_by.retain(|served| served.strong_count() > 0);
+        if !served_by
+            .iter()
+            .any(|served| std::ptr::eq(served.as_ptr(), Arc::as_ptr(app)))
+        {
+            served_by.push(Arc::downgrade(app));
+        }
     }
-    pub fn freeze(&self, py: Python<'_>) {
+    pub fn freeze(&self, py: Python<'_>, app: &Arc<LiveRouteTables>) {
         if self.frozen.load(Ordering::Acquire) {
             return;
         }
-        let flat = Arc::new(flatten_router(py, self));
-        *self.cached_flat.lock().unwrap() = Some(flat);
-        self.mark_frozen();
+        self.flatten(py, app);
     }
 
-    pub fn flatten(&self, py: Python<'_>) -> Arc<(Vec<RouteEntry>, Vec<WebSocketEntry>)> {
-        if self.frozen.load(Ordering::Acquire) {
-            if let Some(cached) = self.cached_flat.lock().unwrap().as_ref() {
-                return cached.clone();
-            }
-            return Arc::new(flatten_router(py, self));
+    /// Flattening marks every router it reaches frozen; routes added to a
+    /// frozen router bump the serving app's route version, which invalidates
+    /// the cache.
+    pub fn flatten(
+        &self,
+        py: Python<'_>,
+        app: &Arc<LiveRouteTables>,
+    ) -> Arc<(Vec<RouteEntry>, Vec<WebSocketEntry>)> {
+        let version = app.version();
+        if self.frozen.load(Ordering::Acquire)
+            && let Some((cached_version, cached)) = self.cached_flat.lock().unwrap().as_ref()
+            && *cached_version == version
+        {
+            return cached.clone();
         }
 
-        Arc::new(flatten_router(py, self))
+        let flat = Arc::new(flatten_router(py, self, app));
+        *self.cached_flat.lock().unwrap() = Some((version, flat.clone()));
+        flat
+    }
+
+    /// The routes `flatten` would serve, without freezing any router.
+    pub fn list_routes(&self, py: Python<'_>) -> (Vec<RouteEntry>, Vec<WebSocketEntry>) {
+        walk_router(py, self, None)
     }
 }
 
-pub fn flatten_router(py: Python<'_>, root: &PyAPIRouter) -> (Vec<RouteEntry>, Vec<WebSocketEntry>) {
+/// Records that a router already being served changed, so the apps serving
+/// it rebuild their route tables.
+pub(crate) fn routes_changed(py: Python<'_>, served_by: &Mutex<Vec<Weak<LiveRouteTables>>>) {
+    let apps: Vec<_> = served_by
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    for app in apps {
+        app.routes_changed(py);
+    }
+}
+
+pub fn flatten_router(
+    py: Python<'_>,
+    root: &PyAPIRouter,
+    app: &Arc<LiveRouteTables>,
+) -> (Vec<RouteEntry>, Vec<WebSocketEntry>) {
+    walk_router(py, root, Some(app))
+}
+
+fn walk_router(
+    py: Python<'_>,
+    root: &PyAPIRouter,
+    freeze: Option<&Arc<LiveRouteTables>>,
+) -> (Vec<RouteEntry>, Vec<WebSocketEntry>) {
     let mut routes = Vec::new();
     let mut ws_routes = Vec::new();
-    let mut stack = vec![(root.clone(), String::new(), Vec::<String>::new())];
+    let mut stack = vec![(root.clone(), String::new(), Vec::<String>::new(), None)];
 
-    while let Some((router, prefix, parent_tags)) = stack.pop() {
-        router.mark_frozen();
+    while let Some((router, prefix, parent_tags, parent_host)) = stack.pop() {
+        if let Some(app) = freeze {
+            router.mark_frozen(app);
+        }
 
         let full_prefix = join_path(&prefix, &router.prefix);
+        // the innermost host wins
+        let host = router.host.clone().or(parent_host);
 
         let mut current_tags = parent_tags;
         for tag in &router.tags {
@@ -58,7 +113,11 @@ pub fn flatten_router(py: Python<'_>, root: &PyAPIRouter) -> (Vec<RouteEntry>, V
             RouteEntry {
                 method: entry.method,
                 path: join_path(&full_prefix, &entry.path),
-                handler: entry.handler,
+                name: entry.name,
+                handler: match &host {
+                    Some(host) => bind_host_params(&entry.handler, host),
+                    None => entry.handler,
+                },
                 tags,
                 summary: entry.summary.clone(),
                 description: entry.description.clone(),
@@ -69,6 +128,7 @@ pub fn flatten_router(py: Python<'_>, root: &PyAPIRouter) -> (Vec<RouteEntry>, V
                 callbacks: entry.callbacks.clone(),
                 deprecated: entry.deprecated,
                 include_in_schema: entry.include_in_schema,
+                host: host.clone(),
             }
         }));
 
@@ -76,6 +136,7 @@ pub fn flatten_router(py: Python<'_>, root: &PyAPIRouter) -> (Vec<RouteEntry>, V
         ws_routes.extend(ws_entries.into_iter().map(|ws| WebSocketEntry {
             path: join_path(&full_prefix, &ws.path),
             handler: ws.handler.clone_ref(py),
+            host: host.clone(),
         }));
 
         let subs = router.sub_routers.lock().unwrap().clone();
@@ -93,6 +154,7 @@ pub fn flatten_router(py: Python<'_>, root: &PyAPIRouter) -> (Vec<RouteEntry>, V
                 sub_router.clone(),
                 join_path(&full_prefix, &sub.prefix),
                 sub_tags,
+                sub.host.or_else(|| host.clone()),
             ));
         }
     }
@@ -1,6 +1,9 @@
+use crate::engine::server::telemetry::{
+    child_span, in_child_span, in_current_context, in_python_span,
+};
 use crate::ffi::exceptions::PyHTTPException;
 use crate::ffi::pydantic;
-use crate::http::request::PyRequest;
+use crate::http::request::{PendingBody, PyBodyReceiver, PyRequest};
 use crate::routing::dependencies::{self, DependencyExecutionError};
 use crate::routing::types::{BodyPayload, PathParamRange, RequestInput, RouteHandler};
 use crate::types::response::ResponseType;
@@ -15,6 +18,7 @@ use smallvec::SmallVec;
 use std::future::Future;
 use std::sync::Arc;
 use std::sync::OnceLock;
+use tokio_util::task::TaskTracker;
 use tracing::error;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,6 +41,7 @@ fn specialized_response_conversion(
     result: &Bound<'_, PyAny>,
     handler: &RouteHandler,
 ) -> Response {
+    let _span = child_span("serialize_response");
     match handler.response_type {
         ResponseType::PlainText => {
             if let Ok(s) = result.cast::<PyString>() {
@@ -94,7 +99,7 @@ fn build_request_input_from_parts<'a>(
     if !param_ranges.is_empty() {
         let path_params_vec: SmallVec<[(String, &'a str); 8]> = param_ranges
             .iter()
-            .map(|r| (r.key.clone(), &path_str[r.start..r.end]))
+            .map(|r| (r.key.clone(), r.value(path_str)))
             .collect();
         let _ = path_params.set(path_params_vec);
     }
@@ -107,6 +112,7 @@ fn build_request_input_from_parts<'a>(
         path_params,
         query_params: OnceLock::new(),
         cookies: OnceLock::new(),
+        body: parts.extensions.get::<PendingBody>(),
     }
 }
 
@@ -151,17 +157,37 @@ fn create_request_object(py: Python<'_>, request_input: &RequestInput<'_>) -> Py
         .try_for_each(|(k, v)| cookies.set_item(k, v))?;
     scope.set_item(intern!(py, "cookies"), cookies)?;
 
-    let py_request = PyRequest::from_scope(py, scope.into_any().unbind());
+    let receive = request_input
+        .body
+        .map(|body| {
+            Py::new(py, PyBodyReceiver { body: body.clone() }).map(|receiver| receiver.into_any())
+        })
+        .transpose()?;
+    let py_request = PyRequest::from_scope(py, scope.into_any().unbind(), receive);
     Ok(Py::new(py, py_request)?.into_any())
 }
 
+/// A tracker for the background tasks of a single request, set as a request
+/// extension by callers that wait for them, such as the test client.
+#[derive(Clone)]
+pub struct RequestTasks(pub TaskTracker);
+
+/// The trackers a request's background tasks are spawned onto: the app's,
+/// which shutdown drains, and the request's own if it carries `RequestTasks`.
+#[derive(Clone)]
+struct BackgroundTrackers {
+    app: TaskTracker,
+    request: Option<TaskTracker>,
+}
+
 #[inline(always)]
 fn spawn_background_tasks(
+    background_tasks: &BackgroundTrackers,
     rt_handle: &tokio::runtime::Handle,
     bg_tasks: Option<Py<crate::engine::background::PyBackgroundTasks>>,
 ) {
     if let Some(tasks) = bg_tasks {
-        rt_handle.spawn(async move {
+        let run = async move {
             let handles = Python::attach(|py| match tasks.try_borrow(py) {
                 Ok(bg) => bg.execute_all(),
                 Err(e) => {
@@ -172,7 +198,17 @@ fn spawn_background_tasks(
             for handle in handles {
                 let _ = handle.await;
             }
-        });
+        };
+        match &background_tasks.request {
+            Some(request) => {
+                background_tasks
+                    .app
+                    .spawn_on(request.track_future(run), rt_handle);
+            }
+            None => {
+                background_tasks.app.spawn_on(run, rt_handle);
+            }
+        }
     }
 }
 
@@ -203,6 +239,7 @@ fn resolve_sync_deps<'py>(
     request_object: Option<Py<PyAny>>,
     kwargs: &Bound<'py, PyDict>,
 ) -> Result<(), Response> {
+    let _span = child_span("execute_dependencies");
     let dep_results = dependencies::execute_dependencies_sync(
         py,
         &handler.dependencies,
@@ -258,6 +295,7 @@ pub(crate) fn render_no_request_json_response(py: Python<'_>, handler: &RouteHan
             .into_response();
     }
 
+    let _span = child_span("serialize_response");
     crate::utils::py_json_response_with_status_hint(
         py,
         handler.default_status.unwrap_or(StatusCode::OK),
@@ -287,12 +325,51 @@ where
     })
 }
 
+/// Runs the app's 405 exception handler with the request and an
+/// `HTTPException` carrying the `Allow` header.
+pub(crate) async fn run_method_not_allowed_handler(
+    handler: Arc<Py<PyAny>>,
+    async_loop: Arc<Py<PyAny>>,
+    parts: Parts,
+    allow: String,
+) -> Response {
+    let scheduled = Python::attach(|py| {
+        let call = || -> PyResult<Result<Py<PyAny>, _>> {
+            let request = create_request_object(py, &build_request_input_from_parts(&parts, &[]))?;
+            let headers = PyDict::new(py);
+            headers.set_item("Allow", &allow)?;
+            let exc = py.get_type::<PyHTTPException>().call1((
+                StatusCode::METHOD_NOT_ALLOWED.as_u16(),
+                "Method Not Allowed",
+                headers,
+            ))?;
+            let result = handler.bind(py).call1((request, exc))?;
+            if result.hasattr(intern!(py, "__await__"))? {
+                return Ok(Err(schedule_python_coroutine(py, &async_loop, result)?));
+            }
+            Ok(Ok(result.unbind()))
+        };
+        call().map_err(|err| python_error_to_response(py, err))
+    });
+
+    let result = match scheduled {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(future)) => future.await,
+        Err(resp) => return resp,
+    };
+    Python::attach(|py| match result {
+        Ok(result) => crate::http::responses::convert_auto_response(py, result.bind(py)),
+        Err(err) => python_error_to_response(py, err),
+    })
+}
+
 #[inline(always)]
 pub(crate) fn schedule_python_coroutine(
     py: Python<'_>,
     async_loop: &Arc<Py<PyAny>>,
     coroutine: Bound<'_, PyAny>,
 ) -> PyResult<std::pin::Pin<Box<dyn Future<Output = PyResult<Py<PyAny>>> + Send>>> {
+    let coroutine = in_python_span(py, coroutine)?;
     let locals = rsloop::rust_async::TaskLocals::new(async_loop.bind(py).clone());
     let future = rsloop::rust_async::into_future_with_locals(&locals, coroutine)?;
     Ok(Box::pin(future))
@@ -320,12 +397,12 @@ async fn core_sync_no_args(
     }
 
     rt_handle
-        .spawn_blocking(move || {
+        .spawn_blocking(in_current_context(move || {
             Python::attach(|py| match call_handler_and_convert(py, &handler, None) {
                 Ok(res) => res,
                 Err(err) => python_error_to_response(py, err),
             })
-        })
+        }))
         .await
         .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
 }
@@ -357,6 +434,7 @@ async fn core_sync_no_deps(
     param_ranges: SmallVec<[PathParamRange; 4]>,
     payload: Option<BodyPayload>,
     sync_to_threadpool: bool,
+    background_tasks: BackgroundTrackers,
 ) -> Response {
     if !sync_to_threadpool {
         return Python::attach(|py| {
@@ -368,7 +446,7 @@ async fn core_sync_no_deps(
                 };
             match call_handler_and_convert(py, &handler, Some(&kwargs)) {
                 Ok(response) => {
-                    spawn_background_tasks(&rt_handle, bg_tasks);
+                    spawn_background_tasks(&background_tasks, &rt_handle, bg_tasks);
                     response
                 }
                 Err(err) => python_error_to_response(py, err),
@@ -377,7 +455,7 @@ async fn core_sync_no_deps(
     }
 
     rt_handle
-        .spawn_blocking(move || {
+        .spawn_blocking(in_current_context(move || {
             Python::attach(|py| {
                 let request_input = build_request_input_from_parts(&request_parts, &param_ranges);
                 let (kwargs, bg_tasks) = match prepare_kwargs_and_payload(
@@ -392,13 +470,13 @@ async fn core_sync_no_deps(
                 match call_handler_and_convert(py, &handler, Some(&kwargs)) {
                     Ok(response) => {
                         let current_rt = tokio::runtime::Handle::current();
-                        spawn_background_tasks(&current_rt, bg_tasks);
+                        spawn_background_tasks(&background_tasks, &current_rt, bg_tasks);
                         response
                     }
                     Err(err) => python_error_to_response(py, err),
                 }
             })
-        })
+        }))
         .await
         .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
 }
@@ -410,6 +488,7 @@ async fn core_sync_deps<const NEEDS_REQ: bool>(
     param_ranges: SmallVec<[PathParamRange; 4]>,
     payload: Option<BodyPayload>,
     sync_to_threadpool: bool,
+    background_tasks: BackgroundTrackers,
 ) -> Response {
     if !sync_to_threadpool {
         return Python::attach(|py| {
@@ -435,7 +514,7 @@ async fn core_sync_deps<const NEEDS_REQ: bool>(
 
             match call_handler_and_convert(py, &handler, Some(&kwargs)) {
                 Ok(response) => {
-                    spawn_background_tasks(&rt_handle, bg_tasks);
+                    spawn_background_tasks(&background_tasks, &rt_handle, bg_tasks);
                     response
                 }
                 Err(err) => python_error_to_response(py, err),
@@ -444,7 +523,7 @@ async fn core_sync_deps<const NEEDS_REQ: bool>(
     }
 
     rt_handle
-        .spawn_blocking(move || {
+        .spawn_blocking(in_current_context(move || {
             Python::attach(|py| {
                 let request_input = build_request_input_from_parts(&request_parts, &param_ranges);
                 let (kwargs, bg_tasks) = match prepare_kwargs_and_payload(
@@ -473,13 +552,13 @@ async fn core_sync_deps<const NEEDS_REQ: bool>(
                 match call_handler_and_convert(py, &handler, Some(&kwargs)) {
                     Ok(response) => {
                         let current_rt = tokio::runtime::Handle::current();
-                        spawn_background_tasks(&current_rt, bg_tasks);
+                        spawn_background_tasks(&background_tasks, &current_rt, bg_tasks);
                         response
                     }
                     Err(err) => python_error_to_response(py, err),
                 }
             })
-        })
+        }))
         .await
         .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
 }
@@ -491,10 +570,11 @@ async fn core_async_no_deps(
     request_parts: Parts,
     param_ranges: SmallVec<[PathParamRange; 4]>,
     payload: Option<BodyPayload>,
+    background_tasks: BackgroundTrackers,
 ) -> Response {
     let handler_clone = handler.clone();
     let setup_result = rt_handle
-        .spawn_blocking(move || {
+        .spawn_blocking(in_current_context(move || {
             Python::attach(
                 |py| -> Result<
                     (
@@ -522,7 +602,7 @@ async fn core_async_no_deps(
                     Ok((coroutine, bg_tasks))
                 },
             )
-        })
+        }))
         .await
         .unwrap_or_else(|_| Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()));
 
@@ -535,7 +615,7 @@ async fn core_async_no_deps(
         Python::attach(|py| into_asyncio_future(py, &async_loop, coroutine.into_bound(py)));
     match await_python_future(handler, future_res).await {
         Ok(response) => {
-            spawn_background_tasks(&rt_handle, bg_tasks);
+            spawn_background_tasks(&background_tasks, &rt_handle, bg_tasks);
             response
         }
         Err(err_resp) => err_resp,
@@ -549,10 +629,11 @@ async fn core_async_sync_deps<const NEEDS_REQ: bool>(
     request_parts: Parts,
     param_ranges: SmallVec<[PathParamRange; 4]>,
     payload: Option<BodyPayload>,
+    background_tasks: BackgroundTrackers,
 ) -> Response {
     let handler_clone = handler.clone();
     let setup_result = rt_handle
-        .spawn_blocking(move || {
+        .spawn_blocking(in_current_context(move || {
             Python::attach(
                 |py| -> Result<
                     (
@@ -591,7 +672,7 @@ async fn core_async_sync_deps<const NEEDS_REQ: bool>(
                     Ok((coroutine, bg_tasks))
                 },
             )
-        })
+        }))
         .await
         .unwrap_or_else(|_| Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()));
 
@@ -605,7 +686,7 @@ async fn core_async_sync_deps<const NEEDS_REQ: bool>(
 
     match await_python_future(handler, future_res).await {
         Ok(response) => {
-            spawn_background_tasks(&rt_handle, bg_tasks);
+            spawn_background_tasks(&background_tasks, &rt_handle, bg_tasks);
             response
         }
         Err(err_resp) => err_resp,
@@ -619,6 +700,7 @@ async fn core_async_async_deps<const NEEDS_REQ: bool>(
     request_parts: Parts,
     param_ranges: SmallVec<[PathParamRange; 4]>,
     payload: Option<BodyPayload>,
+    background_tasks: BackgroundTrackers,
 ) -> Response {
     let handler_clone = handler.clone();
     let prep_parts = request_parts.clone();
@@ -626,7 +708,7 @@ async fn core_async_async_deps<const NEEDS_REQ: bool>(
     let prep_payload = payload.clone();
 
     let prep_res = rt_handle
-        .spawn_blocking(move || {
+        .spawn_blocking(in_current_context(move || {
             Python::attach(
                 |py| -> Result<
                     (
@@ -655,7 +737,7 @@ async fn core_async_async_deps<const NEEDS_REQ: bool>(
                     Ok((req_obj, kwargs.unbind(), bg_tasks))
                 },
             )
-        })
+        }))
         .await
         .unwrap_or_else(|_| Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()));
 
@@ -665,12 +747,15 @@ async fn core_async_async_deps<const NEEDS_REQ: bool>(
     };
 
     let request_input = build_request_input_from_parts(&request_parts, &param_ranges);
-    let dependency_results = match dependencies::execute_dependencies(
-        rt_handle.clone(),
-        &async_loop,
-        &handler.dependencies,
-        &request_input,
-        request_object,
+    let dependency_results = match in_child_span(
+        "execute_dependencies",
+        dependencies::execute_dependencies(
+            rt_handle.clone(),
+            &async_loop,
+            &handler.dependencies,
+            &request_input,
+            request_object,
+        ),
     )
     .await
     {
@@ -686,7 +771,7 @@ async fn core_async_async_deps<const NEEDS_REQ: bool>(
 
     let handler_clone2 = handler.clone();
     let setup_result = rt_handle
-        .spawn_blocking(move || {
+        .spawn_blocking(in_current_context(move || {
             Python::attach(|py| -> Result<Py<PyAny>, Response> {
                 let kwargs = kwargs_unbind.bind(py);
 
@@ -705,7 +790,7 @@ async fn core_async_async_deps<const NEEDS_REQ: bool>(
                     .map(Bound::unbind)
                     .map_err(|err| python_error_to_response(py, err))
             })
-        })
+        }))
         .await
         .unwrap_or_else(|_| Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()));
 
@@ -718,7 +803,7 @@ async fn core_async_async_deps<const NEEDS_REQ: bool>(
         Python::attach(|py| into_asyncio_future(py, &async_loop, coroutine.into_bound(py)));
     match await_python_future(handler, future_res).await {
         Ok(response) => {
-            spawn_background_tasks(&rt_handle, bg_tasks);
+            spawn_background_tasks(&background_tasks, &rt_handle, bg_tasks);
             response
         }
         Err(err_resp) => err_resp,
@@ -751,11 +836,19 @@ pub async fn run_py_handler(
     rt_handle: tokio::runtime::Handle,
     async_loop: Arc<Py<PyAny>>,
     sync_to_threadpool: bool,
+    background_tasks: TaskTracker,
     handler: Arc<RouteHandler>,
     request_parts: Parts,
     param_ranges: SmallVec<[PathParamRange; 4]>,
     payload: Option<BodyPayload>,
 ) -> Response {
+    let background_tasks = BackgroundTrackers {
+        app: background_tasks,
+        request: request_parts
+            .extensions
+            .get::<RequestTasks>()
+            .map(|tasks| tasks.0.clone()),
+    };
     match handler.execution_mode {
         ExecutionMode::SyncNoArgs => {
             core_sync_no_args(rt_handle, handler, sync_to_threadpool).await
@@ -770,6 +863,7 @@ pub async fn run_py_handler(
                 param_ranges,
                 payload,
                 sync_to_threadpool,
+                background_tasks,
             )
             .await
         }
@@ -781,6 +875,7 @@ pub async fn run_py_handler(
                 param_ranges,
                 payload,
                 sync_to_threadpool,
+                background_tasks,
             )
             .await
         }
@@ -792,6 +887,7 @@ pub async fn run_py_handler(
                 param_ranges,
                 payload,
                 sync_to_threadpool,
+                background_tasks,
             )
             .await
         }
@@ -803,6 +899,7 @@ pub async fn run_py_handler(
                 request_parts,
                 param_ranges,
                 payload,
+                background_tasks,
             )
             .await
         }
@@ -814,6 +911,7 @@ pub async fn run_py_handler(
                 request_parts,
                 param_ranges,
                 payload,
+                background_tasks,
             )
             .await
         }
@@ -825,6 +923,7 @@ pub async fn run_py_handler(
                 request_parts,
                 param_ranges,
                 payload,
+                background_tasks,
             )
             .await
         }
@@ -836,6 +935,7 @@ pub async fn run_py_handler(
                 request_parts,
                 param_ranges,
                 payload,
+                background_tasks,
             )
             .await
         }
@@ -847,6 +947,7 @@ pub async fn run_py_handler(
                 request_parts,
                 param_ranges,
                 payload,
+                background_tasks,
             )
             .await
         }
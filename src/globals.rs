@@ -2,7 +2,11 @@ use crate::http::middleware::PyMiddleware;
 use papaya::HashMap as PapayaHashMap;
 use pyo3::prelude::*;
 use pyo3::types::PyType;
+use serde::Deserialize;
+use std::fmt::Display;
+use std::str::FromStr;
 use std::sync::{Arc, LazyLock, OnceLock, atomic::AtomicUsize};
+use std::time::Duration;
 
 pub static MIDDLEWARES: LazyLock<PapayaHashMap<String, Arc<PyMiddleware>>> =
     LazyLock::new(|| PapayaHashMap::with_capacity(16));
@@ -11,26 +15,248 @@ pub static MIDDLEWARE_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
 pub static BASEMODEL_TYPE: OnceLock<Py<PyType>> = OnceLock::new();
 
+/// Sizing of `PYTHON_RUNTIME`, which is shared by every app in the process
+/// and cannot be resized once started.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RuntimeOptions {
+    pub(crate) worker_threads: Option<usize>,
+    pub(crate) max_blocking_threads: Option<usize>,
+    pub(crate) thread_keep_alive: Option<Duration>,
+    pub(crate) thread_name: Option<String>,
+}
+
+impl RuntimeOptions {
+    fn from_settings() -> Self {
+        let settings = settings();
+        Self {
+            worker_threads: settings.worker_threads,
+            max_blocking_threads: settings.max_blocking_threads,
+            thread_keep_alive: settings.thread_keep_alive.map(Duration::from_secs_f64),
+            thread_name: settings.thread_name.clone(),
+        }
+    }
+}
+
+static RUNTIME_OPTIONS: OnceLock<RuntimeOptions> = OnceLock::new();
+
+/// Sizes the runtime before its first use. Like the log subscriber this is
+/// process-wide, so once the runtime is running other options are refused.
+pub(crate) fn configure_runtime(options: RuntimeOptions) -> Result<(), String> {
+    let current = RUNTIME_OPTIONS.get_or_init(|| options.clone());
+    if *current == options {
+        Ok(())
+    } else {
+        Err(format!(
+            "the Python runtime is already configured with {current:?}; ignoring {options:?}"
+        ))
+    }
+}
+
 pub static PYTHON_RUNTIME: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
-    let cpus = std::thread::available_parallelism()
-        .map(|n| n.get())
-        .unwrap_or(4);
-
-    tokio::runtime::Builder::new_multi_thread()
-        .worker_threads(cpus.clamp(4, 16))
-        .thread_name("python-handler")
-        .enable_all()
-        .build()
-        .expect("Failed to create Python runtime")
+    let options = RUNTIME_OPTIONS.get_or_init(RuntimeOptions::from_settings);
+    let worker_threads = options.worker_threads.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(4)
+            .clamp(4, 16)
+    });
+
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder
+        .worker_threads(worker_threads)
+        .thread_name(options.thread_name.as_deref().unwrap_or("python-handler"))
+        .enable_all();
+    if let Some(max_blocking_threads) = options.max_blocking_threads {
+        builder.max_blocking_threads(max_blocking_threads);
+    }
+    if let Some(keep_alive) = options.thread_keep_alive {
+        builder.thread_keep_alive(keep_alive);
+    }
+    builder.build().expect("Failed to create Python runtime")
 });
 
 // Config
-#[derive(Debug, Default)]
-pub struct Config {
-    // TODO: fields later
+macro_rules! config_fields {
+    ($($(#[$meta:meta])* $field:ident: $ty:ty),* $(,)?) => {
+        /// Defaults for the `FastrAPI(...)`, `serve()` and `start()` arguments.
+        /// Unset fields keep the built-in default.
+        #[derive(Debug, Default, Clone, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        pub struct Config {
+            $($(#[$meta])* pub $field: Option<$ty>,)*
+        }
+
+        impl Config {
+            fn merge(&mut self, other: Self) {
+                $(if other.$field.is_some() {
+                    self.$field = other.$field;
+                })*
+            }
+
+            fn from_env() -> Result<Self, String> {
+                Ok(Self {
+                    $($field: env_setting(stringify!($field))?,)*
+                })
+            }
+        }
+    };
+}
+
+config_fields! {
+    host: String,
+    port: u16,
+    workers: usize,
+    graceful_timeout: f64,
+    request_timeout: u64,
+    max_body_size: usize,
+    max_field_size: usize,
+    max_file_size: usize,
+    max_sync_workers: usize,
+    sync_queue_size: usize,
+    worker_threads: usize,
+    max_blocking_threads: usize,
+    /// Seconds an idle blocking thread is kept around.
+    thread_keep_alive: f64,
+    thread_name: String,
+    log_level: String,
+    log_format: String,
+    ssl_certfile: String,
+    ssl_keyfile: String,
+    /// An empty string disables the page, like passing `None`; same for
+    /// `redoc_url` and `openapi_url`.
+    docs_url: String,
+    redoc_url: String,
+    openapi_url: String,
+}
+
+/// `FASTRAPI_<FIELD>`, e.g. `FASTRAPI_MAX_BODY_SIZE`.
+fn env_setting<T>(field: &str) -> Result<Option<T>, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let key = format!("FASTRAPI_{}", field.to_ascii_uppercase());
+    match std::env::var(&key) {
+        Ok(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|err| format!("{key}: invalid value '{value}': {err}")),
+        Err(_) => Ok(None),
+    }
+}
+
+fn read_toml(path: &str) -> Result<Option<toml::Table>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => text
+            .parse::<toml::Table>()
+            .map(Some)
+            .map_err(|err| format!("{path}: {err}")),
+        Err(_) => Ok(None),
+    }
+}
+
+impl Config {
+    /// Lowest precedence first: `[tool.fastrapi]` in pyproject.toml,
+    /// fastrapi.toml, then `FASTRAPI_*` environment variables. Both files are
+    /// read from the working directory.
+    fn load() -> Result<Self, String> {
+        let mut config = Self::default();
+
+        if let Some(mut pyproject) = read_toml("pyproject.toml")?
+            && let Some(toml::Value::Table(mut tool)) = pyproject.remove("tool")
+            && let Some(section) = tool.remove("fastrapi")
+        {
+            config.merge(
+                section
+                    .try_into()
+                    .map_err(|err| format!("pyproject.toml [tool.fastrapi]: {err}"))?,
+            );
+        }
+        if let Some(table) = read_toml("fastrapi.toml")? {
+            config.merge(
+                table
+                    .try_into()
+                    .map_err(|err| format!("fastrapi.toml: {err}"))?,
+            );
+        }
+        config.merge(Self::from_env()?);
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.workers == Some(0) {
+            return Err("workers must be at least 1".to_string());
+        }
+        if let Some(timeout) = self.graceful_timeout
+            && (!timeout.is_finite() || timeout < 0.0)
+        {
+            return Err("graceful_timeout must be a non-negative number of seconds".to_string());
+        }
+        for (name, value) in [
+            ("max_sync_workers", self.max_sync_workers),
+            ("worker_threads", self.worker_threads),
+            ("max_blocking_threads", self.max_blocking_threads),
+        ] {
+            if value == Some(0) {
+                return Err(format!("{name} must be at least 1"));
+            }
+        }
+        if let Some(keep_alive) = self.thread_keep_alive
+            && (!keep_alive.is_finite() || keep_alive < 0.0)
+        {
+            return Err("thread_keep_alive must be a non-negative number of seconds".to_string());
+        }
+        if let Some(level) = &self.log_level
+            && !matches!(
+                level.to_ascii_lowercase().as_str(),
+                "trace" | "debug" | "info" | "warn" | "warning" | "error" | "critical" | "off"
+            )
+        {
+            return Err(format!(
+                "log_level must be one of trace, debug, info, warning, error, critical or off, got '{level}'"
+            ));
+        }
+        if let Some(format) = &self.log_format
+            && format != "text"
+            && format != "json"
+        {
+            return Err(format!(
+                "log_format must be 'text' or 'json', got '{format}'"
+            ));
+        }
+        if self.ssl_certfile.is_some() != self.ssl_keyfile.is_some() {
+            return Err("ssl_certfile and ssl_keyfile must be set together".to_string());
+        }
+        for (name, url) in [
+            ("docs_url", &self.docs_url),
+            ("redoc_url", &self.redoc_url),
+            ("openapi_url", &self.openapi_url),
+        ] {
+            if let Some(url) = url
+                && !url.is_empty()
+                && !url.starts_with('/')
+            {
+                return Err(format!("{name} must start with '/', got '{url}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+static CONFIG: LazyLock<Result<Config, String>> = LazyLock::new(Config::load);
+
+/// The merged configuration, loaded on first use.
+pub fn config() -> Result<&'static Config, String> {
+    CONFIG
+        .as_ref()
+        .map_err(|err| format!("invalid fastrapi configuration: {err}"))
 }
 
-pub fn config() -> &'static Config {
-    static CONFIG: std::sync::LazyLock<Config> = std::sync::LazyLock::new(Config::default);
-    &CONFIG
+/// `config()` for argument defaults: an invalid configuration reads as empty
+/// here and is reported when the app is created or served.
+pub(crate) fn settings() -> &'static Config {
+    static EMPTY: LazyLock<Config> = LazyLock::new(Config::default);
+    CONFIG.as_ref().unwrap_or(&EMPTY)
 }
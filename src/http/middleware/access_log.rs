@@ -0,0 +1,423 @@
+use crate::routing::types::MatchedRoute;
+use axum::{
+    body::{Body, HttpBody},
+    extract::{ConnectInfo, MatchedPath, Request},
+    http::{
+        HeaderMap, HeaderName, Version,
+        header::{REFERER, USER_AGENT},
+    },
+    middleware::Next,
+    response::Response,
+};
+use futures_util::StreamExt;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::{
+    collections::BTreeMap,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::info;
+
+const COMMON_FORMAT: &str =
+    r#"{client} - - [{time}] "{method} {target} {protocol}" {status} {bytes}"#;
+const COMBINED_FORMAT: &str = r#"{client} - - [{time}] "{method} {target} {protocol}" {status} {bytes} "{referer}" "{user_agent}""#;
+
+/// Logs one line per response on the `fastrapi::access` target.
+///
+/// `format` is `"common"`, `"combined"`, `"json"` or a template using
+/// `{client}`, `{time}`, `{method}`, `{path}`, `{query}`, `{target}`,
+/// `{protocol}`, `{status}`, `{bytes}`, `{duration_ms}`, `{route}`,
+/// `{request_id}`, `{user_agent}` and `{referer}`.
+#[pyclass(name = "AccessLogMiddleware", skip_from_py_object)]
+#[derive(Clone, Debug)]
+pub struct AccessLogMiddleware {
+    pub format: String,
+    pub exclude_paths: Vec<String>,
+    pub sample_rate: f64,
+}
+
+impl Default for AccessLogMiddleware {
+    fn default() -> Self {
+        Self {
+            format: "combined".to_string(),
+            exclude_paths: Vec::new(),
+            sample_rate: 1.0,
+        }
+    }
+}
+
+#[pymethods]
+impl AccessLogMiddleware {
+    #[new]
+    #[pyo3(signature = (format="combined".to_string(), exclude_paths=None, sample_rate=1.0))]
+    fn new(format: String, exclude_paths: Option<Vec<String>>, sample_rate: f64) -> PyResult<Self> {
+        let config = Self {
+            format,
+            exclude_paths: exclude_paths.unwrap_or_default(),
+            sample_rate,
+        };
+        AccessLogger::new(&config, None)?;
+        Ok(config)
+    }
+}
+
+pub fn parse_access_log_params(kwargs: &Bound<'_, PyDict>) -> PyResult<AccessLogMiddleware> {
+    let mut config = AccessLogMiddleware::default();
+    set_field!(kwargs, config, "format", format: String);
+    set_field!(kwargs, config, "exclude_paths", exclude_paths: Vec<String>);
+    set_field!(kwargs, config, "sample_rate", sample_rate: f64);
+    AccessLogger::new(&config, None)?;
+    Ok(config)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    Client,
+    Time,
+    Method,
+    Path,
+    Query,
+    Target,
+    Protocol,
+    Status,
+    Bytes,
+    DurationMs,
+    Route,
+    RequestId,
+    UserAgent,
+    Referer,
+}
+
+impl Field {
+    const ALL: [(&'static str, Field); 14] = [
+        ("client", Field::Client),
+        ("time", Field::Time),
+        ("method", Field::Method),
+        ("path", Field::Path),
+        ("query", Field::Query),
+        ("target", Field::Target),
+        ("protocol", Field::Protocol),
+        ("status", Field::Status),
+        ("bytes", Field::Bytes),
+        ("duration_ms", Field::DurationMs),
+        ("route", Field::Route),
+        ("request_id", Field::RequestId),
+        ("user_agent", Field::UserAgent),
+        ("referer", Field::Referer),
+    ];
+}
+
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+enum LineFormat {
+    Template(Vec<Segment>),
+    Json,
+}
+
+fn parse_template(template: &str) -> PyResult<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            segments.push(Segment::Literal(rest[..start].to_string()));
+        }
+        let Some(end) = rest[start..].find('}') else {
+            return Err(PyValueError::new_err(format!(
+                "unclosed '{{' in access log format '{template}'"
+            )));
+        };
+        let name = &rest[start + 1..start + end];
+        let Some((_, field)) = Field::ALL.iter().find(|(known, _)| *known == name) else {
+            return Err(PyValueError::new_err(format!(
+                "unknown access log field '{{{name}}}'"
+            )));
+        };
+        segments.push(Segment::Field(*field));
+        rest = &rest[start + end + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest.to_string()));
+    }
+    Ok(segments)
+}
+
+/// Compiled form of `AccessLogMiddleware` shared by every request.
+pub struct AccessLogger {
+    format: LineFormat,
+    exclude: GlobSet,
+    sample_rate: f64,
+    seen: AtomicU64,
+    request_id_header: Option<HeaderName>,
+}
+
+impl AccessLogger {
+    pub fn new(
+        config: &AccessLogMiddleware,
+        request_id_header: Option<HeaderName>,
+    ) -> PyResult<Self> {
+        let format = match config.format.as_str() {
+            "common" => LineFormat::Template(parse_template(COMMON_FORMAT)?),
+            "combined" => LineFormat::Template(parse_template(COMBINED_FORMAT)?),
+            "json" => LineFormat::Json,
+            template => LineFormat::Template(parse_template(template)?),
+        };
+
+        let mut exclude = GlobSetBuilder::new();
+        for pattern in &config.exclude_paths {
+            let glob = Glob::new(pattern).map_err(|err| {
+                PyValueError::new_err(format!("invalid exclude_paths pattern '{pattern}': {err}"))
+            })?;
+            exclude.add(glob);
+        }
+        let exclude = exclude
+            .build()
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+        if !(0.0..=1.0).contains(&config.sample_rate) {
+            return Err(PyValueError::new_err(
+                "sample_rate must be between 0.0 and 1.0",
+            ));
+        }
+
+        Ok(Self {
+            format,
+            exclude,
+            sample_rate: config.sample_rate,
+            seen: AtomicU64::new(0),
+            request_id_header,
+        })
+    }
+
+    /// Sampling is evenly spaced rather than random: with 0.25 every fourth
+    /// request is logged.
+    fn sampled(&self) -> bool {
+        if self.sample_rate >= 1.0 {
+            return true;
+        }
+        let n = self.seen.fetch_add(1, Ordering::Relaxed) as f64;
+        (n * self.sample_rate).floor() != ((n + 1.0) * self.sample_rate).floor()
+    }
+
+    fn emit(&self, record: &AccessRecord) {
+        let line = match &self.format {
+            LineFormat::Template(segments) => {
+                let mut line = String::with_capacity(128);
+                for segment in segments {
+                    match segment {
+                        Segment::Literal(text) => line.push_str(text),
+                        Segment::Field(field) => {
+                            line.push_str(record.text(*field).as_deref().unwrap_or("-"))
+                        }
+                    }
+                }
+                line
+            }
+            LineFormat::Json => {
+                let object: BTreeMap<&str, sonic_rs::Value> = Field::ALL
+                    .iter()
+                    .filter(|(_, field)| *field != Field::Target)
+                    .map(|(name, field)| (*name, record.json(*field)))
+                    .collect();
+                sonic_rs::to_string(&object).unwrap_or_default()
+            }
+        };
+        info!(target: "fastrapi::access", "{}", line);
+    }
+}
+
+struct AccessRecord {
+    client: Option<SocketAddr>,
+    time: SystemTime,
+    method: String,
+    path: String,
+    query: Option<String>,
+    protocol: Version,
+    status: u16,
+    bytes: u64,
+    duration: Duration,
+    route: Option<Arc<str>>,
+    request_id: Option<String>,
+    user_agent: Option<String>,
+    referer: Option<String>,
+}
+
+impl AccessRecord {
+    fn json(&self, field: Field) -> sonic_rs::Value {
+        match field {
+            Field::Status => sonic_rs::json!(self.status),
+            Field::Bytes => sonic_rs::json!(self.bytes),
+            Field::DurationMs => sonic_rs::json!(self.duration.as_secs_f64() * 1000.0),
+            _ => match self.text(field) {
+                Some(text) => sonic_rs::json!(text),
+                None => sonic_rs::json!(null),
+            },
+        }
+    }
+
+    fn text(&self, field: Field) -> Option<String> {
+        match field {
+            Field::Client => self.client.map(|addr| addr.ip().to_string()),
+            Field::Time => Some(clf_time(self.time)),
+            Field::Method => Some(self.method.clone()),
+            Field::Path => Some(self.path.clone()),
+            Field::Query => self.query.clone(),
+            Field::Target => Some(match &self.query {
+                Some(query) => format!("{}?{}", self.path, query),
+                None => self.path.clone(),
+            }),
+            Field::Protocol => Some(format!("{:?}", self.protocol)),
+            Field::Status => Some(self.status.to_string()),
+            Field::Bytes => Some(self.bytes.to_string()),
+            Field::DurationMs => Some(format!("{:.3}", self.duration.as_secs_f64() * 1000.0)),
+            Field::Route => self.route.as_deref().map(str::to_string),
+            Field::RequestId => self.request_id.clone(),
+            Field::UserAgent => self.user_agent.clone(),
+            Field::Referer => self.referer.clone(),
+        }
+    }
+}
+
+/// `10/Oct/2000:13:55:36 +0000`, always in UTC.
+fn clf_time(time: SystemTime) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let (days, secs_of_day) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // civil-from-days (Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    )
+}
+
+fn header_string(headers: &HeaderMap, name: &HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Logs once the body has been sent, or when it is dropped early because the
+/// client went away.
+struct PendingRecord {
+    logger: Arc<AccessLogger>,
+    record: AccessRecord,
+    started: Instant,
+}
+
+impl Drop for PendingRecord {
+    fn drop(&mut self) {
+        self.record.duration = self.started.elapsed();
+        self.logger.emit(&self.record);
+    }
+}
+
+pub async fn access_log(logger: Arc<AccessLogger>, req: Request, next: Next) -> Response {
+    let path = req.uri().path();
+    if logger.exclude.is_match(path) || !logger.sampled() {
+        return next.run(req).await;
+    }
+
+    let started = Instant::now();
+    let time = SystemTime::now();
+    let client = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| *addr);
+    let matched_path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched| Arc::from(matched.as_str()));
+    let method = req.method().to_string();
+    let path = path.to_string();
+    let query = req.uri().query().map(str::to_string);
+    let protocol = req.version();
+    let user_agent = header_string(req.headers(), &USER_AGENT);
+    let referer = header_string(req.headers(), &REFERER);
+    let request_id = logger
+        .request_id_header
+        .as_ref()
+        .and_then(|name| header_string(req.headers(), name));
+
+    let response = next.run(req).await;
+
+    let (parts, body) = response.into_parts();
+    let route = matched_path.or_else(|| {
+        parts
+            .extensions
+            .get::<MatchedRoute>()
+            .map(|MatchedRoute(route)| route.clone())
+    });
+    // a generated ID only exists on the response
+    let request_id = request_id.or_else(|| {
+        logger
+            .request_id_header
+            .as_ref()
+            .and_then(|name| header_string(&parts.headers, name))
+    });
+    let exact_size = body.size_hint().exact();
+
+    let mut pending = PendingRecord {
+        logger,
+        record: AccessRecord {
+            client,
+            time,
+            method,
+            path,
+            query,
+            protocol,
+            status: parts.status.as_u16(),
+            bytes: exact_size.unwrap_or_default(),
+            duration: Duration::ZERO,
+            route,
+            request_id,
+            user_agent,
+            referer,
+        },
+        started,
+    };
+
+    // sized bodies are logged right away so hyper keeps its Content-Length
+    if exact_size.is_some() {
+        drop(pending);
+        return Response::from_parts(parts, body);
+    }
+
+    let body = body.into_data_stream().map(move |chunk| {
+        if let Ok(chunk) = &chunk {
+            pending.record.bytes += chunk.len() as u64;
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
@@ -32,6 +32,7 @@ use pyo3::types::{PyAny, PyDict};
 use std::sync::Arc;
 use tracing::error;
 
+mod access_log;
 pub mod cors;
 pub mod gzip;
 pub mod httpsredirect;
@@ -39,6 +40,7 @@ mod rate_limit;
 mod session;
 mod trustedhost;
 
+pub use access_log::{AccessLogMiddleware, AccessLogger, access_log, parse_access_log_params};
 pub use cors::{CORSMiddleware, build_cors_layer, parse_cors_params};
 pub use gzip::{GZipMiddleware, parse_gzip_params};
 pub use httpsredirect::{HTTPSRedirectMiddleware, parse_https_redirect_params};
@@ -1,8 +1,10 @@
-use pyo3::exceptions::PyValueError;
+use axum::body::{Body, BodyDataStream};
+use futures_util::StreamExt;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::{PyAny, PyBytes, PyDict};
 use std::sync::Arc;
-use tokio::sync::OnceCell;
+use tokio::sync::{Mutex, OnceCell};
 
 #[pyclass(name = "ClientInfo", module = "fastrapi.request", skip_from_py_object)]
 #[derive(Clone)]
@@ -61,10 +63,10 @@ pub struct PyRequest {
 }
 
 impl PyRequest {
-    pub fn from_scope(py: Python<'_>, scope: Py<PyAny>) -> Self {
+    pub fn from_scope(py: Python<'_>, scope: Py<PyAny>, receive: Option<Py<PyAny>>) -> Self {
         Self {
             scope,
-            receive: py.None(),
+            receive: receive.unwrap_or_else(|| py.None()),
             send: py.None(),
             _body: Arc::new(OnceCell::new()),
         }
@@ -293,6 +295,60 @@ impl PyRequest {
     }
 }
 
+/// Request body left for `Request.receive()` when no body parameter consumed it.
+#[derive(Clone)]
+pub struct PendingBody(Arc<Mutex<Option<BodyDataStream>>>);
+
+impl PendingBody {
+    pub fn new(body: Body) -> Self {
+        Self(Arc::new(Mutex::new(Some(body.into_data_stream()))))
+    }
+}
+
+/// ASGI `receive` callable backed by a `PendingBody`: yields the body as
+/// `http.request` messages, then `http.disconnect` once it is exhausted.
+#[pyclass(name = "BodyReceiver", module = "fastrapi.request", skip_from_py_object)]
+pub struct PyBodyReceiver {
+    pub body: PendingBody,
+}
+
+#[pymethods]
+impl PyBodyReceiver {
+    fn __call__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let body = self.body.0.clone();
+
+        rsloop::rust_async::future_into_py(py, async move {
+            let mut stream = body.lock().await;
+            let chunk = match stream.as_mut() {
+                Some(data) => data.next().await,
+                None => {
+                    return Python::attach(|py| {
+                        let message = PyDict::new(py);
+                        message.set_item("type", "http.disconnect")?;
+                        Ok(message.into_any().unbind())
+                    });
+                }
+            };
+            let (chunk, more_body) = match chunk {
+                Some(Ok(chunk)) => (chunk, true),
+                Some(Err(err)) => return Err(PyRuntimeError::new_err(err.to_string())),
+                None => {
+                    *stream = None;
+                    (Default::default(), false)
+                }
+            };
+
+            Python::attach(|py| {
+                let message = PyDict::new(py);
+                message.set_item("type", "http.request")?;
+                message.set_item("body", PyBytes::new(py, &chunk))?;
+                message.set_item("more_body", more_body)?;
+                Ok(message.into_any().unbind())
+            })
+        })
+    }
+}
+
 #[pyclass(name = "HTTPConnection", module = "fastrapi.request")]
 pub struct PyHTTPConnection {
     #[pyo3(get)]
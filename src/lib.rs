@@ -16,6 +16,7 @@ pub mod utils;
 
 pub use engine::app;
 pub use engine::background;
+pub use engine::cli;
 pub use engine::server;
 pub use ffi::datastructures;
 pub use ffi::decorators;
@@ -52,14 +53,17 @@ use exceptions::{
     PyResponseValidationError, PyValidationException, PyWebSocketException,
 };
 use middleware::{
-    CORSMiddleware, GZipMiddleware, HTTPSRedirectMiddleware, SessionMiddleware,
-    TrustedHostMiddleware,
+    AccessLogMiddleware, CORSMiddleware, GZipMiddleware, HTTPSRedirectMiddleware,
+    SessionMiddleware, TrustedHostMiddleware,
 };
 use params::{
     PyBody, PyCookie, PyDepends, PyFile, PyForm, PyHeader, PyPath, PyQuery, PySecurity, Undefined,
     Unset,
 };
+use routing::introspection::{PyRoute, PyRouteParam, PyWebSocketRoute};
 use routing::prometheus::PyInstrumentator;
+use server::handle::PyServerHandle;
+use server::testclient::{PyTestClient, PyTestHeaders, PyTestResponse, PyWebSocketTestSession};
 use staticfiles::PyStaticFiles;
 use websocket::PyWebSocket;
 
@@ -140,6 +144,16 @@ fn fastrapi(m: &Bound<'_, PyModule>) -> PyResult<()> {
         )
     );
     submodule!(m, "staticfiles", add_classes!(PyStaticFiles));
+    submodule!(
+        m,
+        "routing",
+        add_classes!(PyRoute, PyWebSocketRoute, PyRouteParam)
+    );
+    let routing_module = m.getattr("routing")?.cast_into::<PyModule>()?;
+    routing_module.add_function(wrap_pyfunction!(
+        routing::converters::register_converter,
+        &routing_module
+    )?)?;
     submodule!(
         m,
         "middleware",
@@ -148,7 +162,8 @@ fn fastrapi(m: &Bound<'_, PyModule>) -> PyResult<()> {
             HTTPSRedirectMiddleware,
             TrustedHostMiddleware,
             GZipMiddleware,
-            SessionMiddleware
+            SessionMiddleware,
+            AccessLogMiddleware
         )
     );
 
@@ -159,8 +174,20 @@ fn fastrapi(m: &Bound<'_, PyModule>) -> PyResult<()> {
         m.getattr("prometheus")?.getattr("Instrumentator")?,
     )?;
     submodule!(m, "websocket", add_classes!(PyWebSocket));
+    submodule!(
+        m,
+        "testclient",
+        add_classes!(
+            PyTestClient,
+            PyTestResponse,
+            PyTestHeaders,
+            PyWebSocketTestSession
+        )
+    );
 
     status::create_status_submodule(m)?;
+    server::telemetry::create_tracing_submodule(m)?;
+    cli::create_cli_submodule(m)?;
     pydantic::register_pydantic_integration(m)?;
     register_rsloop_asyncio_alias(m)?;
 
@@ -212,6 +239,7 @@ fn fastrapi(m: &Bound<'_, PyModule>) -> PyResult<()> {
         m.getattr("datastructures")?.getattr("UploadFile")?,
     )?;
     m.add_class::<PyAPIRouter>()?;
+    m.add_class::<PyServerHandle>()?;
 
     Ok(())
 }
@@ -31,6 +31,10 @@ macro_rules! generate_http_methods {
             fn head(&self, py: pyo3::prelude::Python<'_>, path: String, kwargs: Option<&pyo3::Bound<'_, pyo3::types::PyDict>>) -> pyo3::prelude::PyResult<pyo3::prelude::Py<pyo3::prelude::PyAny>> {
                 self.$get_router(py).create_method_decorator_kw(py, $crate::routing::types::HttpMethod::HEAD, path, kwargs)
             }
+            #[pyo3(signature = (path, methods=None, **kwargs), text_signature = "(self, path, methods=None, *, response_model=None, status_code=None, tags=None, dependencies=None, summary=None, description=None, response_description=None, responses=None, deprecated=None, operation_id=None, response_model_include=None, response_model_exclude=None, response_model_by_alias=True, response_model_exclude_unset=False, response_model_exclude_defaults=False, response_model_exclude_none=False, include_in_schema=True, response_class=None, name=None, callbacks=None, openapi_extra=None, generate_unique_id_function=None, cache_resp=False, rate_limit=None)")]
+            fn api_route(&self, py: pyo3::prelude::Python<'_>, path: String, methods: Option<&pyo3::Bound<'_, pyo3::PyAny>>, kwargs: Option<&pyo3::Bound<'_, pyo3::types::PyDict>>) -> pyo3::prelude::PyResult<pyo3::prelude::Py<pyo3::prelude::PyAny>> {
+                self.$get_router(py).create_api_route_decorator(py, path, methods, kwargs)
+            }
         }
     }
 }
@@ -0,0 +1,178 @@
+use ahash::AHashMap;
+use pyo3::{exceptions::PyValueError, prelude::*};
+use regex::Regex;
+use smallvec::SmallVec;
+use std::{
+    borrow::Cow,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+/// What a `{name:converter}` path parameter accepts. A bare `{name}` is
+/// `Str`.
+#[derive(Clone, Debug)]
+pub enum Converter {
+    Str,
+    Path,
+    Int,
+    Float,
+    Uuid,
+    Regex(Arc<Regex>),
+}
+
+impl Converter {
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "str" => Some(Self::Str),
+            "path" => Some(Self::Path),
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "uuid" => Some(Self::Uuid),
+            _ => None,
+        }
+    }
+
+    pub fn lookup(name: &str) -> Option<Self> {
+        Self::builtin(name).or_else(|| {
+            CUSTOM_CONVERTERS
+                .read()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .map(Self::Regex)
+        })
+    }
+
+    #[inline]
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            Self::Str | Self::Path => true,
+            Self::Int => is_digits(value),
+            Self::Float => match value.split_once('.') {
+                Some((whole, fraction)) => is_digits(whole) && is_digits(fraction),
+                None => is_digits(value),
+            },
+            Self::Uuid => is_uuid(value),
+            Self::Regex(regex) => regex.is_match(value),
+        }
+    }
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Lowercase hyphenated UUIDs only, as Starlette's `uuid` converter.
+fn is_uuid(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 36
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_digit() || (b'a'..=b'f').contains(b),
+        })
+}
+
+static CUSTOM_CONVERTERS: LazyLock<RwLock<AHashMap<String, Arc<Regex>>>> =
+    LazyLock::new(|| RwLock::new(AHashMap::new()));
+
+/// Registers a path converter matching `regex`, usable as `{name:<name>}` in
+/// routes declared afterwards. The value reaches the handler as a string, so
+/// the parameter annotation still decides its type.
+#[pyfunction]
+pub fn register_converter(name: &str, regex: &str) -> PyResult<()> {
+    if Converter::builtin(name).is_some() {
+        return Err(PyValueError::new_err(format!(
+            "'{name}' is a built-in path converter"
+        )));
+    }
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        return Err(PyValueError::new_err(format!(
+            "'{name}' is not a valid converter name"
+        )));
+    }
+    let regex = Regex::new(&format!("^(?:{regex})$"))
+        .map_err(|err| PyValueError::new_err(format!("invalid converter regex: {err}")))?;
+    CUSTOM_CONVERTERS
+        .write()
+        .unwrap()
+        .insert(name.to_string(), Arc::new(regex));
+    Ok(())
+}
+
+/// A route template split into the pattern handed to matchit, with its
+/// parameters renamed by position, and each parameter's name and converter.
+/// `plain` is the template with the converters dropped, as OpenAPI documents
+/// it.
+pub struct PathTemplate {
+    pub shape: String,
+    pub plain: String,
+    pub params: SmallVec<[(Box<str>, Converter); 4]>,
+}
+
+impl PathTemplate {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let mut shape = String::with_capacity(path.len());
+        let mut plain = String::with_capacity(path.len());
+        let mut params: SmallVec<[(Box<str>, Converter); 4]> = SmallVec::new();
+        let mut rest = path;
+
+        while let Some(start) = rest.find(['{', '}']) {
+            shape.push_str(&rest[..start]);
+            plain.push_str(&rest[..start]);
+            rest = &rest[start..];
+            // doubled braces are literal
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                shape.push_str(&rest[..2]);
+                plain.push_str(&rest[..2]);
+                rest = &rest[2..];
+                continue;
+            }
+            let Some(end) = rest.find('}').filter(|_| rest.starts_with('{')) else {
+                return Err(format!("unbalanced braces in path '{path}'"));
+            };
+            let (name, converter) = match rest[1..end].split_once(':') {
+                Some((name, converter)) => (
+                    name,
+                    Converter::lookup(converter).ok_or_else(|| {
+                        format!("unknown path converter '{converter}' in path '{path}'")
+                    })?,
+                ),
+                None => (&rest[1..end], Converter::Str),
+            };
+            rest = &rest[end + 1..];
+            if matches!(converter, Converter::Path) {
+                if !rest.is_empty() {
+                    return Err(format!("the path converter must end the path in '{path}'"));
+                }
+                shape.push_str(&format!("{{*p{}}}", params.len()));
+            } else {
+                shape.push_str(&format!("{{p{}}}", params.len()));
+            }
+            plain.push_str(&format!("{{{name}}}"));
+            params.push((Box::from(name), converter));
+        }
+        shape.push_str(rest);
+        plain.push_str(rest);
+        Ok(Self {
+            shape,
+            plain,
+            params,
+        })
+    }
+}
+
+/// Checks the template a route is declared with, so a typo in a converter
+/// fails there rather than leaving the route unreachable.
+pub fn check_converters(path: &str) -> Result<(), String> {
+    PathTemplate::parse(path.trim_end_matches('/')).map(|_| ())
+}
+
+/// The path as OpenAPI documents it, without converters.
+pub fn strip_converters(path: &str) -> Cow<'_, str> {
+    if !path.contains(':') {
+        return Cow::Borrowed(path);
+    }
+    match PathTemplate::parse(path) {
+        Ok(template) => Cow::Owned(template.plain),
+        Err(_) => Cow::Borrowed(path),
+    }
+}
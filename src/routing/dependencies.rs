@@ -1,6 +1,7 @@
 use super::params;
 use super::security::PySecurityScopes;
 use super::types::{ParsedParameter, RequestInput};
+use crate::engine::server::telemetry::{in_current_context, in_python_span};
 use crate::ffi::pydantic;
 use axum::response::Response;
 use pyo3::intern;
@@ -447,7 +448,7 @@ pub async fn execute_dependencies(
                 )?;
                 let bound_func = dep.func.bind(py);
                 let bound_kwargs = py_kwargs.bind(py);
-                let coroutine = bound_func.call((), Some(bound_kwargs))?;
+                let coroutine = in_python_span(py, bound_func.call((), Some(bound_kwargs))?)?;
                 let locals = rsloop::rust_async::TaskLocals::new(async_loop.bind(py).clone());
                 Ok(rsloop::rust_async::into_future_with_locals(
                     &locals, coroutine,
@@ -462,13 +463,13 @@ pub async fn execute_dependencies(
             let py_func = dep.func.clone();
 
             rt_handle
-                .spawn_blocking(move || {
+                .spawn_blocking(in_current_context(move || {
                     Python::attach(|py| -> Result<Py<PyAny>, DependencyExecutionError> {
                         let bound_func = py_func.bind(py);
                         let bound_kwargs = py_kwargs.bind(py);
                         Ok(bound_func.call((), Some(bound_kwargs))?.unbind())
                     })
-                })
+                }))
                 .await
                 .unwrap_or_else(|_| {
                     Err(DependencyExecutionError::Python(Python::attach(|_py| {
@@ -0,0 +1,124 @@
+use super::converters::Converter;
+use super::dependencies::InjectionType;
+use super::types::{ParamValue, ParameterSource, ParsedParameter, PathParamRange, RouteHandler};
+use regex::Regex;
+use smallvec::SmallVec;
+use std::sync::Arc;
+
+/// A `Host` header pattern such as `{tenant}.example.com`, matched without
+/// regard to case as host names are. Parameters take the same converters as
+/// path parameters; all but `path` and `float` stop at a dot.
+#[derive(Debug)]
+pub struct HostPattern {
+    pub source: Box<str>,
+    regex: Regex,
+    params: SmallVec<[(Box<str>, Converter); 2]>,
+}
+
+impl HostPattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let mut expr = String::from("(?i)^");
+        let mut params: SmallVec<[(Box<str>, Converter); 2]> = SmallVec::new();
+        let mut rest = pattern;
+
+        while let Some(start) = rest.find(['{', '}']) {
+            expr.push_str(&regex::escape(&rest[..start]));
+            rest = &rest[start..];
+            let Some(end) = rest.find('}').filter(|_| rest.starts_with('{')) else {
+                return Err(format!("unbalanced braces in host '{pattern}'"));
+            };
+            let (name, converter) = match rest[1..end].split_once(':') {
+                Some((name, converter)) => (
+                    name,
+                    Converter::lookup(converter).ok_or_else(|| {
+                        format!("unknown path converter '{converter}' in host '{pattern}'")
+                    })?,
+                ),
+                None => (&rest[1..end], Converter::Str),
+            };
+            if name.is_empty() {
+                return Err(format!("unnamed parameter in host '{pattern}'"));
+            }
+            expr.push_str(match converter {
+                Converter::Path => "(.+)",
+                Converter::Float => r"([0-9]+(?:\.[0-9]+)?)",
+                _ => r"([^.]+)",
+            });
+            params.push((Box::from(name), converter));
+            rest = &rest[end + 1..];
+        }
+        expr.push_str(&regex::escape(rest));
+        expr.push('$');
+
+        let regex = Regex::new(&expr).map_err(|err| format!("invalid host '{pattern}': {err}"))?;
+        Ok(Self {
+            source: Box::from(pattern),
+            regex,
+            params,
+        })
+    }
+
+    pub fn param_names(&self) -> impl Iterator<Item = &str> {
+        self.params.iter().map(|(name, _)| &**name)
+    }
+
+    /// The parameters of `host`, without its port, if the pattern matches
+    /// it. They are handed over as path parameters.
+    pub fn matches(&self, host: &str) -> Option<SmallVec<[PathParamRange; 4]>> {
+        let host = host.rsplit_once(':').map_or(host, |(name, port)| {
+            if port.bytes().all(|b| b.is_ascii_digit()) {
+                name
+            } else {
+                host
+            }
+        });
+        let caps = self.regex.captures(host)?;
+        self.params
+            .iter()
+            .zip(caps.iter().skip(1))
+            .map(|((name, converter), value)| {
+                let value = value?.as_str();
+                converter.matches(value).then(|| PathParamRange {
+                    key: name.to_string(),
+                    value: ParamValue::Host(Box::from(value)),
+                })
+            })
+            .collect()
+    }
+}
+
+/// `handler` with its parameters named after host parameters read from the
+/// host. They were declared before the router got its host, so without an
+/// explicit `Query()` they were taken for query parameters.
+pub fn bind_host_params(handler: &Arc<RouteHandler>, host: &HostPattern) -> Arc<RouteHandler> {
+    let is_host_param = |param: &ParsedParameter| {
+        param.source == ParameterSource::Query
+            && param.param_object.is_none()
+            && host.param_names().any(|name| name == param.name)
+    };
+    let binds_any = handler.parsed_params.iter().any(is_host_param)
+        || handler.dependencies.iter().any(|dep| {
+            dep.injection_plan.iter().any(|(_, injection)| {
+                matches!(injection, InjectionType::Parameter(param) if is_host_param(param))
+            })
+        });
+    if !binds_any {
+        return handler.clone();
+    }
+
+    let mut bound = RouteHandler::clone(handler);
+    let params = bound.parsed_params.iter_mut().chain(
+        bound
+            .dependencies
+            .iter_mut()
+            .flat_map(|dep| dep.injection_plan.iter_mut())
+            .filter_map(|(_, injection)| match injection {
+                InjectionType::Parameter(param) => Some(&mut **param),
+                _ => None,
+            }),
+    );
+    params
+        .filter(|param| is_host_param(param))
+        .for_each(|param| param.source = ParameterSource::Path);
+    Arc::new(bound)
+}
@@ -0,0 +1,173 @@
+use crate::routing::types::{ParameterSource, ParsedParameter, RouteEntry, WebSocketEntry};
+use pyo3::{intern, prelude::*};
+use std::{collections::HashSet, sync::Arc};
+
+/// A parsed handler parameter and where its value comes from.
+#[pyclass(
+    name = "RouteParam",
+    module = "fastrapi.routing",
+    frozen,
+    skip_from_py_object
+)]
+pub struct PyRouteParam {
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    alias: String,
+    #[pyo3(get)]
+    source: String,
+    #[pyo3(get)]
+    required: bool,
+    #[pyo3(get)]
+    default: Option<Py<PyAny>>,
+    #[pyo3(get)]
+    annotation: Option<Py<PyAny>>,
+}
+
+impl PyRouteParam {
+    fn from_parsed(py: Python<'_>, param: &ParsedParameter) -> Self {
+        Self {
+            name: param.name.clone(),
+            alias: param.external_name.clone(),
+            source: match param.source {
+                ParameterSource::Path => "path",
+                ParameterSource::Query => "query",
+                ParameterSource::Body => "body",
+                ParameterSource::Header => "header",
+                ParameterSource::Cookie => "cookie",
+                ParameterSource::BackgroundTasks => "background_tasks",
+            }
+            .to_string(),
+            required: param.required,
+            default: param
+                .default_value
+                .as_ref()
+                .map(|value| value.clone_ref(py)),
+            annotation: param.annotation.as_ref().map(|value| value.clone_ref(py)),
+        }
+    }
+}
+
+#[pymethods]
+impl PyRouteParam {
+    fn __repr__(&self) -> String {
+        format!("RouteParam(name='{}', source='{}')", self.name, self.source)
+    }
+}
+
+/// A registered HTTP route as it is served, with any router prefixes and tags
+/// already applied.
+#[pyclass(
+    name = "Route",
+    module = "fastrapi.routing",
+    frozen,
+    skip_from_py_object
+)]
+pub struct PyRoute {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    methods: HashSet<String>,
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    endpoint: Py<PyAny>,
+    #[pyo3(get)]
+    tags: Vec<String>,
+    #[pyo3(get)]
+    execution_mode: String,
+    #[pyo3(get)]
+    cached: bool,
+    #[pyo3(get)]
+    rate_limit: Option<u32>,
+    #[pyo3(get)]
+    include_in_schema: bool,
+    #[pyo3(get)]
+    params: Vec<Py<PyRouteParam>>,
+}
+
+#[pymethods]
+impl PyRoute {
+    fn __repr__(&self) -> String {
+        let mut methods: Vec<&str> = self.methods.iter().map(String::as_str).collect();
+        methods.sort_unstable();
+        format!(
+            "Route(path='{}', name='{}', methods={:?})",
+            self.path, self.name, methods
+        )
+    }
+}
+
+#[pyclass(
+    name = "WebSocketRoute",
+    module = "fastrapi.routing",
+    frozen,
+    skip_from_py_object
+)]
+pub struct PyWebSocketRoute {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    name: String,
+    #[pyo3(get)]
+    endpoint: Py<PyAny>,
+}
+
+#[pymethods]
+impl PyWebSocketRoute {
+    fn __repr__(&self) -> String {
+        format!("WebSocketRoute(path='{}', name='{}')", self.path, self.name)
+    }
+}
+
+fn endpoint_name(py: Python<'_>, endpoint: &Py<PyAny>) -> String {
+    endpoint
+        .bind(py)
+        .getattr(intern!(py, "__name__"))
+        .and_then(|name| name.extract())
+        .unwrap_or_default()
+}
+
+/// `Route` and `WebSocketRoute` objects for flattened routes, HTTP routes
+/// first. The entries one `api_route` registers make up a single `Route`.
+pub(crate) fn route_objects(
+    py: Python<'_>,
+    (routes, ws_routes): &(Vec<RouteEntry>, Vec<WebSocketEntry>),
+) -> PyResult<Vec<Py<PyAny>>> {
+    let mut objects = Vec::with_capacity(routes.len() + ws_routes.len());
+    let grouped = routes.chunk_by(|a, b| a.path == b.path && Arc::ptr_eq(&a.handler, &b.handler));
+    for group in grouped {
+        let route = &group[0];
+        let handler = &route.handler;
+        let params = handler
+            .parsed_params
+            .iter()
+            .map(|param| Py::new(py, PyRouteParam::from_parsed(py, param)))
+            .collect::<PyResult<_>>()?;
+        let route = PyRoute {
+            path: route.path.clone(),
+            methods: group.iter().map(|entry| entry.method.to_string()).collect(),
+            name: route
+                .name
+                .clone()
+                .unwrap_or_else(|| endpoint_name(py, &handler.func)),
+            endpoint: handler.func.clone_ref(py),
+            tags: route.tags.clone(),
+            execution_mode: format!("{:?}", handler.execution_mode),
+            cached: handler.cache_response,
+            rate_limit: handler.rate_limit_per_second,
+            include_in_schema: route.include_in_schema,
+            params,
+        };
+        objects.push(Py::new(py, route)?.into_any());
+    }
+    for ws in ws_routes {
+        let route = PyWebSocketRoute {
+            path: ws.path.clone(),
+            name: endpoint_name(py, &ws.handler),
+            endpoint: ws.handler.clone_ref(py),
+        };
+        objects.push(Py::new(py, route)?.into_any());
+    }
+    Ok(objects)
+}
@@ -1,4 +1,7 @@
+pub mod converters;
 pub mod dependencies;
+pub mod host;
+pub mod introspection;
 pub mod params;
 pub mod prometheus;
 pub mod router;
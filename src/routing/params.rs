@@ -11,10 +11,11 @@ static INSPECT_PARAMETER_EMPTY: OnceLock<Py<PyAny>> = OnceLock::new();
 
 // utils
 
-/// from route patterns like "/users/{user_id}"
+/// from route patterns like "/users/{user_id}" or "/users/{user_id:int}"
 pub fn extract_path_param_names(path: &str) -> Vec<String> {
     static PATH_PARAM_REGEX: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
-    let regex = PATH_PARAM_REGEX.get_or_init(|| regex::Regex::new(r"\{([^}]+)\}").unwrap());
+    let regex =
+        PATH_PARAM_REGEX.get_or_init(|| regex::Regex::new(r"\{([^}:]+)(:[^}]*)?\}").unwrap());
 
     regex
         .captures_iter(path)
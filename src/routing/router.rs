@@ -1,30 +1,252 @@
-use crate::routing::types::{HTTP_METHOD_COUNT, HttpMethod, RouteHandler};
+use crate::routing::{
+    converters::{Converter, PathTemplate},
+    types::{HTTP_METHOD_COUNT, HttpMethod, RouteHandler, RouteMethod, decode_path_component},
+};
 use ahash::AHashMap;
 use pyo3::{Py, PyAny};
+use smallvec::SmallVec;
 use std::{borrow::Cow, sync::Arc};
+use strum::IntoEnumIterator;
 
+pub type AllowedMethods<'a> = SmallVec<[&'a str; HTTP_METHOD_COUNT]>;
+
+/// Path parameter names and the raw slices of the request path they matched.
+pub type RouteParams<'a> = SmallVec<[(&'a str, &'a str); 4]>;
+
+/// Matches carry the path template the route was declared with.
 pub enum RouteMatch<'a> {
-    Static(Arc<RouteHandler>),
-    Params(Arc<RouteHandler>, matchit::Params<'a, 'a>),
+    Static(Arc<RouteHandler>, &'a Arc<str>),
+    Params(Arc<RouteHandler>, RouteParams<'a>, &'a Arc<str>),
+}
+
+#[derive(Clone)]
+struct RouteTarget {
+    route: Arc<str>,
+    handler: Arc<RouteHandler>,
+}
+
+#[derive(Clone)]
+struct ParamTarget {
+    target: RouteTarget,
+    params: SmallVec<[(Box<str>, Converter); 4]>,
+}
+
+impl ParamTarget {
+    /// The parameters, if every value passes its converter. Converters see
+    /// the decoded value; the parameters stay raw slices of the path, which
+    /// dispatch decodes for the handler. Values a plain `str` or `path`
+    /// accepts are not decoded here, so a bad escape still gets its 400.
+    #[inline]
+    fn accept<'a>(&'a self, matched: &matchit::Params<'a, 'a>) -> Option<RouteParams<'a>> {
+        self.params
+            .iter()
+            .zip(matched.iter())
+            .map(|((name, converter), (_, value))| {
+                let accepted = match converter {
+                    Converter::Str | Converter::Path => true,
+                    converter => decode_path_component(value)
+                        .is_ok_and(|decoded| converter.matches(&decoded)),
+                };
+                accepted.then_some((&**name, value))
+            })
+            .collect()
+    }
+}
+
+/// Parameterized routes that differ only in names and converters, such as
+/// `/items/{id:int}` and `/items/{slug}`, share a matchit pattern; they are
+/// tried in registration order. `fallbacks` are the other patterns that may
+/// match the same paths, tried when every converter here refuses.
+#[derive(Clone)]
+struct ParamShape {
+    matcher: matchit::Router<()>,
+    targets: Vec<ParamTarget>,
+    fallbacks: SmallVec<[usize; 4]>,
+}
+
+impl ParamShape {
+    fn resolve<'a>(&'a self, matched: &matchit::Params<'a, 'a>) -> Option<RouteMatch<'a>> {
+        self.targets.iter().find_map(|candidate| {
+            let params = candidate.accept(matched)?;
+            Some(RouteMatch::Params(
+                candidate.target.handler.clone(),
+                params,
+                &candidate.target.route,
+            ))
+        })
+    }
+}
+
+/// The routes registered under one method.
+#[derive(Clone, Default)]
+struct MethodTable {
+    static_routes: AHashMap<Box<str>, RouteTarget>,
+    param_routes: Option<matchit::Router<usize>>,
+    shapes: Vec<ParamShape>,
+}
+
+impl MethodTable {
+    #[inline(always)]
+    fn resolve<'a>(&'a self, path: &'a str) -> Option<RouteMatch<'a>> {
+        let normalized = normalize_lookup(path);
+        if let Some(target) = self.static_routes.get(normalized) {
+            return Some(RouteMatch::Static(target.handler.clone(), &target.route));
+        }
+        let matched = self.param_routes.as_ref()?.at(path).ok()?;
+        let best = *matched.value;
+        if let Some(found) = self.shapes[best].resolve(&matched.params) {
+            return Some(found);
+        }
+        // every converter of the most specific pattern refused; fall through
+        // to the other patterns that match
+        self.shapes[best].fallbacks.iter().find_map(|idx| {
+            let shape = &self.shapes[*idx];
+            let matched = shape.matcher.at(path).ok()?;
+            shape.resolve(&matched.params)
+        })
+    }
+}
+
+#[derive(Default)]
+struct MethodEntries {
+    static_routes: AHashMap<Box<str>, RouteTarget>,
+    param_entries: Vec<(String, RouteTarget)>,
+}
+
+impl MethodEntries {
+    fn add(&mut self, path: String, handler: Arc<RouteHandler>) {
+        let (normalized, has_params) = normalize_register(&path);
+        let target = RouteTarget {
+            route: Arc::from(path.as_str()),
+            handler,
+        };
+
+        if has_params {
+            self.param_entries.push((normalized.into_owned(), target));
+        } else {
+            self.static_routes
+                .insert(normalized.into_owned().into_boxed_str(), target);
+        }
+    }
+
+    fn build(self) -> MethodTable {
+        let mut router = matchit::Router::new();
+        let mut shapes: Vec<ParamShape> = Vec::new();
+        let mut shape_index: AHashMap<String, usize> = AHashMap::new();
+        for (path, target) in self.param_entries {
+            let template = match PathTemplate::parse(&path) {
+                Ok(template) => template,
+                Err(e) => {
+                    tracing::warn!("Failed to insert parameterized route '{}': {}", path, e);
+                    continue;
+                }
+            };
+            let candidate = ParamTarget {
+                target,
+                params: template.params,
+            };
+            if let Some(idx) = shape_index.get(&template.shape) {
+                shapes[*idx].targets.push(candidate);
+                continue;
+            }
+
+            let mut matcher = matchit::Router::new();
+            let inserted = router
+                .insert(template.shape.as_str(), shapes.len())
+                .and_then(|_| matcher.insert(template.shape.as_str(), ()));
+            if let Err(e) = inserted {
+                tracing::warn!("Failed to insert parameterized route '{}': {}", path, e);
+                continue;
+            }
+            shape_index.insert(template.shape, shapes.len());
+            shapes.push(ParamShape {
+                matcher,
+                targets: vec![candidate],
+                fallbacks: SmallVec::new(),
+            });
+        }
+
+        let patterns: Vec<&str> = {
+            let mut patterns = vec![""; shapes.len()];
+            for (pattern, idx) in &shape_index {
+                patterns[*idx] = pattern;
+            }
+            patterns
+        };
+        for (idx, shape) in shapes.iter_mut().enumerate() {
+            shape.fallbacks = (0..patterns.len())
+                .filter(|other| *other != idx && may_overlap(patterns[idx], patterns[*other]))
+                .collect();
+        }
+
+        MethodTable {
+            static_routes: self.static_routes,
+            param_routes: (!shapes.is_empty()).then_some(router),
+            shapes,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct FrozenRouter {
-    static_routes: [AHashMap<Box<str>, Arc<RouteHandler>>; HTTP_METHOD_COUNT],
-    param_routes: [Option<matchit::Router<Arc<RouteHandler>>>; HTTP_METHOD_COUNT],
+    routes: [MethodTable; HTTP_METHOD_COUNT],
+    /// Extension methods, which requests rarely use, keyed by method name.
+    extension_routes: AHashMap<Box<str>, MethodTable>,
     websocket_routes: AHashMap<String, Py<PyAny>>,
 }
 
 impl FrozenRouter {
     #[inline(always)]
     pub fn resolve<'a>(&'a self, method: HttpMethod, path: &'a str) -> Option<RouteMatch<'a>> {
-        let idx = method as usize;
-        let normalized = normalize_lookup(path);
-        if let Some(handler) = self.static_routes[idx].get(normalized) {
-            return Some(RouteMatch::Static(handler.clone()));
+        self.routes[method as usize].resolve(path)
+    }
+
+    pub fn resolve_extension<'a>(&'a self, method: &str, path: &'a str) -> Option<RouteMatch<'a>> {
+        self.extension_routes.get(method)?.resolve(path)
+    }
+
+    /// Methods `path` answers to: the standard ones in `HttpMethod` order,
+    /// including HEAD wherever GET is registered and OPTIONS for any
+    /// registered path, then extension methods by name. Only misses need
+    /// this, so it looks the path up per method rather than keeping an index.
+    pub fn allowed_methods(&self, path: &str) -> AllowedMethods<'_> {
+        let registered: SmallVec<[HttpMethod; HTTP_METHOD_COUNT]> = HttpMethod::iter()
+            .filter(|method| self.resolve(*method, path).is_some())
+            .collect();
+        let mut extensions: AllowedMethods<'_> = self
+            .extension_routes
+            .iter()
+            .filter(|(_, table)| table.resolve(path).is_some())
+            .map(|(name, _)| &**name)
+            .collect();
+        if registered.is_empty() && extensions.is_empty() {
+            return extensions;
+        }
+        extensions.sort_unstable();
+
+        // HEAD is answered by GET routes, so it is listed right after GET
+        let mut allowed = AllowedMethods::new();
+        for method in HttpMethod::iter() {
+            match method {
+                HttpMethod::GET if registered.contains(&method) => {
+                    allowed.push(method.into());
+                    allowed.push(HttpMethod::HEAD.into());
+                }
+                HttpMethod::HEAD => {}
+                HttpMethod::OPTIONS => allowed.push(method.into()),
+                method if registered.contains(&method) => allowed.push(method.into()),
+                _ => {}
+            }
         }
-        let matched = self.param_routes[idx].as_ref()?.at(path).ok()?;
-        Some(RouteMatch::Params(matched.value.clone(), matched.params))
+        allowed.extend(extensions);
+        allowed
+    }
+
+    pub fn has_path(&self, path: &str) -> bool {
+        self.routes
+            .iter()
+            .chain(self.extension_routes.values())
+            .any(|table| table.resolve(path).is_some())
     }
 
     pub fn resolve_ws(&self, path: &str) -> Option<Py<PyAny>> {
@@ -34,29 +256,28 @@ impl FrozenRouter {
 }
 
 pub struct FrozenRouterBuilder {
-    static_routes: [AHashMap<Box<str>, Arc<RouteHandler>>; HTTP_METHOD_COUNT],
-    param_entries: [Vec<(String, Arc<RouteHandler>)>; HTTP_METHOD_COUNT],
+    routes: [MethodEntries; HTTP_METHOD_COUNT],
+    extension_routes: AHashMap<Box<str>, MethodEntries>,
     websocket_routes: AHashMap<String, Py<PyAny>>,
 }
 
 impl FrozenRouterBuilder {
     pub fn new() -> Self {
         Self {
-            static_routes: std::array::from_fn(|_| AHashMap::new()),
-            param_entries: std::array::from_fn(|_| Vec::new()),
+            routes: std::array::from_fn(|_| MethodEntries::default()),
+            extension_routes: AHashMap::new(),
             websocket_routes: AHashMap::new(),
         }
     }
 
-    pub fn add_route(&mut self, method: HttpMethod, path: String, handler: Arc<RouteHandler>) {
-        let idx = method as usize;
-        let (normalized, has_params) = normalize_register(&path);
-
-        if has_params {
-            self.param_entries[idx].push((normalized.into_owned(), handler));
-        } else {
-            self.static_routes[idx].insert(normalized.into_owned().into_boxed_str(), handler);
-        }
+    pub fn add_route(&mut self, method: &RouteMethod, path: String, handler: Arc<RouteHandler>) {
+        let entries = match method {
+            RouteMethod::Standard(method) => &mut self.routes[*method as usize],
+            RouteMethod::Extension(name) => {
+                self.extension_routes.entry(Box::from(&**name)).or_default()
+            }
+        };
+        entries.add(path, handler);
     }
 
     pub fn add_websocket(&mut self, path: String, handler: Py<PyAny>) {
@@ -66,24 +287,13 @@ impl FrozenRouterBuilder {
     }
 
     pub fn build(self) -> FrozenRouter {
-        let param_routes = std::array::from_fn(|idx| {
-            let entries = &self.param_entries[idx];
-            if entries.is_empty() {
-                return None;
-            }
-            let mut router = matchit::Router::new();
-            entries.iter().for_each(|(path, handler)| {
-                if let Err(e) = router.insert(path, handler.clone()) {
-                    tracing::warn!("Failed to insert parameterized route '{}': {}", path, e);
-                }
-            });
-
-            Some(router)
-        });
-
         FrozenRouter {
-            static_routes: self.static_routes,
-            param_routes,
+            routes: self.routes.map(MethodEntries::build),
+            extension_routes: self
+                .extension_routes
+                .into_iter()
+                .map(|(name, entries)| (name, entries.build()))
+                .collect(),
             websocket_routes: self.websocket_routes,
         }
     }
@@ -95,6 +305,28 @@ impl Default for FrozenRouterBuilder {
     }
 }
 
+/// Whether two matchit patterns could match the same path. Literal segments
+/// must agree; a parameter matches any segment and a catch-all the rest, so
+/// this errs towards overlapping.
+fn may_overlap(a: &str, b: &str) -> bool {
+    let mut a_segments = a.split('/');
+    let mut b_segments = b.split('/');
+    loop {
+        match (a_segments.next(), b_segments.next()) {
+            (None, None) => return true,
+            (Some(a), Some(b)) => {
+                if a.starts_with("{*") || b.starts_with("{*") {
+                    return true;
+                }
+                if !a.contains('{') && !b.contains('{') && a != b {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+}
+
 fn normalize_lookup(input: &str) -> &str {
     let trimmed = input.trim();
     if trimmed.len() > 1 {
@@ -1,5 +1,7 @@
 use crate::decorators::PyAPIRouter;
+use crate::http::request::PendingBody;
 use crate::routing::dependencies::DependencyNode;
+use crate::routing::host::HostPattern;
 use crate::types::response::ResponseType;
 use ahash::{AHashMap, AHashSet};
 use axum::http::Method;
@@ -10,10 +12,21 @@ use smallvec::SmallVec;
 use std::borrow::Cow;
 use std::sync::Arc;
 use std::sync::OnceLock;
-use strum::{AsRefStr, Display, EnumCount, EnumIter, EnumString};
+use strum::{AsRefStr, Display, EnumCount, EnumIter, EnumString, IntoStaticStr};
 
 #[derive(
-    Clone, Copy, Debug, PartialEq, Eq, Hash, AsRefStr, Display, EnumString, EnumCount, EnumIter,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    AsRefStr,
+    Display,
+    EnumString,
+    EnumCount,
+    EnumIter,
+    IntoStaticStr,
 )]
 #[repr(u8)]
 pub enum HttpMethod {
@@ -36,6 +49,55 @@ impl TryFrom<&Method> for HttpMethod {
 
 pub const HTTP_METHOD_COUNT: usize = HttpMethod::COUNT;
 
+/// The method a route is registered under. Extension methods (TRACE,
+/// PROPFIND, QUERY, ...) live in the router's overflow table.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RouteMethod {
+    Standard(HttpMethod),
+    Extension(Arc<str>),
+}
+
+impl RouteMethod {
+    /// Parses a method name the way `api_route` accepts it: uppercased, and
+    /// made only of token characters.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let name = name.to_ascii_uppercase();
+        if let Ok(method) = name.parse::<HttpMethod>() {
+            return Ok(Self::Standard(method));
+        }
+        if Method::from_bytes(name.as_bytes()).is_err() {
+            return Err(format!("'{name}' is not a valid HTTP method"));
+        }
+        Ok(Self::Extension(Arc::from(name)))
+    }
+
+    pub fn standard(&self) -> Option<HttpMethod> {
+        match self {
+            Self::Standard(method) => Some(*method),
+            Self::Extension(_) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Standard(method) => method.as_ref(),
+            Self::Extension(name) => name,
+        }
+    }
+}
+
+impl From<HttpMethod> for RouteMethod {
+    fn from(method: HttpMethod) -> Self {
+        Self::Standard(method)
+    }
+}
+
+impl std::fmt::Display for RouteMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ParameterSource {
     Path,
@@ -75,11 +137,36 @@ pub struct ParsedParameter {
     pub validator_index: Option<usize>,
 }
 
+/// Path template of the route that handled a request. The dispatcher attaches
+/// it to responses when `AppState::track_routes` is set.
+#[derive(Clone, Debug)]
+pub struct MatchedRoute(pub Arc<str>);
+
 #[derive(Clone, Debug)]
 pub struct PathParamRange {
     pub key: String,
-    pub start: usize,
-    pub end: usize,
+    pub value: ParamValue,
+}
+
+/// Where a path parameter's value lives.
+#[derive(Clone, Debug)]
+pub enum ParamValue {
+    /// A slice of the request path, which had nothing to percent-decode.
+    Span { start: usize, end: usize },
+    /// The percent-decoded form of a slice of the path.
+    Decoded(Box<str>),
+    /// Read from the `Host` header by a host router's pattern.
+    Host(Box<str>),
+}
+
+impl PathParamRange {
+    #[inline]
+    pub fn value<'a>(&'a self, path: &'a str) -> &'a str {
+        match &self.value {
+            ParamValue::Span { start, end } => &path[*start..*end],
+            ParamValue::Decoded(value) | ParamValue::Host(value) => value,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -158,6 +245,7 @@ pub struct RequestInput<'a> {
     pub query_params: QueryParams<'a>,
     pub headers: &'a axum::http::HeaderMap,
     pub cookies: OnceLock<SmallVec<[(&'a str, &'a str); 8]>>,
+    pub body: Option<&'a PendingBody>,
 }
 
 #[inline(always)]
@@ -186,6 +274,16 @@ fn decode_query_component(raw: &str) -> Cow<'_, str> {
     }
 }
 
+/// Unlike in query strings `+` is literal in a path, and a value that does not
+/// decode to UTF-8 is an error rather than being replaced.
+#[inline(always)]
+pub fn decode_path_component(raw: &str) -> Result<Cow<'_, str>, std::str::Utf8Error> {
+    if !raw.as_bytes().contains(&b'%') {
+        return Ok(Cow::Borrowed(raw));
+    }
+    percent_encoding::percent_decode_str(raw).decode_utf8()
+}
+
 impl<'a> RequestInput<'a> {
     pub fn get_path_param(&self, key: &str) -> Option<&'a str> {
         self.path_params
@@ -279,8 +377,9 @@ pub struct RouteHandler {
 
 #[derive(Clone)]
 pub struct RouteEntry {
-    pub method: HttpMethod,
+    pub method: RouteMethod,
     pub path: String,
+    pub name: Option<String>,
     pub handler: Arc<RouteHandler>,
     pub tags: Vec<String>,
     pub summary: Option<String>,
@@ -292,12 +391,16 @@ pub struct RouteEntry {
     pub callbacks: Option<sonic_rs::Value>,
     pub deprecated: Option<bool>,
     pub include_in_schema: bool,
+    /// The host the route is served under, set when flattening.
+    pub host: Option<Arc<HostPattern>>,
 }
 
 #[derive(Clone)]
 pub struct WebSocketEntry {
     pub path: String,
     pub handler: Py<PyAny>,
+    /// The host the route is served under, set when flattening.
+    pub host: Option<Arc<HostPattern>>,
 }
 
 #[derive(Clone)]
@@ -311,4 +414,5 @@ pub struct SubRouterMount {
     pub include_in_schema: bool,
     pub default_response_class: Option<Py<PyAny>>,
     pub generate_unique_id_function: Option<Py<PyAny>>,
+    pub host: Option<Arc<HostPattern>>,
 }
@@ -2,8 +2,11 @@ use crate::{
     FastrAPI,
     decorators::PyAPIRouter,
     ffi::pydantic,
-    routing::types::{ParameterConstraints, ParameterSource, RouteEntry},
-    types::route::HttpMethod,
+    routing::{
+        converters::strip_converters,
+        types::{ParameterConstraints, ParameterSource, RouteEntry},
+    },
+    types::route::{HttpMethod, RouteMethod},
     utils::py_dict_to_json,
 };
 use pyo3::prelude::*;
@@ -85,6 +88,12 @@ pub struct PathItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub patch: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<Parameter>>,
 }
 
@@ -346,8 +355,7 @@ pub fn build_openapi_spec(py: Python<'_>, app: &FastrAPI) -> JsonValue {
     }
 
     let router = app.router.bind(py);
-    let router = router.borrow();
-    let collected = collect_routes(py, &router);
+    let collected = router.borrow().flatten(py, &app.live_routes).0.clone();
 
     let app_responses = if let Some(resp) = &app.responses
         && let Ok(dict) = resp.bind(py).cast::<PyDict>()
@@ -370,7 +378,7 @@ pub fn build_openapi_spec(py: Python<'_>, app: &FastrAPI) -> JsonValue {
     if let Some(wh) = &app.webhooks
         && let Ok(router) = wh.bind(py).cast::<crate::decorators::PyAPIRouter>()
     {
-        let wh_collected = collect_routes(py, &router.borrow());
+        let wh_collected = router.borrow().flatten(py, &app.live_routes).0.clone();
         let wh_paths = build_paths_from_routes(
             py,
             wh_collected,
@@ -400,12 +408,17 @@ pub fn build_paths_from_routes(
     let mut paths: HashMap<String, PathItem> = HashMap::new();
 
     for route in collected {
-        if !route.include_in_schema {
+        // path items have no slot for extension methods other than TRACE
+        let openapi_method = match &route.method {
+            RouteMethod::Standard(_) => true,
+            RouteMethod::Extension(name) => &**name == "TRACE",
+        };
+        if !route.include_in_schema || !openapi_method {
             continue;
         }
 
-        let path = route.path.clone();
-        let method = route.method.as_ref().to_lowercase();
+        let path = strip_converters(&route.path).into_owned();
+        let method = route.method.as_str().to_lowercase();
         let handler = route.handler.clone();
         let tags = &route.tags;
 
@@ -445,6 +458,15 @@ pub fn build_paths_from_routes(
 
         handler.parsed_params.iter().for_each(|param| {
             let location = match param.source {
+                // the host is not part of the documented path
+                ParameterSource::Path
+                    if route
+                        .host
+                        .as_ref()
+                        .is_some_and(|host| host.param_names().any(|name| name == param.name)) =>
+                {
+                    return;
+                }
                 ParameterSource::Path => "path",
                 ParameterSource::Query => "query",
                 ParameterSource::Header => "header",
@@ -475,7 +497,7 @@ pub fn build_paths_from_routes(
         if !handler.param_validators.is_empty()
             && matches!(
                 route.method,
-                HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH
+                RouteMethod::Standard(HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH)
             )
         {
             let validator_count = handler.param_validators.len();
@@ -639,6 +661,9 @@ pub fn build_paths_from_routes(
             put: None,
             delete: None,
             patch: None,
+            options: None,
+            head: None,
+            trace: None,
             parameters: None,
         });
 
@@ -675,14 +700,17 @@ pub fn build_paths_from_routes(
             }
         }
 
-        match route.method {
-            HttpMethod::GET => path_item.get = Some(operation_val),
-            HttpMethod::POST => path_item.post = Some(operation_val),
-            HttpMethod::PUT => path_item.put = Some(operation_val),
-            HttpMethod::DELETE => path_item.delete = Some(operation_val),
-            HttpMethod::PATCH => path_item.patch = Some(operation_val),
-            _ => {}
-        }
+        let slot = match &route.method {
+            RouteMethod::Standard(HttpMethod::GET) => &mut path_item.get,
+            RouteMethod::Standard(HttpMethod::POST) => &mut path_item.post,
+            RouteMethod::Standard(HttpMethod::PUT) => &mut path_item.put,
+            RouteMethod::Standard(HttpMethod::DELETE) => &mut path_item.delete,
+            RouteMethod::Standard(HttpMethod::PATCH) => &mut path_item.patch,
+            RouteMethod::Standard(HttpMethod::OPTIONS) => &mut path_item.options,
+            RouteMethod::Standard(HttpMethod::HEAD) => &mut path_item.head,
+            RouteMethod::Extension(_) => &mut path_item.trace,
+        };
+        *slot = Some(operation_val);
     }
     paths
 }
@@ -747,6 +775,5 @@ pub fn parse_callbacks_to_json(
 }
 
 fn collect_routes(py: Python<'_>, router: &PyAPIRouter) -> Vec<RouteEntry> {
-    let flat = router.flatten(py);
-    flat.0.clone()
+    router.list_routes(py).0
 }
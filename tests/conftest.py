@@ -1,56 +1,66 @@
 import pytest
-from fastapi.testclient import TestClient
 from fastrapi import FastrAPI
+from fastrapi.testclient import TestClient
 from fastrapi.responses import JSONResponse
 import asyncio
+import socket
+import subprocess
+import time
 
-@pytest.fixture
-def app():
-    return FastrAPI(debug=True)
+import httpx
 
 
-import httpx
-import threading
-import time
-import socket
+def get_free_port() -> int:
+    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as sock:
+        sock.bind(("127.0.0.1", 0))
+        return sock.getsockname()[1]
 
-def get_free_port():
-    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
-        s.bind(("", 0))
-        return s.getsockname()[1]
-
-class LiveServerTestClient(httpx.Client):
-    def __init__(self, app):
-        self.app = app
-        self.port = get_free_port()
-        self.server_thread = None
-        super().__init__(base_url=f"http://127.0.0.1:{self.port}")
-        
-    def _ensure_server_running(self):
-        if self.server_thread is None:
-            self.server_thread = threading.Thread(
-                target=self.app.serve,
-                args=("127.0.0.1", self.port),
-                daemon=True
+
+def wait_for_server(
+    proc: subprocess.Popen[str],
+    url: str,
+    ready=lambda response: True,
+    timeout: float = 15.0,
+) -> httpx.Response:
+    """Polls `url` until the server run by `proc` answers with a response
+    `ready` accepts, failing as soon as the process exits."""
+    deadline = time.time() + timeout
+    last_error = None
+
+    while time.time() < deadline:
+        if proc.poll() is not None:
+            output = proc.stdout.read() if proc.stdout is not None else ""
+            raise AssertionError(
+                f"server exited early with code {proc.returncode}\n{output}"
             )
-            self.server_thread.start()
-            time.sleep(0.5)  # Wait for server to start
 
-    def request(self, method, url, **kwargs):
-        self._ensure_server_running()
-        return super().request(method, url, **kwargs)
+        try:
+            response = httpx.get(url, timeout=0.5)
+            if ready(response):
+                return response
+        except Exception as exc:  # pragma: no cover - retry loop
+            last_error = exc
+        time.sleep(0.1)
+
+    raise AssertionError(f"{url} was never ready: {last_error}")
+
+@pytest.fixture
+def app():
+    return FastrAPI(debug=True)
+
 
 @pytest.fixture
 def client(app):
-    with LiveServerTestClient(app) as c:
-        yield c
+    # routes registered after the client started are picked up on the fly
+    with TestClient(app) as client:
+        yield client
 
 
 @pytest.fixture
 def async_client(app):
     """For testing async endpoints"""
-    with LiveServerTestClient(app) as c:
-        yield c
+    with TestClient(app) as client:
+        yield client
 
 
 @pytest.fixture
@@ -0,0 +1,113 @@
+import json
+import subprocess
+import sys
+from pathlib import Path
+
+import pytest
+
+from fastrapi import FastrAPI
+from fastrapi.middleware import AccessLogMiddleware
+
+
+ROOT = Path(__file__).resolve().parents[1]
+
+
+def access_lines(tmp_path: Path, middleware: str, requests: str) -> list[str]:
+    script = tmp_path / "access_app.py"
+    script.write_text(
+        f"""
+import httpx
+
+from fastrapi import FastrAPI
+from fastrapi.middleware import AccessLogMiddleware
+
+app = FastrAPI(request_id_header="x-request-id")
+app.add_middleware(AccessLogMiddleware, {middleware})
+
+
+@app.get("/items/{{item_id}}")
+def item(item_id: int):
+    return {{"item_id": item_id}}
+
+
+@app.get("/health")
+def health():
+    return {{"ok": True}}
+
+
+handle = app.start("127.0.0.1", 0, log_format="json", log_filter="fastrapi::access=info,off")
+handle.wait_ready(timeout=10)
+base = f"http://127.0.0.1:{{handle.port}}"
+{requests}
+handle.stop(timeout=10)
+""",
+        encoding="utf-8",
+    )
+    result = subprocess.run(
+        [sys.executable, str(script)],
+        cwd=ROOT,
+        capture_output=True,
+        text=True,
+        timeout=30,
+    )
+    assert result.returncode == 0, result.stderr
+    return [
+        json.loads(line)["message"]
+        for line in result.stdout.splitlines()
+        if line.startswith("{") and json.loads(line)["target"] == "fastrapi::access"
+    ]
+
+
+def test_combined_format(tmp_path: Path):
+    lines = access_lines(
+        tmp_path,
+        "",
+        'httpx.get(base + "/items/7?full=1", headers={"user-agent": "probe", "referer": "http://ref"}, timeout=5)',
+    )
+
+    assert len(lines) == 1
+    assert lines[0].startswith("127.0.0.1 - - [")
+    assert '"GET /items/7?full=1 HTTP/1.1" 200 13 "http://ref" "probe"' in lines[0]
+
+
+def test_json_format_includes_route_and_request_id(tmp_path: Path):
+    lines = access_lines(
+        tmp_path,
+        'format="json"',
+        'httpx.get(base + "/items/7", headers={"x-request-id": "abc"}, timeout=5)',
+    )
+
+    record = json.loads(lines[0])
+    assert record["route"] == "/items/{item_id}"
+    assert record["status"] == 200
+    assert record["bytes"] == 13
+    assert record["client"] == "127.0.0.1"
+    assert record["request_id"] == "abc"
+    assert record["query"] is None
+
+
+def test_template_exclusions_and_sampling(tmp_path: Path):
+    lines = access_lines(
+        tmp_path,
+        'format="{method} {path} {status}", exclude_paths=["/health"], sample_rate=0.5',
+        """
+for path in ["/health", "/items/1", "/items/2", "/items/3", "/items/4"]:
+    httpx.get(base + path, timeout=5)
+""",
+    )
+
+    # sampling is evenly spaced, so every second non-excluded request is logged
+    assert lines == ["GET /items/2 200", "GET /items/4 200"]
+
+
+@pytest.mark.parametrize(
+    "options",
+    [{"format": "{nope}"}, {"sample_rate": 1.5}, {"exclude_paths": ["[unclosed"]}],
+)
+def test_invalid_options_are_rejected(options):
+    with pytest.raises(ValueError):
+        AccessLogMiddleware(**options)
+
+    app = FastrAPI()
+    with pytest.raises(ValueError):
+        app.add_middleware(AccessLogMiddleware, **options)
@@ -0,0 +1,79 @@
+import pytest
+
+from fastrapi import APIRouter, FastrAPI
+from fastrapi.routing import Route
+from fastrapi.testclient import TestClient
+
+
+def test_api_route_registers_every_method():
+    app = FastrAPI()
+
+    @app.api_route("/items", methods=["GET", "post"])
+    def items(request_id: int = 0):
+        return {"request_id": request_id}
+
+    client = TestClient(app)
+    assert client.get("/items").json() == {"request_id": 0}
+    assert client.post("/items?request_id=3").json() == {"request_id": 3}
+    assert client.put("/items").status_code == 405
+
+    [route] = [route for route in app.routes if isinstance(route, Route)]
+    assert route.methods == {"GET", "POST"}
+
+
+def test_api_route_defaults_to_get():
+    app = FastrAPI()
+
+    @app.api_route("/ping")
+    def ping():
+        return "pong"
+
+    client = TestClient(app)
+    assert client.get("/ping").json() == "pong"
+    assert client.post("/ping").status_code == 405
+
+
+def test_extension_methods_are_routed():
+    app = FastrAPI()
+    router = APIRouter(prefix="/dav")
+
+    @router.api_route("/{name}", methods={"PROPFIND", "REPORT"})
+    def properties(name: str):
+        return {"name": name}
+
+    @app.api_route("/search", methods=["QUERY"])
+    def search():
+        return {"results": []}
+
+    app.include_router(router)
+    client = TestClient(app)
+    assert client.request("PROPFIND", "/dav/notes").json() == {"name": "notes"}
+    assert client.request("REPORT", "/dav/notes").json() == {"name": "notes"}
+    assert client.request("QUERY", "/search").json() == {"results": []}
+    assert client.request("MKCOL", "/dav/notes").status_code == 405
+    assert client.request("MKCOL", "/missing").status_code == 404
+
+    response = client.get("/dav/notes")
+    assert response.status_code == 405
+    assert response.headers["allow"] == "OPTIONS, PROPFIND, REPORT"
+
+
+def test_openapi_only_lists_supported_methods():
+    app = FastrAPI()
+
+    @app.api_route("/resource", methods=["GET", "TRACE", "PROPFIND"])
+    def resource():
+        return {}
+
+    paths = TestClient(app).get("/api-docs/openapi.json").json()["paths"]
+    assert set(paths["/resource"]) == {"get", "trace"}
+
+
+def test_invalid_methods_are_rejected():
+    app = FastrAPI()
+    with pytest.raises(ValueError, match="not a valid HTTP method"):
+        app.api_route("/x", methods=["BAD METHOD"])
+    with pytest.raises(ValueError, match="at least one method"):
+        app.api_route("/x", methods=[])
+    with pytest.raises(TypeError, match="list or set"):
+        app.api_route("/x", methods="GET")
@@ -403,25 +403,29 @@ class TestOpenAPI:
 
 
 # -----------------------------------------------------------------
-# Post-serve freeze: serve() calls base_router.freeze(), which
-# recursively caches the flattened tree. Mutating any router whose
-# `frozen` flag is set must raise.
+# Post-serve freeze: serve() flattens the router tree and freezes it.
+# HTTP routes added to a frozen router are swapped into the running
+# server; WebSocket routes still cannot be added.
 # -----------------------------------------------------------------
 class TestPostServeFrozen:
-    def test_app_base_router_frozen_after_serve(self, live_app):
-        # The base router lives inside FastrAPI; we observe its
-        # frozen-ness indirectly through @app.get raising.
+    def test_app_base_router_accepts_routes_after_serve(self, live_app):
         @live_app.app.get("/before")
         def before():
             return {"ok": True}
 
-        live_app.ready()
+        base = live_app.ready()
+
+        @live_app.app.get("/after")
+        def after():
+            return {"late": True}
+
+        assert httpx.get(f"{base}/after").json() == {"late": True}
 
-        with pytest.raises(RuntimeError, match="frozen"):
+        with pytest.raises(RuntimeError, match="WebSocket"):
 
-            @live_app.app.get("/after")
-            def after():
-                return {}
+            @live_app.app.websocket("/ws")
+            async def ws(websocket):
+                pass
 
     def test_included_router_can_be_pinned_at_serve(self, live_app):
         # An APIRouter included into the app is reachable through the
@@ -0,0 +1,147 @@
+import asyncio
+import json
+
+from fastrapi import Depends, FastrAPI
+from fastrapi.request import Request
+from fastrapi.responses import StreamingResponse
+
+
+def http_scope(method: str, path: str, **extra):
+    scope = {
+        "type": "http",
+        "asgi": {"version": "3.0"},
+        "http_version": "1.1",
+        "method": method,
+        "scheme": "http",
+        "path": path,
+        "root_path": "",
+        "query_string": b"",
+        "headers": [(b"host", b"testserver")],
+        "client": ("127.0.0.1", 50000),
+        "server": ("testserver", 80),
+    }
+    scope.update(extra)
+    return scope
+
+
+async def call(app, scope, messages=()):
+    incoming = list(messages) or [{"type": "http.request", "body": b"", "more_body": False}]
+    sent = []
+
+    async def receive():
+        if incoming:
+            return incoming.pop(0)
+        await asyncio.sleep(3600)
+
+    async def send(message):
+        sent.append(message)
+
+    await app(scope, receive, send)
+    return sent
+
+
+def response_of(sent):
+    start = sent[0]
+    assert start["type"] == "http.response.start"
+    assert sent[-1] == {"type": "http.response.body", "body": b"", "more_body": False}
+    headers = {name.decode(): value.decode() for name, value in start["headers"]}
+    body = b"".join(message["body"] for message in sent[1:])
+    return start["status"], headers, body
+
+
+def test_http_request_through_asgi():
+    app = FastrAPI()
+
+    @app.get("/items/{item_id}")
+    def read_item(item_id: int, q: str = ""):
+        return {"item_id": item_id, "q": q}
+
+    sent = asyncio.run(call(app, http_scope("GET", "/items/5", query_string=b"q=rust")))
+    status, headers, body = response_of(sent)
+
+    assert status == 200
+    assert headers["content-type"].startswith("application/json")
+    assert json.loads(body) == {"item_id": 5, "q": "rust"}
+
+
+def test_root_path_is_stripped():
+    app = FastrAPI()
+
+    @app.get("/ping")
+    def ping():
+        return {"ok": True}
+
+    scope = http_scope("GET", "/api/ping", root_path="/api")
+    status, _, _ = response_of(asyncio.run(call(app, scope)))
+
+    assert status == 200
+
+
+def test_request_body_is_read_from_receive():
+    app = FastrAPI()
+
+    async def raw_body(request: Request):
+        return await request.body()
+
+    @app.post("/echo")
+    def echo(body=Depends(raw_body)):
+        return {"body": body.decode()}
+
+    messages = [
+        {"type": "http.request", "body": b"hello ", "more_body": True},
+        {"type": "http.request", "body": b"world", "more_body": False},
+    ]
+    sent = asyncio.run(call(app, http_scope("POST", "/echo"), messages))
+    status, _, body = response_of(sent)
+
+    assert status == 200
+    assert json.loads(body) == {"body": "hello world"}
+
+
+def test_streaming_response_is_sent_in_chunks():
+    app = FastrAPI()
+
+    @app.get("/stream")
+    def stream():
+        def chunks():
+            yield "one,"
+            yield "two"
+
+        return StreamingResponse(chunks(), media_type="text/plain")
+
+    sent = asyncio.run(call(app, http_scope("GET", "/stream")))
+    status, _, body = response_of(sent)
+
+    assert status == 200
+    assert body == b"one,two"
+    assert all(message["more_body"] for message in sent[1:-1])
+
+
+def test_lifespan_protocol_runs_startup_and_shutdown():
+    events = []
+    app = FastrAPI(
+        on_startup=[lambda: events.append("startup")],
+        on_shutdown=[lambda: events.append("shutdown")],
+    )
+
+    messages = [{"type": "lifespan.startup"}, {"type": "lifespan.shutdown"}]
+    sent = asyncio.run(call(app, {"type": "lifespan", "asgi": {"version": "3.0"}}, messages))
+
+    assert [message["type"] for message in sent] == [
+        "lifespan.startup.complete",
+        "lifespan.shutdown.complete",
+    ]
+    assert events == ["startup", "shutdown"]
+
+
+def test_lifespan_startup_failure_is_reported():
+    def fail():
+        raise RuntimeError("no database")
+
+    app = FastrAPI(on_startup=[fail])
+
+    messages = [{"type": "lifespan.startup"}]
+    sent = asyncio.run(call(app, {"type": "lifespan", "asgi": {"version": "3.0"}}, messages))
+
+    assert sent[0]["type"] == "lifespan.startup.failed"
+    assert "no database" in sent[0]["message"]
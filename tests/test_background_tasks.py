@@ -1,5 +1,10 @@
 # tests/test_background_tasks.py
+import threading
+import time
+
+from fastrapi import FastrAPI
 from fastrapi.background import BackgroundTasks
+from fastrapi.testclient import TestClient
 
 def test_background_task(client, app, caplog):
     background_called = False
@@ -22,4 +27,62 @@ def test_background_task(client, app, caplog):
             break
         time.sleep(0.1)
         
-    assert background_called is True
\ No newline at end of file
+    assert background_called is True
+
+def test_background_tasks_are_tracked_per_app():
+    release = threading.Event()
+    slow_app, fast_app = FastrAPI(), FastrAPI()
+
+    @slow_app.post("/slow")
+    def slow(background_tasks: BackgroundTasks):
+        background_tasks.add_task(release.wait, 5)
+        return {"status": "accepted"}
+
+    @fast_app.get("/fast")
+    def fast():
+        return {"ok": True}
+
+    slow_client, fast_client = TestClient(slow_app), TestClient(fast_app)
+    slow_request = threading.Thread(target=slow_client.post, args=("/slow",))
+    slow_request.start()
+    time.sleep(0.2)
+
+    # the other app's pending background task does not hold this response back
+    started = time.monotonic()
+    assert fast_client.get("/fast").status_code == 200
+    assert time.monotonic() - started < 2
+    release.set()
+    slow_request.join(timeout=5)
+    assert not slow_request.is_alive()
+
+
+def test_test_client_waits_only_for_its_own_background_tasks():
+    release = threading.Event()
+    done = []
+    app = FastrAPI()
+
+    @app.post("/slow")
+    def slow(background_tasks: BackgroundTasks):
+        background_tasks.add_task(release.wait, 5)
+        return {"status": "accepted"}
+
+    @app.post("/fast")
+    def fast(background_tasks: BackgroundTasks):
+        background_tasks.add_task(done.append, "fast")
+        return {"status": "accepted"}
+
+    with TestClient(app) as client:
+        slow_request = threading.Thread(target=client.post, args=("/slow",))
+        slow_request.start()
+        time.sleep(0.2)
+
+        # another request's pending background task does not hold this one back,
+        # but its own has run by the time the response is returned
+        started = time.monotonic()
+        assert client.post("/fast").status_code == 200
+        assert time.monotonic() - started < 2
+        assert done == ["fast"]
+        assert slow_request.is_alive()
+        release.set()
+        slow_request.join(timeout=5)
+        assert not slow_request.is_alive()
@@ -0,0 +1,146 @@
+import os
+import signal
+import subprocess
+import sys
+import time
+from pathlib import Path
+
+import pytest
+
+from conftest import get_free_port, wait_for_server
+
+
+pytestmark = pytest.mark.skipif(
+    sys.platform == "win32", reason="servers are stopped through their process group"
+)
+
+
+APP = """
+from fastrapi import FastrAPI
+
+app = FastrAPI()
+
+
+@app.get("/version")
+def version():
+    return {"version": VERSION}
+"""
+
+
+def run_cli(tmp_path: Path, *args: str, **kwargs):
+    code = f"import sys; from fastrapi.cli import main; sys.argv = {['fastrapi', *args]!r}; main()"
+    env = {key: value for key, value in os.environ.items() if not key.startswith("FASTRAPI_")}
+    return subprocess.Popen(
+        [sys.executable, "-c", code],
+        cwd=tmp_path,
+        env=env,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+        start_new_session=True,
+        **kwargs,
+    )
+
+
+def serves_version(expected: int):
+    return lambda response: response.json()["version"] == expected
+
+
+def stop(proc: subprocess.Popen[str]):
+    if proc.poll() is None:
+        os.killpg(proc.pid, signal.SIGTERM)
+    try:
+        proc.wait(timeout=15)
+    except subprocess.TimeoutExpired:  # pragma: no cover - cleanup
+        os.killpg(proc.pid, signal.SIGKILL)
+        proc.wait()
+
+
+def test_run_serves_module_attribute(tmp_path: Path):
+    (tmp_path / "service.py").write_text(APP.replace("VERSION", "1"), encoding="utf-8")
+    port = get_free_port()
+
+    proc = run_cli(tmp_path, "run", "service:app", "--host", "127.0.0.1", "--port", str(port))
+    try:
+        wait_for_server(proc, f"http://127.0.0.1:{port}/version", serves_version(1))
+    finally:
+        stop(proc)
+
+
+def test_dev_restarts_on_change(tmp_path: Path):
+    package = tmp_path / "pkg"
+    package.mkdir()
+    (package / "__init__.py").write_text("", encoding="utf-8")
+    (package / "main.py").write_text(APP.replace("VERSION", "1"), encoding="utf-8")
+    port = get_free_port()
+    url = f"http://127.0.0.1:{port}/version"
+
+    proc = run_cli(tmp_path, "dev", "pkg.main:app", "--port", str(port))
+    try:
+        wait_for_server(proc, url, serves_version(1))
+        time.sleep(1)
+        (package / "main.py").write_text(APP.replace("VERSION", "2"), encoding="utf-8")
+        wait_for_server(proc, url, serves_version(2))
+    finally:
+        stop(proc)
+
+
+def test_reload_under_python_m(tmp_path: Path):
+    package = tmp_path / "pkg"
+    package.mkdir()
+    (package / "__init__.py").write_text("", encoding="utf-8")
+    (package / "routes.py").write_text("VERSION = 1\n", encoding="utf-8")
+    port = get_free_port()
+    (package / "__main__.py").write_text(
+        f"""
+from fastrapi import FastrAPI
+
+from .routes import VERSION
+
+app = FastrAPI()
+
+
+@app.get("/version")
+def version():
+    return {{"version": VERSION}}
+
+
+app.serve("127.0.0.1", {port}, reload=True)
+""",
+        encoding="utf-8",
+    )
+    url = f"http://127.0.0.1:{port}/version"
+
+    proc = subprocess.Popen(
+        [sys.executable, "-m", "pkg"],
+        cwd=tmp_path,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+        start_new_session=True,
+    )
+    try:
+        # the relative import only works if the child is started with -m again
+        wait_for_server(proc, url, serves_version(1))
+        time.sleep(1)
+        (package / "routes.py").write_text("VERSION = 2\n", encoding="utf-8")
+        wait_for_server(proc, url, serves_version(2))
+    finally:
+        stop(proc)
+
+
+def test_help_and_bad_targets(tmp_path: Path):
+    proc = run_cli(tmp_path, "--help")
+    output, _ = proc.communicate(timeout=30)
+    assert proc.returncode == 0
+    assert "run" in output and "dev" in output
+
+    proc = run_cli(tmp_path, "run")
+    output, _ = proc.communicate(timeout=30)
+    assert proc.returncode == 2
+
+    (tmp_path / "notapp.py").write_text("app = 42\n", encoding="utf-8")
+    proc = run_cli(tmp_path, "run", "notapp:app")
+    output, _ = proc.communicate(timeout=30)
+    assert proc.returncode != 0
+    assert "is not a FastrAPI application" in output
@@ -0,0 +1,160 @@
+import os
+import socket
+import subprocess
+import sys
+from pathlib import Path
+
+
+def run_in(tmp_path: Path, body: str, env: dict[str, str] | None = None):
+    script = tmp_path / "config_app.py"
+    script.write_text(
+        f"""
+from fastrapi import FastrAPI
+
+{body}
+""",
+        encoding="utf-8",
+    )
+    full_env = {
+        key: value for key, value in os.environ.items() if not key.startswith("FASTRAPI_")
+    }
+    full_env.update(env or {})
+    return subprocess.run(
+        [sys.executable, str(script)],
+        cwd=tmp_path,
+        env=full_env,
+        capture_output=True,
+        text=True,
+        timeout=30,
+    )
+
+
+def test_precedence_of_files_environment_and_arguments(tmp_path: Path):
+    (tmp_path / "pyproject.toml").write_text(
+        """
+[project]
+name = "demo"
+
+[tool.fastrapi]
+max_body_size = 1024
+docs_url = "/from-pyproject"
+redoc_url = "/redoc-from-pyproject"
+""",
+        encoding="utf-8",
+    )
+    (tmp_path / "fastrapi.toml").write_text(
+        """
+docs_url = "/from-file"
+openapi_url = "/spec.json"
+request_timeout = 5
+""",
+        encoding="utf-8",
+    )
+
+    result = run_in(
+        tmp_path,
+        """
+app = FastrAPI()
+print(app.max_body_size, app.docs_url, app.redoc_url, app.openapi_url, app.request_timeout)
+print(FastrAPI(docs_url="/explicit", max_body_size=None).docs_url)
+""",
+        env={"FASTRAPI_DOCS_URL": "/from-env"},
+    )
+
+    assert result.returncode == 0, result.stderr
+    assert result.stdout.splitlines() == [
+        "1024 /from-env /redoc-from-pyproject /spec.json 5",
+        "/explicit",
+    ]
+
+
+def test_empty_docs_url_disables_the_page(tmp_path: Path):
+    result = run_in(
+        tmp_path, "print(FastrAPI().docs_url)", env={"FASTRAPI_DOCS_URL": ""}
+    )
+
+    assert result.returncode == 0, result.stderr
+    assert result.stdout.strip() == "None"
+
+
+def test_empty_openapi_url_disables_the_schema(tmp_path: Path):
+    result = run_in(
+        tmp_path,
+        """
+from fastrapi.testclient import TestClient
+
+app = FastrAPI()
+print(app.openapi_url)
+print(TestClient(app).get("/api-docs/openapi.json").status_code)
+""",
+        env={"FASTRAPI_OPENAPI_URL": ""},
+    )
+
+    assert result.returncode == 0, result.stderr
+    assert result.stdout.splitlines() == ["None", "404"]
+
+
+def test_configured_host_and_port_are_used_by_start(tmp_path: Path):
+    with socket.socket() as probe:
+        probe.bind(("127.0.0.1", 0))
+        port = probe.getsockname()[1]
+
+    result = run_in(
+        tmp_path,
+        """
+app = FastrAPI()
+handle = app.start()
+handle.wait_ready(timeout=10)
+print(handle.port)
+handle.stop(timeout=10)
+""",
+        env={"FASTRAPI_HOST": "127.0.0.1", "FASTRAPI_PORT": str(port)},
+    )
+
+    assert result.returncode == 0, result.stderr
+    assert result.stdout.strip().splitlines()[-1] == str(port)
+
+
+def test_invalid_environment_value_is_rejected(tmp_path: Path):
+    result = run_in(
+        tmp_path,
+        """
+try:
+    FastrAPI()
+except ValueError as err:
+    print(err)
+""",
+        env={"FASTRAPI_PORT": "eighty"},
+    )
+
+    assert result.returncode == 0, result.stderr
+    assert "FASTRAPI_PORT" in result.stdout
+
+
+def test_invalid_file_settings_are_rejected(tmp_path: Path):
+    (tmp_path / "fastrapi.toml").write_text(
+        'workers = 0\n', encoding="utf-8"
+    )
+    result = run_in(
+        tmp_path,
+        """
+try:
+    FastrAPI()
+except ValueError as err:
+    print(err)
+""",
+    )
+    assert "workers must be at least 1" in result.stdout
+
+    (tmp_path / "fastrapi.toml").write_text('prot = 8000\n', encoding="utf-8")
+    result = run_in(
+        tmp_path,
+        """
+try:
+    FastrAPI()
+except ValueError as err:
+    print(err)
+""",
+    )
+    assert "fastrapi.toml" in result.stdout
+    assert "prot" in result.stdout
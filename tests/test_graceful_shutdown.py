@@ -0,0 +1,99 @@
+import signal
+import subprocess
+import sys
+import threading
+import time
+from pathlib import Path
+
+import httpx
+import pytest
+
+from conftest import get_free_port, wait_for_server
+
+
+ROOT = Path(__file__).resolve().parents[1]
+
+pytestmark = pytest.mark.skipif(sys.platform == "win32", reason="needs SIGTERM")
+
+
+def test_sigterm_drains_requests_and_background_tasks(tmp_path: Path):
+    port = get_free_port()
+    events_file = tmp_path / "events.txt"
+    script_file = tmp_path / "graceful_app.py"
+    script_file.write_text(
+        f'''
+import time
+from pathlib import Path
+
+from fastrapi import FastrAPI
+from fastrapi.background import BackgroundTasks
+
+
+EVENTS = Path(r"{events_file}")
+
+
+def log_event(name: str) -> None:
+    with EVENTS.open("a", encoding="utf-8") as handle:
+        handle.write(name + "\\n")
+
+
+def slow_task() -> None:
+    time.sleep(1.0)
+    log_event("background_done")
+
+
+app = FastrAPI(on_shutdown=[lambda: log_event("shutdown")])
+
+
+@app.get("/ready")
+def ready():
+    return {{"ok": True}}
+
+
+@app.get("/slow")
+def slow(background_tasks: BackgroundTasks):
+    time.sleep(1.0)
+    background_tasks.add_task(slow_task)
+    log_event("request_done")
+    return {{"ok": True}}
+
+
+app.serve(host="127.0.0.1", port={port}, graceful_timeout=10.0)
+''',
+        encoding="utf-8",
+    )
+
+    proc = subprocess.Popen(
+        [sys.executable, str(script_file)],
+        cwd=ROOT,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+    )
+
+    try:
+        wait_for_server(proc, f"http://127.0.0.1:{port}/ready")
+
+        responses = []
+        request = threading.Thread(
+            target=lambda: responses.append(
+                httpx.get(f"http://127.0.0.1:{port}/slow", timeout=10.0)
+            )
+        )
+        request.start()
+        time.sleep(0.3)
+        proc.send_signal(signal.SIGTERM)
+        request.join(timeout=10.0)
+        proc.communicate(timeout=15.0)
+
+        assert responses and responses[0].status_code == 200
+        assert proc.returncode == 0
+        assert events_file.read_text(encoding="utf-8").splitlines() == [
+            "request_done",
+            "background_done",
+            "shutdown",
+        ]
+    finally:
+        if proc.poll() is None:
+            proc.kill()
+            proc.communicate(timeout=5.0)
@@ -0,0 +1,92 @@
+from fastrapi import FastrAPI
+from fastrapi.request import Request
+from fastrapi.responses import JSONResponse
+from fastrapi.testclient import TestClient
+
+
+def build_app():
+    app = FastrAPI()
+    calls = []
+
+    @app.get("/items")
+    def list_items():
+        calls.append("get")
+        return [{"id": 1}, {"id": 2}]
+
+    @app.get("/items/{item_id}")
+    def read_item(item_id: int):
+        calls.append("get_item")
+        return {"item_id": item_id}
+
+    @app.post("/items")
+    def create_item():
+        return {}
+
+    return app, calls
+
+
+def test_head_runs_get_and_drops_the_body():
+    app, calls = build_app()
+    client = TestClient(app)
+    expected = client.get("/items")
+    response = client.head("/items")
+    assert response.status_code == 200
+    assert response.content == b""
+    assert response.headers["content-length"] == str(len(expected.content))
+    assert response.headers["content-type"] == expected.headers["content-type"]
+    assert calls == ["get", "get"]
+
+
+def test_head_on_param_route():
+    app, calls = build_app()
+    response = TestClient(app).head("/items/7")
+    assert response.status_code == 200
+    assert response.content == b""
+    assert response.headers["content-length"] == str(len(b'{"item_id":7}'))
+    assert calls == ["get_item"]
+
+
+def test_head_without_get_route_is_405():
+    app = FastrAPI()
+
+    @app.post("/submit")
+    def submit():
+        return {}
+
+    response = TestClient(app).head("/submit")
+    assert response.status_code == 405
+    assert response.headers["allow"] == "POST, OPTIONS"
+
+
+def test_options_lists_allowed_methods():
+    app, _ = build_app()
+    client = TestClient(app)
+    response = client.options("/items")
+    assert response.status_code == 204
+    assert response.headers["allow"] == "GET, HEAD, POST, OPTIONS"
+    assert client.options("/items/3").headers["allow"] == "GET, HEAD, OPTIONS"
+    assert client.options("/missing").status_code == 404
+
+
+def test_explicit_head_and_options_win():
+    app = FastrAPI()
+
+    @app.get("/")
+    def index():
+        return {"hello": "world"}
+
+    # takes the request, so it is dispatched while GET / is served by axum
+    @app.head("/")
+    def index_head(request: Request):
+        return JSONResponse({}, headers={"x-head": "explicit"})
+
+    @app.options("/")
+    def index_options():
+        return {"methods": ["GET"]}
+
+    client = TestClient(app)
+    head = client.head("/")
+    assert head.headers["x-head"] == "explicit"
+    options = client.options("/")
+    assert options.status_code == 200
+    assert options.json() == {"methods": ["GET"]}
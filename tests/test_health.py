@@ -0,0 +1,111 @@
+import asyncio
+import time
+
+import httpx
+import pytest
+
+from fastrapi import FastrAPI
+from fastrapi.testclient import TestClient
+
+
+def test_probes_are_opt_in():
+    client = TestClient(FastrAPI())
+    assert client.get("/healthz").status_code == 404
+    assert client.get("/readyz").status_code == 404
+
+
+def test_health_and_ready_after_startup():
+    started = []
+    app = FastrAPI(
+        health_url="/healthz",
+        ready_url="/readyz",
+        on_startup=[lambda: started.append(True)],
+    )
+
+    with app.start("127.0.0.1", 0) as server, httpx.Client(
+        base_url=f"http://127.0.0.1:{server.port}", timeout=5
+    ) as client:
+        assert started == [True]
+        health = client.get("/healthz")
+        assert health.status_code == 200
+        assert health.json() == {"status": "ok"}
+        ready = client.get("/readyz")
+        assert ready.status_code == 200
+        assert ready.json() == {"status": "ready"}
+
+        paths = client.get("/api-docs/openapi.json").json()["paths"]
+        assert "/healthz" not in paths
+        assert "/readyz" not in paths
+
+
+def test_readiness_checks_report_each_result():
+    app = FastrAPI(ready_url="/readyz")
+
+    @app.readiness_check("database")
+    def database():
+        return True
+
+    @app.readiness_check()
+    async def cache():
+        await asyncio.sleep(0)
+
+    @app.readiness_check("queue", timeout=0.1)
+    def queue():
+        time.sleep(1)
+
+    @app.readiness_check("search")
+    def search():
+        raise RuntimeError("search is down")
+
+    with TestClient(app) as client:
+        response = client.get("/readyz")
+        assert response.status_code == 503
+        assert response.json() == {
+            "status": "unavailable",
+            "checks": {
+                "cache": "ok",
+                "database": "ok",
+                "queue": "timeout",
+                "search": "failed",
+            },
+        }
+
+
+def test_ready_once_every_check_passes():
+    app = FastrAPI(ready_url="/readyz")
+    database_up = {"value": False}
+
+    @app.readiness_check("database")
+    def database():
+        return database_up["value"]
+
+    with TestClient(app) as client:
+        response = client.get("/readyz")
+        assert response.status_code == 503
+        assert response.json()["checks"] == {"database": "failed"}
+
+        database_up["value"] = True
+        response = client.get("/readyz")
+        assert response.status_code == 200
+        assert response.json() == {"status": "ready", "checks": {"database": "ok"}}
+
+
+@pytest.mark.parametrize(
+    ("kwargs", "message"),
+    [
+        ({"health_url": "healthz"}, "must start with '/'"),
+        ({"ready_url": "readyz"}, "must start with '/'"),
+        ({"health_url": "/probe", "ready_url": "/probe"}, "must be different"),
+        ({"health_url": "/docs"}, "docs_url"),
+        ({"ready_url": "/api-docs/openapi.json"}, "openapi_url"),
+    ],
+)
+def test_probe_urls_are_validated(kwargs, message):
+    with pytest.raises(ValueError, match=message):
+        FastrAPI(**kwargs)
+
+
+def test_readiness_check_timeout_is_validated():
+    app = FastrAPI()
+    with pytest.raises(ValueError, match="timeout"):
+        app.readiness_check(timeout=0)
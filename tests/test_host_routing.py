@@ -0,0 +1,250 @@
+import pytest
+
+from fastrapi import APIRouter, Depends, FastrAPI
+from fastrapi.request import Request
+from fastrapi.testclient import TestClient
+
+
+def test_routes_are_selected_by_host():
+    app = FastrAPI()
+    admin = APIRouter()
+
+    @admin.get("/")
+    def admin_home():
+        return {"site": "admin"}
+
+    @app.get("/")
+    def home():
+        return {"site": "main"}
+
+    app.include_router(admin, host="admin.example.com")
+
+    client = TestClient(app)
+    assert client.get("http://admin.example.com/").json() == {"site": "admin"}
+    assert client.get("http://admin.example.com:8000/").json() == {"site": "admin"}
+    assert client.get("http://www.example.com/").json() == {"site": "main"}
+    assert client.get("/").json() == {"site": "main"}
+
+
+def test_plain_routes_keep_their_fast_path():
+    app = FastrAPI()
+    admin = APIRouter(host="admin.example.com")
+    calls = []
+
+    @admin.get("/")
+    def admin_home():
+        return {"site": "admin"}
+
+    @app.get("/", cache_resp=True)
+    def home():
+        calls.append("/")
+        return {"site": "main"}
+
+    @app.get("/about", cache_resp=True)
+    def about():
+        calls.append("/about")
+        return {"page": "about"}
+
+    app.include_router(admin)
+
+    with TestClient(app) as client:
+        for _ in range(3):
+            assert client.get("/").json() == {"site": "main"}
+            assert client.get("/about").json() == {"page": "about"}
+        assert client.get("http://admin.example.com/").json() == {"site": "admin"}
+
+    # /about is rendered once at startup; / is shared with the host router,
+    # so it goes through host routing on every request
+    assert calls.count("/about") == 1
+    assert calls.count("/") == 3
+
+
+def test_host_parameters_are_injected():
+    app = FastrAPI()
+    tenants = APIRouter(host="{tenant}.example.com")
+
+    @tenants.get("/items/{item_id}")
+    def item(tenant: str, item_id: int):
+        return {"tenant": tenant, "item_id": item_id}
+
+    app.include_router(tenants)
+
+    client = TestClient(app)
+    response = client.get("http://acme.example.com/items/3")
+    assert response.json() == {"tenant": "acme", "item_id": 3}
+    assert client.get("http://example.com/items/3").status_code == 404
+    assert client.get("http://a.b.example.com/items/3").status_code == 404
+
+
+def test_hosts_match_regardless_of_case():
+    app = FastrAPI()
+    tenants = APIRouter(host="{tenant}.Example.com")
+
+    @tenants.get("/")
+    def home(tenant: str):
+        return {"tenant": tenant}
+
+    app.include_router(tenants)
+
+    client = TestClient(app)
+    assert client.get("http://acme.EXAMPLE.com/").json() == {"tenant": "acme"}
+    assert client.get("/", headers={"Host": "ACME.example.COM"}).json() == {
+        "tenant": "ACME"
+    }
+
+
+def test_host_parameters_reach_dependencies_and_path_params():
+    app = FastrAPI()
+    tenants = APIRouter()
+
+    def current_tenant(tenant: str):
+        return tenant.upper()
+
+    @tenants.get("/whoami")
+    def whoami(request: Request, name: str = Depends(current_tenant)):
+        return {"name": name, "path_params": request.path_params}
+
+    app.include_router(tenants, prefix="/api", host="{tenant}.example.com")
+
+    client = TestClient(app)
+    assert client.get("http://acme.example.com/api/whoami").json() == {
+        "name": "ACME",
+        "path_params": {"tenant": "acme"},
+    }
+
+
+def test_host_converters_are_checked():
+    app = FastrAPI()
+    shards = APIRouter()
+
+    @shards.get("/")
+    def shard(shard: int):
+        return {"shard": shard}
+
+    app.include_router(shards, host="shard{shard:int}.example.com")
+
+    client = TestClient(app)
+    assert client.get("http://shard7.example.com/").json() == {"shard": 7}
+    assert client.get("http://shardx.example.com/").status_code == 404
+
+
+def test_innermost_host_wins():
+    app = FastrAPI()
+    outer = APIRouter(host="outer.example.com")
+    inner = APIRouter(host="inner.example.com")
+
+    @outer.get("/outer")
+    def outer_route():
+        return {"router": "outer"}
+
+    @inner.get("/inner")
+    def inner_route():
+        return {"router": "inner"}
+
+    outer.include_router(inner)
+    app.include_router(outer)
+
+    client = TestClient(app)
+    assert client.get("http://outer.example.com/outer").status_code == 200
+    assert client.get("http://inner.example.com/inner").status_code == 200
+    assert client.get("http://outer.example.com/inner").status_code == 404
+
+
+def test_method_not_allowed_under_host():
+    app = FastrAPI()
+    tenants = APIRouter(host="{tenant}.example.com")
+
+    @tenants.post("/orders")
+    def create_order(tenant: str):
+        return {"tenant": tenant}
+
+    app.include_router(tenants)
+
+    client = TestClient(app)
+    response = client.get("http://acme.example.com/orders")
+    assert response.status_code == 405
+    assert response.headers["allow"] == "POST, OPTIONS"
+
+
+def test_route_for_the_method_wins_over_host_router_with_other_methods():
+    app = FastrAPI()
+    admin = APIRouter(host="admin.example.com")
+
+    @admin.post("/items")
+    def admin_create():
+        return {"site": "admin"}
+
+    @app.get("/items")
+    def items():
+        return {"site": "main"}
+
+    app.include_router(admin)
+
+    client = TestClient(app)
+    assert client.post("http://admin.example.com/items").json() == {"site": "admin"}
+    assert client.get("http://admin.example.com/items").json() == {"site": "main"}
+    assert client.delete("http://admin.example.com/items").status_code == 405
+
+
+def test_host_parameters_are_left_out_of_openapi():
+    app = FastrAPI()
+    tenants = APIRouter(host="{tenant}.example.com")
+
+    @tenants.get("/items/{item_id}")
+    def item(tenant: str, item_id: int):
+        return {"tenant": tenant}
+
+    app.include_router(tenants)
+
+    paths = TestClient(app).get("/api-docs/openapi.json").json()["paths"]
+    parameters = paths["/items/{item_id}"]["get"]["parameters"]
+    assert [param["name"] for param in parameters] == ["item_id"]
+
+
+def test_websocket_routes_are_selected_by_host():
+    app = FastrAPI()
+    admin = APIRouter(host="admin.example.com")
+    private = APIRouter(host="private.example.com")
+
+    @admin.websocket("/ws")
+    async def admin_ws(ws):
+        await ws.accept()
+        await ws.send_text("admin")
+        await ws.close()
+
+    @private.websocket("/private")
+    async def private_ws(ws):
+        await ws.accept()
+        await ws.send_text("private")
+        await ws.close()
+
+    @app.websocket("/ws")
+    async def main_ws(ws):
+        await ws.accept()
+        await ws.send_text("main")
+        await ws.close()
+
+    app.include_router(admin)
+    app.include_router(private)
+
+    with TestClient(app) as client:
+        with client.websocket_connect("http://admin.example.com/ws") as ws:
+            assert ws.receive_text() == "admin"
+        with client.websocket_connect("http://www.example.com/ws") as ws:
+            assert ws.receive_text() == "main"
+        with pytest.raises(ConnectionError):
+            client.websocket_connect("http://www.example.com/private")
+
+
+def test_router_exposes_its_host():
+    assert APIRouter(host="{tenant}.example.com").host == "{tenant}.example.com"
+    assert APIRouter().host is None
+
+
+@pytest.mark.parametrize(
+    "host",
+    ["{tenant.example.com", "{tenant:nope}.example.com", "{}.example.com"],
+)
+def test_invalid_host_patterns_are_rejected(host):
+    with pytest.raises(ValueError):
+        APIRouter(host=host)
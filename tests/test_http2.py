@@ -0,0 +1,122 @@
+import json
+import socket
+import struct
+import subprocess
+import sys
+import time
+from pathlib import Path
+
+import pytest
+
+from conftest import get_free_port
+
+
+PREFACE = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n"
+DATA, HEADERS, SETTINGS, GOAWAY = 0x0, 0x1, 0x4, 0x7
+END_STREAM, ACK, END_HEADERS = 0x1, 0x1, 0x4
+
+
+def frame(kind: int, flags: int, stream_id: int, payload: bytes = b"") -> bytes:
+    return struct.pack(">I", len(payload))[1:] + bytes([kind, flags]) + struct.pack(
+        ">I", stream_id
+    ) + payload
+
+
+def read_exact(sock: socket.socket, size: int) -> bytes:
+    data = b""
+    while len(data) < size:
+        chunk = sock.recv(size - len(data))
+        assert chunk, "connection closed"
+        data += chunk
+    return data
+
+
+def read_frame(sock: socket.socket):
+    header = read_exact(sock, 9)
+    length = int.from_bytes(header[:3], "big")
+    kind, flags = header[3], header[4]
+    stream_id = int.from_bytes(header[5:9], "big") & 0x7FFFFFFF
+    return kind, flags, stream_id, read_exact(sock, length)
+
+
+@pytest.fixture
+def h2c_port(tmp_path: Path):
+    port = get_free_port()
+    script = tmp_path / "h2c_app.py"
+    script.write_text(
+        f'''
+from fastrapi import FastrAPI
+
+app = FastrAPI()
+
+
+@app.get("/")
+def index():
+    return {{"ok": True}}
+
+
+app.serve(host="127.0.0.1", port={port}, http2=True)
+'''
+    )
+    proc = subprocess.Popen(
+        [sys.executable, str(script)],
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+    )
+    deadline = time.time() + 15
+    while True:
+        if proc.poll() is not None:
+            raise AssertionError(f"server exited early\n{proc.stdout.read()}")
+        try:
+            socket.create_connection(("127.0.0.1", port), timeout=0.5).close()
+            break
+        except OSError:
+            assert time.time() < deadline, "server did not start in time"
+            time.sleep(0.1)
+    yield port
+    proc.terminate()
+    proc.wait(timeout=15)
+
+
+def test_prior_knowledge_h2c_request(h2c_port: int):
+    # :method GET, :scheme http, :path / from the static table, then a
+    # literal :authority
+    block = bytes([0x82, 0x86, 0x84, 0x41, 9]) + b"localhost"
+
+    with socket.create_connection(("127.0.0.1", h2c_port), timeout=5) as sock:
+        sock.sendall(
+            PREFACE
+            + frame(SETTINGS, 0, 0)
+            + frame(HEADERS, END_STREAM | END_HEADERS, 1, block)
+        )
+
+        kind, flags, _, _ = read_frame(sock)
+        assert kind == SETTINGS and not flags & ACK
+        sock.sendall(frame(SETTINGS, ACK, 0))
+
+        status_block, body = None, b""
+        while True:
+            kind, flags, stream_id, payload = read_frame(sock)
+            assert kind != GOAWAY, payload
+            if stream_id != 1:
+                continue
+            if kind == HEADERS:
+                status_block = payload
+            elif kind == DATA:
+                body += payload
+            if flags & END_STREAM:
+                break
+
+    # indexed :status 200
+    assert status_block is not None and status_block[0] == 0x88
+    assert json.loads(body) == {"ok": True}
+
+
+def test_http1_still_served_with_http2_enabled(h2c_port: int):
+    with socket.create_connection(("127.0.0.1", h2c_port), timeout=5) as sock:
+        sock.sendall(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        response = b""
+        while chunk := sock.recv(4096):
+            response += chunk
+    assert response.startswith(b"HTTP/1.1 200")
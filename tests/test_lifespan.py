@@ -1,43 +1,13 @@
 import signal
-import socket
 import subprocess
 import sys
-import time
 from pathlib import Path
 import httpx
 
+from conftest import get_free_port, wait_for_server
 
-ROOT = Path(__file__).resolve().parents[1]
-
-
-def get_free_port() -> int:
-    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as sock:
-        sock.bind(("127.0.0.1", 0))
-        return sock.getsockname()[1]
-
-
-def wait_for_server(
-    proc: subprocess.Popen[str], port: int, path: str = "/", timeout: float = 15.0
-):
-    deadline = time.time() + timeout
-    last_error = None
-
-    while time.time() < deadline:
-        if proc.poll() is not None:
-            output = proc.stdout.read() if proc.stdout is not None else ""
-            raise AssertionError(
-                f"server exited early with code {proc.returncode}\n{output}"
-            )
-
-        try:
-            response = httpx.get(f"http://127.0.0.1:{port}{path}", timeout=0.5)
-            return response
-        except Exception as exc:  # pragma: no cover - retry loop
-            last_error = exc
-            time.sleep(0.1)
 
-    output = proc.stdout.read() if proc.stdout is not None else ""
-    raise AssertionError(f"server did not start in time: {last_error}\n{output}")
+ROOT = Path(__file__).resolve().parents[1]
 
 
 def stop_process(proc: subprocess.Popen[str], timeout: float = 10.0) -> str:
@@ -137,7 +107,7 @@ app.serve(host="127.0.0.1", port={port})
     )
 
     try:
-        response = wait_for_server(proc, port, "/state")
+        response = wait_for_server(proc, f"http://127.0.0.1:{port}/state")
         assert response.status_code == 200
         assert response.json() == {
             "title": "started",
@@ -222,7 +192,7 @@ app.serve(host="127.0.0.1", port={port})
     )
 
     try:
-        response = wait_for_server(proc, port, "/events")
+        response = wait_for_server(proc, f"http://127.0.0.1:{port}/events")
         assert response.status_code == 200
         assert response.json() == ["startup_sync", "startup_async"]
 
@@ -238,3 +208,56 @@ app.serve(host="127.0.0.1", port={port})
         if proc.poll() is None:
             proc.kill()
             proc.communicate(timeout=5.0)
+
+
+def test_start_returns_handle_and_stop_exits_lifespan():
+    from contextlib import asynccontextmanager
+
+    from fastrapi import FastrAPI
+
+    events = []
+
+    @asynccontextmanager
+    async def lifespan(app):
+        events.append("enter")
+        yield
+        events.append("exit")
+
+    app = FastrAPI(lifespan=lifespan)
+
+    @app.get("/events")
+    def get_events():
+        return list(events)
+
+    handle = app.start("127.0.0.1", 0)
+    try:
+        handle.wait_ready(timeout=10)
+        assert handle.port and handle.port != 0
+        response = httpx.get(f"http://127.0.0.1:{handle.port}/events", timeout=5)
+        assert response.json() == ["enter"]
+    finally:
+        handle.stop(timeout=10)
+
+    assert not handle.running
+    assert events == ["enter", "exit"]
+
+
+def test_handle_rejects_invalid_timeouts():
+    import math
+
+    import pytest
+
+    from fastrapi import FastrAPI
+
+    app = FastrAPI()
+    handle = app.start("127.0.0.1", 0)
+    try:
+        for timeout in (-1.0, math.nan, math.inf):
+            with pytest.raises(ValueError):
+                handle.wait_ready(timeout=timeout)
+            with pytest.raises(ValueError):
+                handle.stop(timeout=timeout)
+        handle.wait_ready(timeout=10)
+        assert handle.running
+    finally:
+        handle.stop(timeout=10)
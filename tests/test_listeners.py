@@ -0,0 +1,132 @@
+import os
+import socket
+import stat
+import subprocess
+import sys
+import threading
+import time
+from pathlib import Path
+
+import httpx
+import pytest
+
+from fastrapi import FastrAPI
+
+
+pytestmark = pytest.mark.skipif(
+    sys.platform == "win32", reason="unix sockets and fd passing are unix only"
+)
+
+
+def _serve(app: FastrAPI, client: httpx.Client, **kwargs) -> httpx.Client:
+    thread = threading.Thread(target=lambda: app.serve(**kwargs), daemon=True)
+    thread.start()
+    deadline = time.time() + 10.0
+    while time.time() < deadline:
+        try:
+            client.get("/ping")
+            return client
+        except httpx.TransportError:
+            time.sleep(0.05)
+    raise RuntimeError("server never became ready")
+
+
+def test_serve_on_unix_socket(tmp_path: Path):
+    path = tmp_path / "app.sock"
+    app = FastrAPI()
+
+    @app.get("/ping")
+    def ping():
+        return {"pong": True}
+
+    client = httpx.Client(
+        transport=httpx.HTTPTransport(uds=str(path)), base_url="http://fastrapi"
+    )
+    _serve(app, client, uds=str(path), uds_mode=0o600)
+
+    assert stat.S_ISSOCK(path.stat().st_mode)
+    assert stat.S_IMODE(path.stat().st_mode) == 0o600
+    assert client.get("/ping").json() == {"pong": True}
+
+
+def test_serve_adopts_inherited_fd():
+    sock = socket.socket(socket.AF_INET, socket.SOCK_STREAM)
+    sock.bind(("127.0.0.1", 0))
+    sock.listen()
+    port = sock.getsockname()[1]
+
+    app = FastrAPI()
+
+    @app.get("/ping")
+    def ping():
+        return {"pong": True}
+
+    # ownership of the descriptor moves to the server
+    client = httpx.Client(base_url=f"http://127.0.0.1:{port}")
+    _serve(app, client, fd=sock.detach())
+
+    assert client.get("/ping").json() == {"pong": True}
+
+
+def test_socket_activation_wins_over_a_configured_port(tmp_path: Path):
+    with socket.socket() as probe:
+        probe.bind(("127.0.0.1", 0))
+        configured_port = probe.getsockname()[1]
+
+    script = tmp_path / "activated_app.py"
+    script.write_text(
+        """
+import json
+import os
+import socket
+import urllib.request
+
+from fastrapi import FastrAPI
+
+# what systemd hands over: the listening socket as descriptor 3
+sock = socket.socket(socket.AF_INET, socket.SOCK_STREAM)
+sock.bind(("127.0.0.1", 0))
+sock.listen()
+os.dup2(sock.fileno(), 3)
+os.environ["LISTEN_FDS"] = "1"
+os.environ["LISTEN_PID"] = str(os.getpid())
+
+app = FastrAPI()
+
+
+@app.get("/ping")
+def ping():
+    return {"pong": True}
+
+
+handle = app.start()
+handle.wait_ready(timeout=10)
+print("inherited", handle.port == sock.getsockname()[1])
+print("pong", json.load(urllib.request.urlopen(f"http://127.0.0.1:{handle.port}/ping"))["pong"])
+handle.stop(timeout=10)
+""",
+        encoding="utf-8",
+    )
+    env = {key: value for key, value in os.environ.items() if not key.startswith("FASTRAPI_")}
+    env["FASTRAPI_PORT"] = str(configured_port)
+
+    result = subprocess.run(
+        [sys.executable, str(script)],
+        cwd=tmp_path,
+        env=env,
+        capture_output=True,
+        text=True,
+        timeout=30,
+    )
+
+    assert result.returncode == 0, result.stderr
+    lines = result.stdout.splitlines()
+    assert "inherited True" in lines
+    assert "pong True" in lines
+
+
+def test_uds_and_fd_are_exclusive():
+    app = FastrAPI()
+
+    with pytest.raises(ValueError):
+        app.serve(uds="/tmp/fastrapi.sock", fd=3)
@@ -0,0 +1,121 @@
+import json
+import subprocess
+import sys
+from pathlib import Path
+
+import pytest
+
+from fastrapi import FastrAPI
+
+
+ROOT = Path(__file__).resolve().parents[1]
+
+
+def run_script(tmp_path: Path, body: str) -> subprocess.CompletedProcess[str]:
+    script = tmp_path / "logging_app.py"
+    script.write_text(
+        f"""
+import httpx
+import logging
+
+from fastrapi import FastrAPI
+
+app = FastrAPI()
+
+
+@app.get("/boom")
+def boom():
+    raise RuntimeError("kaboom")
+
+{body}
+""",
+        encoding="utf-8",
+    )
+    return subprocess.run(
+        [sys.executable, str(script)],
+        cwd=ROOT,
+        capture_output=True,
+        text=True,
+        timeout=30,
+    )
+
+
+def test_json_log_format(tmp_path: Path):
+    result = run_script(
+        tmp_path,
+        """
+handle = app.start("127.0.0.1", 0, log_format="json", log_level="warning")
+handle.wait_ready(timeout=10)
+httpx.get(f"http://127.0.0.1:{handle.port}/boom", timeout=5)
+handle.stop(timeout=10)
+""",
+    )
+    assert result.returncode == 0, result.stderr
+
+    records = [
+        json.loads(line) for line in result.stdout.splitlines() if line.startswith("{")
+    ]
+    python_errors = [record for record in records if record["target"] == "fastrapi::python"]
+    assert python_errors
+    assert python_errors[0]["level"] == "ERROR"
+    assert "kaboom" in python_errors[0]["message"]
+    # the info-level startup lines are filtered out by log_level
+    assert all(record["level"] in ("WARN", "ERROR") for record in records)
+
+
+def test_events_are_forwarded_to_python_logging(tmp_path: Path):
+    result = run_script(
+        tmp_path,
+        """
+records = []
+
+
+class Collect(logging.Handler):
+    def emit(self, record):
+        records.append((record.name, record.levelname, record.getMessage()))
+
+
+logging.getLogger("fastrapi").addHandler(Collect())
+logging.getLogger("fastrapi").setLevel(logging.DEBUG)
+
+handle = app.start("127.0.0.1", 0, log_to_python=True)
+handle.wait_ready(timeout=10)
+httpx.get(f"http://127.0.0.1:{handle.port}/boom", timeout=5)
+handle.stop(timeout=10)
+
+errors = [r for r in records if r[0] == "fastrapi.python"]
+assert errors, records
+assert errors[0][1] == "ERROR"
+assert "kaboom" in errors[0][2]
+print("forwarded")
+""",
+    )
+    assert result.returncode == 0, result.stderr
+    assert "forwarded" in result.stdout
+
+
+def test_log_filter_overrides_level_per_target(tmp_path: Path):
+    result = run_script(
+        tmp_path,
+        """
+handle = app.start(
+    "127.0.0.1", 0, log_format="json", log_level="info", log_filter="fastrapi::python=off"
+)
+handle.wait_ready(timeout=10)
+httpx.get(f"http://127.0.0.1:{handle.port}/boom", timeout=5)
+handle.stop(timeout=10)
+""",
+    )
+    assert result.returncode == 0, result.stderr
+    assert "fastrapi::python" not in result.stdout
+
+
+@pytest.mark.parametrize(
+    "options",
+    [{"log_format": "xml"}, {"log_level": "loud"}, {"log_filter": "fastrapi=notalevel"}],
+)
+def test_invalid_log_options_are_rejected(options):
+    app = FastrAPI()
+
+    with pytest.raises(ValueError):
+        app.start("127.0.0.1", 0, **options)
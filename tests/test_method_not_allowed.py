@@ -0,0 +1,80 @@
+from fastrapi import FastrAPI, HTTPException
+from fastrapi.responses import JSONResponse
+from fastrapi.testclient import TestClient
+
+
+def build_app():
+    app = FastrAPI()
+
+    @app.get("/items")
+    def list_items():
+        return []
+
+    @app.post("/items")
+    def create_item():
+        return {}
+
+    @app.put("/items/{item_id}")
+    def update_item(item_id: int):
+        return {"item_id": item_id}
+
+    return app
+
+
+def test_wrong_method_on_static_path_lists_allowed_methods():
+    client = TestClient(build_app())
+    response = client.delete("/items")
+    assert response.status_code == 405
+    assert response.headers["allow"] == "GET, HEAD, POST, OPTIONS"
+    assert response.json() == {"detail": "Method Not Allowed"}
+
+
+def test_wrong_method_on_param_path_lists_allowed_methods():
+    client = TestClient(build_app())
+    response = client.get("/items/3")
+    assert response.status_code == 405
+    assert response.headers["allow"] == "PUT, OPTIONS"
+
+
+def test_unsupported_method_gets_allow_header():
+    client = TestClient(build_app())
+    response = client.request("PURGE", "/items")
+    assert response.status_code == 405
+    assert response.headers["allow"] == "GET, HEAD, POST, OPTIONS"
+
+
+def test_unknown_path_is_still_404():
+    client = TestClient(build_app())
+    assert client.delete("/missing").status_code == 404
+    assert client.request("PURGE", "/missing").status_code == 404
+
+
+def test_405_goes_through_exception_handler():
+    app = build_app()
+    seen = []
+
+    @app.exception_handler(405)
+    async def method_not_allowed(request, exc):
+        seen.append((request.scope["method"], exc.status_code))
+        return JSONResponse(
+            {"error": "wrong method", "allowed": exc.headers["Allow"]},
+            status_code=405,
+        )
+
+    client = TestClient(app)
+    response = client.patch("/items")
+    assert response.status_code == 405
+    assert response.json() == {"error": "wrong method", "allowed": "GET, HEAD, POST, OPTIONS"}
+    assert seen == [("PATCH", 405)]
+
+
+def test_http_exception_handler_covers_405():
+    app = build_app()
+
+    @app.exception_handler(HTTPException)
+    def http_error(request, exc):
+        return JSONResponse({"status": exc.status_code}, status_code=exc.status_code)
+
+    response = TestClient(app).delete("/items")
+    assert response.status_code == 405
+    assert response.json() == {"status": 405}
@@ -0,0 +1,129 @@
+import json
+
+import pytest
+
+from fastrapi import FastrAPI
+from fastrapi.testclient import TestClient
+
+
+async def echo_app(scope, receive, send):
+    if scope["type"] == "websocket":
+        assert (await receive())["type"] == "websocket.connect"
+        await send({"type": "websocket.accept"})
+        while True:
+            message = await receive()
+            if message["type"] == "websocket.disconnect":
+                return
+            await send({"type": "websocket.send", "text": "echo: " + message["text"]})
+
+    body = b""
+    while True:
+        message = await receive()
+        body += message.get("body", b"")
+        if not message.get("more_body"):
+            break
+
+    payload = json.dumps(
+        {
+            "method": scope["method"],
+            "path": scope["path"],
+            "root_path": scope["root_path"],
+            "query": scope["query_string"].decode(),
+            "body": body.decode(),
+        }
+    ).encode()
+    await send(
+        {
+            "type": "http.response.start",
+            "status": 200,
+            "headers": [(b"content-type", b"application/json"), (b"x-legacy", b"1")],
+        }
+    )
+    await send({"type": "http.response.body", "body": payload[:10], "more_body": True})
+    await send({"type": "http.response.body", "body": payload[10:]})
+
+
+def test_mounted_asgi_app_gets_root_path_and_body():
+    app = FastrAPI()
+    app.mount("/legacy", echo_app)
+
+    with TestClient(app) as client:
+        response = client.post("/legacy/items", params={"page": "2"}, content=b"hello")
+
+    assert response.status_code == 200
+    assert response.headers["x-legacy"] == "1"
+    assert response.json() == {
+        "method": "POST",
+        "path": "/legacy/items",
+        "root_path": "/legacy",
+        "query": "page=2",
+        "body": "hello",
+    }
+
+
+def test_mounted_fastrapi_app():
+    sub = FastrAPI()
+
+    @sub.get("/hello")
+    def hello():
+        return {"from": "sub"}
+
+    app = FastrAPI()
+    app.mount("/sub", sub)
+
+    with TestClient(app) as client:
+        assert client.get("/sub/hello").json() == {"from": "sub"}
+        assert client.get("/sub/missing").status_code == 404
+
+
+def test_root_mount_only_receives_unmatched_requests():
+    app = FastrAPI()
+
+    @app.get("/native")
+    def native():
+        return {"from": "native"}
+
+    app.mount("/", echo_app)
+
+    with TestClient(app) as client:
+        assert client.get("/native").json() == {"from": "native"}
+        legacy = client.get("/anything/else").json()
+
+    assert legacy["path"] == "/anything/else"
+    assert legacy["root_path"] == ""
+
+
+def test_root_mount_receives_methods_the_native_path_lacks():
+    app = FastrAPI()
+
+    @app.post("/items")
+    def create_item():
+        return {"from": "native"}
+
+    app.mount("/", echo_app)
+
+    with TestClient(app) as client:
+        assert client.post("/items").json() == {"from": "native"}
+        legacy = client.get("/items")
+
+    # the legacy app keeps GET /items rather than a 405 from the native route
+    assert legacy.status_code == 200
+    assert legacy.json()["method"] == "GET"
+    assert legacy.json()["path"] == "/items"
+
+
+def test_mounted_app_websocket():
+    app = FastrAPI()
+    app.mount("/legacy", echo_app)
+
+    with TestClient(app) as client:
+        with client.websocket_connect("/legacy/ws") as ws:
+            ws.send_text("ping")
+            assert ws.receive_text() == "echo: ping"
+
+
+def test_mount_rejects_non_asgi_objects():
+    app = FastrAPI()
+
+    with pytest.raises(TypeError):
+        app.mount("/nope", object())
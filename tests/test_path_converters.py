@@ -0,0 +1,135 @@
+import uuid
+
+import pytest
+
+from fastrapi import FastrAPI
+from fastrapi.routing import register_converter
+from fastrapi.testclient import TestClient
+
+
+def test_int_and_str_routes_coexist():
+    app = FastrAPI()
+
+    @app.get("/items/{item_id:int}")
+    def by_id(item_id: int):
+        return {"id": item_id}
+
+    @app.get("/items/{slug}")
+    def by_slug(slug: str):
+        return {"slug": slug}
+
+    client = TestClient(app)
+    assert client.get("/items/42").json() == {"id": 42}
+    assert client.get("/items/hello").json() == {"slug": "hello"}
+
+
+def test_refused_converters_fall_through_to_other_patterns():
+    app = FastrAPI()
+
+    @app.get("/files/{file_id:int}/meta")
+    def meta(file_id: int):
+        return {"meta": file_id}
+
+    @app.get("/files/latest/{field}")
+    def latest(field: str):
+        return {"latest": field}
+
+    @app.get("/files/{name}/{kind}")
+    def by_kind(name: str, kind: str):
+        return {"name": name, "kind": kind}
+
+    @app.get("/users/{user_id}")
+    def user(user_id: str):
+        return {"user": user_id}
+
+    client = TestClient(app)
+    assert client.get("/files/3/meta").json() == {"meta": 3}
+    assert client.get("/files/latest/meta").json() == {"latest": "meta"}
+    assert client.get("/files/abc/meta").json() == {"name": "abc", "kind": "meta"}
+    assert client.get("/users/abc").json() == {"user": "abc"}
+
+
+def test_non_matching_values_are_404():
+    app = FastrAPI()
+
+    @app.get("/users/{user_id:int}")
+    def user(user_id: int):
+        return {"user_id": user_id}
+
+    @app.get("/prices/{amount:float}")
+    def price(amount: float):
+        return {"amount": amount}
+
+    @app.get("/orders/{order_id:uuid}")
+    def order(order_id: str):
+        return {"order_id": order_id}
+
+    client = TestClient(app)
+    assert client.get("/users/7").json() == {"user_id": 7}
+    assert client.get("/users/seven").status_code == 404
+    assert client.get("/users/-7").status_code == 404
+    assert client.get("/prices/2.50").json() == {"amount": 2.5}
+    assert client.get("/prices/2.").status_code == 404
+    order_id = str(uuid.uuid4())
+    assert client.get(f"/orders/{order_id}").json() == {"order_id": order_id}
+    assert client.get("/orders/not-a-uuid").status_code == 404
+
+
+def test_path_converter_spans_segments():
+    app = FastrAPI()
+
+    @app.get("/files/{file_path:path}")
+    def read_file(file_path: str):
+        return {"file_path": file_path}
+
+    @app.get("/files/{name:int}")
+    def numbered(name: int):
+        return {"number": name}
+
+    client = TestClient(app)
+    assert client.get("/files/docs/guide/index.md").json() == {
+        "file_path": "docs/guide/index.md"
+    }
+    assert client.get("/files/3").json() == {"number": 3}
+    # a refused converter falls through to the catch-all
+    assert client.get("/files/readme").json() == {"file_path": "readme"}
+
+
+def test_custom_regex_converter():
+    register_converter("lang", "[a-z]{2}")
+    app = FastrAPI()
+
+    @app.get("/docs/{code:lang}")
+    def docs(code: str):
+        return {"code": code}
+
+    client = TestClient(app)
+    assert client.get("/docs/en").json() == {"code": "en"}
+    assert client.get("/docs/english").status_code == 404
+
+
+def test_converters_are_documented_without_suffix():
+    app = FastrAPI()
+
+    @app.get("/items/{item_id:int}")
+    def item(item_id: int):
+        return {}
+
+    schema = TestClient(app).get("/api-docs/openapi.json").json()
+    assert "/items/{item_id}" in schema["paths"]
+    [param] = schema["paths"]["/items/{item_id}"]["get"]["parameters"]
+    assert param["name"] == "item_id"
+
+
+def test_invalid_converters_are_rejected():
+    app = FastrAPI()
+    with pytest.raises(ValueError, match="unknown path converter"):
+        app.get("/items/{item_id:nope}")
+    with pytest.raises(ValueError, match="must end the path"):
+        app.get("/files/{file_path:path}/raw")
+    with pytest.raises(ValueError, match="unbalanced braces"):
+        app.get("/items/{item_id")
+    with pytest.raises(ValueError, match="built-in"):
+        register_converter("int", "[0-9]+")
+    with pytest.raises(ValueError, match="invalid converter regex"):
+        register_converter("broken", "(")
@@ -1,4 +1,7 @@
 # tests/test_path_params.py
+from fastrapi import Path
+
+
 def test_single_path_param(client, app):
     @app.get("/items/{item_id}")
     def read_item(item_id: int):
@@ -19,4 +22,44 @@ def test_multiple_path_params(client, app):
 
     response = client.get("/users/preetham/items/456")
     assert response.status_code == 200
-    assert response.json() == {"user_id": "preetham", "item_id": 456}
\ No newline at end of file
+    assert response.json() == {"user_id": "preetham", "item_id": 456}
+
+
+def test_path_params_are_percent_decoded(client, app):
+    @app.get("/files/{name}")
+    def read_file(name: str):
+        return {"name": name}
+
+    assert client.get("/files/hello%20world").json() == {"name": "hello world"}
+    assert client.get("/files/caf%C3%A9").json() == {"name": "café"}
+    # unlike query strings, "+" is literal in a path
+    assert client.get("/files/a+b").json() == {"name": "a+b"}
+    assert client.get("/files/plain").json() == {"name": "plain"}
+
+
+def test_path_param_constraints_see_decoded_value(client, app):
+    @app.get("/tags/{tag}")
+    def read_tag(tag: str = Path(max_length=3)):
+        return {"tag": tag}
+
+    assert client.get("/tags/a%20b").json() == {"tag": "a b"}
+    assert client.get("/tags/long").status_code == 422
+
+
+def test_invalid_utf8_in_path_param_is_rejected(client, app):
+    @app.get("/files/{name}")
+    def read_file(name: str):
+        return {"name": name}
+
+    response = client.get("/files/%FF%FE")
+    assert response.status_code == 400
+    assert response.json() == {"detail": "Invalid percent-encoding in path"}
+
+
+def test_converters_see_decoded_value(client, app):
+    @app.get("/items/{item_id:int}")
+    def read_item(item_id: int):
+        return {"item_id": item_id}
+
+    assert client.get("/items/%34%32").json() == {"item_id": 42}
+    assert client.get("/items/%FF").status_code == 404
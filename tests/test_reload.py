@@ -0,0 +1,82 @@
+import os
+import signal
+import subprocess
+import sys
+import time
+from pathlib import Path
+
+import pytest
+
+from conftest import get_free_port, wait_for_server
+
+
+pytestmark = pytest.mark.skipif(
+    sys.platform == "win32", reason="graceful restarts rely on SIGTERM"
+)
+
+
+def test_included_files_trigger_a_graceful_restart(tmp_path: Path):
+    port = get_free_port()
+    events = tmp_path / "events.txt"
+    (tmp_path / "page.html").write_text("one", encoding="utf-8")
+    (tmp_path / "notes.txt").write_text("", encoding="utf-8")
+    script = tmp_path / "reload_app.py"
+    script.write_text(
+        f"""
+import os
+from pathlib import Path
+
+from fastrapi import FastrAPI
+
+EVENTS = Path(r"{events}")
+
+
+def shutdown():
+    with EVENTS.open("a") as events:
+        events.write(f"shutdown {{os.getpid()}}\\n")
+
+
+app = FastrAPI(on_shutdown=[shutdown])
+
+
+@app.get("/")
+def page():
+    return {{"page": Path("page.html").read_text()}}
+
+
+app.serve("127.0.0.1", {port}, reload=True, reload_includes=["*.html"], reload_tick=100)
+""",
+        encoding="utf-8",
+    )
+    url = f"http://127.0.0.1:{port}/"
+
+    proc = subprocess.Popen(
+        [sys.executable, str(script)],
+        cwd=tmp_path,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+        start_new_session=True,
+    )
+    try:
+        wait_for_server(proc, url, lambda response: response.json()["page"] == "one")
+        time.sleep(1)
+
+        # not included: the running server keeps serving and is not restarted
+        (tmp_path / "notes.txt").write_text("changed", encoding="utf-8")
+        time.sleep(1)
+        assert not events.exists()
+
+        (tmp_path / "page.html").write_text("two", encoding="utf-8")
+        # the page is read per request, so only the restart proves the include matched
+        deadline = time.time() + 15
+        while not events.exists() and time.time() < deadline:
+            time.sleep(0.1)
+        assert events.read_text().startswith("shutdown ")
+        wait_for_server(proc, url, lambda response: response.json()["page"] == "two")
+    finally:
+        if proc.poll() is None:
+            os.killpg(proc.pid, signal.SIGTERM)
+        output, _ = proc.communicate(timeout=30)
+
+    assert "1 file changed (page.html); restarting server" in output
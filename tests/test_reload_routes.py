@@ -0,0 +1,96 @@
+import httpx
+
+from fastrapi import APIRouter, FastrAPI
+from fastrapi.testclient import TestClient
+
+
+def test_routes_registered_after_start_are_swapped_in():
+    app = FastrAPI()
+
+    @app.get("/hello")
+    def hello():
+        return {"version": 1}
+
+    with app.start("127.0.0.1", 0) as server, httpx.Client(
+        base_url=f"http://127.0.0.1:{server.port}", timeout=5
+    ) as client:
+        assert client.get("/hello").json() == {"version": 1}
+        assert client.get("/items/3").status_code == 404
+
+        @app.get("/items/{item_id}")
+        def item(item_id: int):
+            return {"item_id": item_id}
+
+        assert client.get("/items/3").json() == {"item_id": 3}
+        assert "/items/{item_id}" in client.get("/api-docs/openapi.json").json()["paths"]
+
+        # registering the same method and path again replaces the handler,
+        # including routes axum serves without going through the fallback
+        @app.get("/hello")
+        def hello_again():
+            return {"version": 2}
+
+        assert client.get("/hello").json() == {"version": 2}
+        assert client.get("/items/4").json() == {"item_id": 4}
+
+
+def test_include_router_after_start():
+    app = FastrAPI()
+
+    @app.get("/")
+    def root():
+        return {"ok": True}
+
+    client = TestClient(app)
+    assert client.get("/").json() == {"ok": True}
+
+    router = APIRouter(prefix="/admin")
+
+    @router.post("/users")
+    def create_user(name: str):
+        return {"created": name}
+
+    app.include_router(router)
+    assert client.post("/admin/users?name=ada").json() == {"created": "ada"}
+
+
+def test_reload_routes_refreshes_the_schema():
+    app = FastrAPI(title="before")
+
+    @app.get("/")
+    def root():
+        return {"ok": True}
+
+    with app.start("127.0.0.1", 0) as server:
+        url = f"http://127.0.0.1:{server.port}/api-docs/openapi.json"
+        assert httpx.get(url, timeout=5).json()["info"]["title"] == "before"
+
+        app.title = "after"
+        assert httpx.get(url, timeout=5).json()["info"]["title"] == "before"
+        app.reload_routes()
+        assert httpx.get(url, timeout=5).json()["info"]["title"] == "after"
+        assert httpx.get(url.replace("/api-docs/openapi.json", "/"), timeout=5).json() == {
+            "ok": True
+        }
+
+
+def test_route_changes_only_rebuild_the_apps_serving_the_router():
+    shared = APIRouter(prefix="/shared")
+    first, second, unrelated = FastrAPI(), FastrAPI(), FastrAPI(title="before")
+    first.include_router(shared)
+    second.include_router(shared)
+
+    with TestClient(first) as first_client, TestClient(second) as second_client, TestClient(
+        unrelated
+    ) as unrelated_client:
+        assert unrelated_client.get("/api-docs/openapi.json").json()["info"]["title"] == "before"
+        unrelated.title = "after"
+
+        @shared.get("/ping")
+        def ping():
+            return {"pong": True}
+
+        assert first_client.get("/shared/ping").json() == {"pong": True}
+        assert second_client.get("/shared/ping").json() == {"pong": True}
+        # an app that does not serve the router keeps its table
+        assert unrelated_client.get("/api-docs/openapi.json").json()["info"]["title"] == "before"
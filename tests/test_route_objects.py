@@ -0,0 +1,97 @@
+import pytest
+
+from fastrapi import APIRouter, FastrAPI, Header, Query
+from fastrapi.routing import Route, RouteParam, WebSocketRoute
+
+
+def build_app():
+    app = FastrAPI()
+
+    @app.get("/", cache_resp=True)
+    def index():
+        return {"ok": True}
+
+    router = APIRouter(prefix="/items", tags=["items"])
+
+    @router.get("/{item_id}", name="read_item", rate_limit=5)
+    async def get_item(
+        item_id: int,
+        q: str = Query(None),
+        user_agent: str = Header(None, alias="User-Agent"),
+    ):
+        return {"item_id": item_id}
+
+    @app.websocket("/ws")
+    async def feed(websocket):
+        await websocket.accept()
+
+    app.include_router(router)
+    return app
+
+
+def test_app_routes_describe_every_registered_route():
+    app = build_app()
+    routes = app.routes
+
+    http_routes = {route.path: route for route in routes if isinstance(route, Route)}
+    assert set(http_routes) == {"/", "/items/{item_id}"}
+    assert [route.path for route in routes if isinstance(route, WebSocketRoute)] == ["/ws"]
+
+    index = http_routes["/"]
+    assert index.methods == {"GET"}
+    assert index.name == "index"
+    assert index.cached is True
+    assert index.rate_limit is None
+    assert index.execution_mode == "SyncNoArgs"
+    assert index.params == []
+    assert index.endpoint() == {"ok": True}
+
+    item = http_routes["/items/{item_id}"]
+    assert item.name == "read_item"
+    assert item.tags == ["items"]
+    assert item.cached is False
+    assert item.rate_limit == 5
+    assert item.execution_mode.startswith("Async")
+    params = {param.name: param for param in item.params}
+    assert all(isinstance(param, RouteParam) for param in item.params)
+    assert params["item_id"].source == "path"
+    assert params["item_id"].required is True
+    assert params["q"].source == "query"
+    assert params["q"].required is False
+    assert params["user_agent"].source == "header"
+    assert params["user_agent"].alias == "User-Agent"
+
+
+def test_listing_routes_does_not_freeze_routers():
+    app = build_app()
+    assert len(app.routes) == 3
+
+    # websocket routes are refused once a router is frozen by serving
+    @app.websocket("/ws2")
+    async def later(websocket):
+        await websocket.accept()
+
+    assert "/ws2" in [route.path for route in app.routes]
+
+
+def test_router_routes_include_nested_routers():
+    parent = APIRouter(prefix="/api")
+    child = APIRouter(prefix="/v1")
+
+    @child.post("/users")
+    def create_user():
+        return {}
+
+    parent.include_router(child)
+    [route] = parent.routes
+    assert route.path == "/api/v1/users"
+    assert route.methods == {"POST"}
+    assert repr(route) == "Route(path='/api/v1/users', name='create_user', methods=['POST'])"
+
+
+def test_routes_are_read_only():
+    app = FastrAPI()
+    with pytest.raises(AttributeError):
+        app.routes = []
+    with pytest.raises(TypeError):
+        FastrAPI(routes=[])
@@ -0,0 +1,179 @@
+from contextlib import asynccontextmanager
+
+import pytest
+
+from fastrapi import FastrAPI, Cookie, File, Form
+from fastrapi.datastructures import UploadFile
+from fastrapi.request import Request
+from fastrapi.responses import JSONResponse, RedirectResponse
+from fastrapi.testclient import TestClient
+
+
+def test_requests_do_not_need_a_server():
+    app = FastrAPI()
+
+    @app.get("/items")
+    def items(q: str, limit: int = 10):
+        return {"q": q, "limit": limit}
+
+    client = TestClient(app)
+    response = client.get("/items", params={"q": "rust", "limit": 3})
+
+    assert response.status_code == 200
+    assert response.is_success
+    assert response.json() == {"q": "rust", "limit": 3}
+    assert response.headers["Content-Type"].startswith("application/json")
+    assert response.url == "http://testserver/items?q=rust&limit=3"
+    client.close()
+
+
+def test_json_form_and_multipart_bodies():
+    app = FastrAPI()
+
+    @app.post("/json")
+    def echo_json(payload: dict):
+        return payload
+
+    @app.post("/form")
+    def echo_form(name: str = Form(...)):
+        return {"name": name}
+
+    @app.post("/upload")
+    async def upload(note: str = Form(...), file: UploadFile = File(...)):
+        content = await file.read()
+        return {
+            "note": note,
+            "filename": file.filename,
+            "content_type": file.content_type,
+            "content": content.decode(),
+        }
+
+    client = TestClient(app)
+
+    assert client.post("/json", json={"a": [1, 2]}).json() == {"a": [1, 2]}
+    assert client.post("/form", data={"name": "ferris"}).json() == {"name": "ferris"}
+    assert client.post(
+        "/upload",
+        data={"note": "hi"},
+        files={"file": ("notes.txt", b"hello", "text/plain")},
+    ).json() == {
+        "note": "hi",
+        "filename": "notes.txt",
+        "content_type": "text/plain",
+        "content": "hello",
+    }
+    client.close()
+
+
+def test_cookies_persist_across_requests():
+    app = FastrAPI()
+
+    @app.get("/login")
+    def login():
+        return JSONResponse({"ok": True}, headers={"set-cookie": "session=abc; Path=/"})
+
+    @app.get("/me")
+    def me(session: str = Cookie(None)):
+        return {"session": session}
+
+    client = TestClient(app)
+
+    assert client.get("/me").json() == {"session": None}
+    response = client.get("/login")
+    assert response.cookies == {"session": "abc"}
+    assert client.cookies == {"session": "abc"}
+    assert client.get("/me").json() == {"session": "abc"}
+    client.close()
+
+
+def test_redirects_are_followed_unless_disabled():
+    app = FastrAPI()
+
+    @app.get("/")
+    def root():
+        return {"root": True}
+
+    @app.get("/old")
+    def old() -> RedirectResponse:
+        return RedirectResponse("/", status_code=307)
+
+    client = TestClient(app)
+
+    assert client.get("/old").json() == {"root": True}
+    response = client.get("/old", follow_redirects=False)
+    assert response.status_code == 307
+    assert response.is_redirect
+    assert response.headers["location"] == "/"
+    client.close()
+
+
+def test_see_other_redirect_drops_body_and_content_type():
+    app = FastrAPI()
+
+    @app.post("/submit")
+    def submit(payload: dict) -> RedirectResponse:
+        return RedirectResponse("/done", status_code=303)
+
+    @app.get("/done")
+    def done(request: Request):
+        return {"content_type": request.headers.get("content-type")}
+
+    with TestClient(app) as client:
+        response = client.post("/submit", json={"name": "x"})
+    assert response.status_code == 200
+    assert response.json() == {"content_type": None}
+
+
+def test_context_manager_runs_lifespan():
+    events = []
+
+    @asynccontextmanager
+    async def lifespan(app):
+        events.append("startup")
+        yield
+        events.append("shutdown")
+
+    app = FastrAPI(lifespan=lifespan)
+
+    @app.get("/ping")
+    def ping():
+        return {"events": list(events)}
+
+    with TestClient(app) as client:
+        assert client.get("/ping").json() == {"events": ["startup"]}
+
+    assert events == ["startup", "shutdown"]
+
+
+def test_startup_failure_propagates_from_enter():
+    def boom():
+        raise RuntimeError("boom")
+
+    app = FastrAPI(on_startup=[boom])
+
+    with pytest.raises(RuntimeError, match="boom"):
+        with TestClient(app):
+            pass
+
+
+def test_websocket_session():
+    app = FastrAPI()
+
+    @app.websocket("/ws")
+    async def ws_endpoint(ws):
+        await ws.accept()
+        message = await ws.receive_text()
+        await ws.send_text(f"echo:{message}")
+        data = await ws.receive_json()
+        await ws.send_json({"got": data})
+        await ws.close()
+
+    client = TestClient(app)
+    with client.websocket_connect("/ws") as ws:
+        ws.send_text("ping")
+        assert ws.receive_text() == "echo:ping"
+        ws.send_json({"n": 1})
+        assert ws.receive_json() == {"got": {"n": 1}}
+        with pytest.raises(ConnectionError):
+            ws.receive_text()
+    client.close()
@@ -0,0 +1,139 @@
+import os
+import subprocess
+import sys
+import threading
+
+import httpx
+import pytest
+
+from fastrapi import FastrAPI
+from fastrapi.testclient import TestClient
+
+
+def test_saturated_sync_executor_returns_503():
+    app = FastrAPI(max_sync_workers=1, sync_queue_size=0)
+    started = threading.Event()
+    release = threading.Event()
+
+    @app.get("/slow")
+    def slow():
+        started.set()
+        release.wait(5)
+        return {"done": True}
+
+    @app.get("/fast")
+    async def fast():
+        return {"fast": True}
+
+    with app.start("127.0.0.1", 0) as server, httpx.Client(
+        base_url=f"http://127.0.0.1:{server.port}", timeout=10
+    ) as client:
+        results = []
+        worker = threading.Thread(target=lambda: results.append(client.get("/slow")))
+        worker.start()
+        assert started.wait(5)
+
+        refused = client.get("/slow")
+        assert refused.status_code == 503
+        assert refused.headers["retry-after"] == "1"
+        # async handlers never wait on the sync executor
+        assert client.get("/fast").json() == {"fast": True}
+
+        release.set()
+        worker.join(5)
+        assert results[0].json() == {"done": True}
+        assert client.get("/slow").json() == {"done": True}
+
+
+def test_queued_sync_handlers_wait_for_a_worker():
+    app = FastrAPI(max_sync_workers=1, sync_queue_size=4)
+    lock = threading.Lock()
+    running = []
+
+    @app.get("/count")
+    def count():
+        with lock:
+            running.append(1)
+            overlapping = len(running)
+        threading.Event().wait(0.05)
+        with lock:
+            running.pop()
+        return {"overlapping": overlapping}
+
+    client = TestClient(app)
+    responses = []
+    threads = [
+        threading.Thread(target=lambda: responses.append(client.get("/count")))
+        for _ in range(4)
+    ]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join(10)
+
+    assert [response.status_code for response in responses] == [200] * 4
+    assert all(response.json() == {"overlapping": 1} for response in responses)
+
+
+def test_executor_and_runtime_options_are_validated():
+    with pytest.raises(ValueError, match="max_sync_workers"):
+        FastrAPI(max_sync_workers=0)
+
+    app = FastrAPI()
+    assert app.max_sync_workers is None
+    assert app.sync_queue_size == 64
+    with pytest.raises(ValueError, match="worker_threads"):
+        app.start("127.0.0.1", 0, worker_threads=0)
+    with pytest.raises(ValueError, match="max_blocking_threads"):
+        app.start("127.0.0.1", 0, max_blocking_threads=0)
+    with pytest.raises(ValueError, match="thread_keep_alive"):
+        app.start("127.0.0.1", 0, thread_keep_alive=-1.0)
+
+
+def test_thread_settings_come_from_the_environment(tmp_path):
+    script = tmp_path / "threads_app.py"
+    script.write_text(
+        """
+from fastrapi import FastrAPI
+
+app = FastrAPI()
+print(app.max_sync_workers, app.sync_queue_size)
+handle = app.start("127.0.0.1", 0)
+handle.wait_ready(timeout=10)
+handle.stop(timeout=10)
+""",
+        encoding="utf-8",
+    )
+    env = {key: value for key, value in os.environ.items() if not key.startswith("FASTRAPI_")}
+    env.update(
+        {
+            "FASTRAPI_MAX_SYNC_WORKERS": "2",
+            "FASTRAPI_SYNC_QUEUE_SIZE": "5",
+            "FASTRAPI_WORKER_THREADS": "2",
+            "FASTRAPI_MAX_BLOCKING_THREADS": "8",
+            "FASTRAPI_THREAD_KEEP_ALIVE": "1.5",
+            "FASTRAPI_THREAD_NAME": "orders-worker",
+        }
+    )
+    result = subprocess.run(
+        [sys.executable, str(script)],
+        cwd=tmp_path,
+        env=env,
+        capture_output=True,
+        text=True,
+        timeout=30,
+    )
+
+    assert result.returncode == 0, result.stderr
+    assert "2 5" in result.stdout.splitlines()
+
+    env["FASTRAPI_THREAD_KEEP_ALIVE"] = "-1"
+    result = subprocess.run(
+        [sys.executable, str(script)],
+        cwd=tmp_path,
+        env=env,
+        capture_output=True,
+        text=True,
+        timeout=30,
+    )
+    assert "thread_keep_alive must be a non-negative number of seconds" in result.stderr
@@ -0,0 +1,164 @@
+import hashlib
+import shutil
+import signal
+import socket
+import ssl
+import subprocess
+import sys
+import time
+from pathlib import Path
+
+import pytest
+
+from conftest import get_free_port
+
+
+pytestmark = [
+    pytest.mark.skipif(sys.platform == "win32", reason="needs SIGHUP"),
+    pytest.mark.skipif(shutil.which("openssl") is None, reason="needs openssl"),
+]
+
+
+def make_cert(directory: Path, name: str) -> tuple[Path, Path]:
+    cert = directory / f"{name}.pem"
+    key = directory / f"{name}.key"
+    subprocess.run(
+        [
+            "openssl", "req", "-x509", "-newkey", "rsa:2048", "-nodes",
+            "-keyout", str(key), "-out", str(cert), "-days", "1",
+            "-subj", f"/CN={name}", "-addext", "subjectAltName=IP:127.0.0.1",
+        ],
+        check=True,
+        capture_output=True,
+    )
+    return cert, key
+
+
+def client_context(alpn=None) -> ssl.SSLContext:
+    context = ssl.create_default_context()
+    context.check_hostname = False
+    context.verify_mode = ssl.CERT_NONE
+    if alpn:
+        context.set_alpn_protocols(alpn)
+    return context
+
+
+def handshake(port: int, alpn=None) -> ssl.SSLSocket:
+    sock = socket.create_connection(("127.0.0.1", port), timeout=5)
+    return client_context(alpn).wrap_socket(sock, server_hostname="127.0.0.1")
+
+
+def fingerprint(tls_sock: ssl.SSLSocket) -> str:
+    return hashlib.sha256(tls_sock.getpeercert(binary_form=True)).hexdigest()
+
+
+def file_fingerprint(cert: Path) -> str:
+    der = ssl.PEM_cert_to_DER_cert(cert.read_text())
+    return hashlib.sha256(der).hexdigest()
+
+
+def get(tls_sock: ssl.SSLSocket, path: str = "/") -> bytes:
+    tls_sock.sendall(f"GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n".encode())
+    response = b""
+    while b"\r\n\r\n" not in response:
+        chunk = tls_sock.recv(4096)
+        assert chunk, "connection closed"
+        response += chunk
+    head, body = response.split(b"\r\n\r\n", 1)
+    length = next(
+        int(line.split(b":", 1)[1])
+        for line in head.split(b"\r\n")
+        if line.lower().startswith(b"content-length:")
+    )
+    while len(body) < length:
+        body += tls_sock.recv(4096)
+    return head
+
+
+def start_server(tmp_path: Path, port: int, cert: Path, key: Path, http2: bool = False):
+    script = tmp_path / "tls_app.py"
+    script.write_text(
+        f'''
+from fastrapi import FastrAPI
+
+app = FastrAPI()
+
+
+@app.get("/")
+def index():
+    return {{"ok": True}}
+
+
+app.serve(
+    host="127.0.0.1",
+    port={port},
+    ssl_certfile=r"{cert}",
+    ssl_keyfile=r"{key}",
+    http2={http2},
+)
+'''
+    )
+    proc = subprocess.Popen(
+        [sys.executable, str(script)],
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+    )
+    deadline = time.time() + 15
+    while time.time() < deadline:
+        if proc.poll() is not None:
+            raise AssertionError(f"server exited early\n{proc.stdout.read()}")
+        try:
+            handshake(port).close()
+            return proc
+        except OSError:
+            time.sleep(0.1)
+    proc.kill()
+    raise AssertionError("server did not start in time")
+
+
+def test_tls_handshake_and_sighup_certificate_reload(tmp_path: Path):
+    port = get_free_port()
+    cert, key = make_cert(tmp_path, "first")
+    second_cert, second_key = make_cert(tmp_path, "second")
+    proc = start_server(tmp_path, port, cert, key)
+    try:
+        before = handshake(port)
+        assert fingerprint(before) == file_fingerprint(cert)
+        assert b" 200 " in get(before)
+
+        shutil.copy(second_cert, cert)
+        shutil.copy(second_key, key)
+        proc.send_signal(signal.SIGHUP)
+
+        deadline = time.time() + 10
+        while True:
+            with handshake(port) as after:
+                if fingerprint(after) == file_fingerprint(second_cert):
+                    assert b" 200 " in get(after)
+                    break
+            assert time.time() < deadline, "certificate was not reloaded"
+            time.sleep(0.1)
+
+        # the connection opened before the reload keeps working
+        assert b" 200 " in get(before)
+        before.close()
+        assert proc.poll() is None
+    finally:
+        proc.terminate()
+        proc.wait(timeout=15)
+
+
+def test_alpn_negotiates_h2_when_enabled(tmp_path: Path):
+    port = get_free_port()
+    cert, key = make_cert(tmp_path, "alpn")
+    proc = start_server(tmp_path, port, cert, key, http2=True)
+    try:
+        with handshake(port, alpn=["h2", "http/1.1"]) as tls_sock:
+            assert tls_sock.selected_alpn_protocol() == "h2"
+        with handshake(port, alpn=["http/1.1"]) as tls_sock:
+            assert tls_sock.selected_alpn_protocol() == "http/1.1"
+            assert b" 200 " in get(tls_sock)
+    finally:
+        proc.terminate()
+        proc.wait(timeout=15)
@@ -0,0 +1,177 @@
+import json
+import subprocess
+import sys
+from pathlib import Path
+
+from fastrapi.tracing import current_span_context
+
+
+ROOT = Path(__file__).resolve().parents[1]
+
+TRACE_ID = "0af7651916cd43dd8448eb211c80319c"
+PARENT_SPAN_ID = "b7ad6b7169203331"
+CANCELLED_TRACE_ID = "4bf92f3577b34da6a3ce929d0e0e4736"
+
+SCRIPT = """
+import asyncio
+import json
+import threading
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+import httpx
+from pydantic import BaseModel
+
+from fastrapi import Depends, FastrAPI
+from fastrapi.tracing import current_span_context
+
+exported = []
+
+
+class Collector(BaseHTTPRequestHandler):
+    def do_POST(self):
+        body = self.rfile.read(int(self.headers["content-length"]))
+        exported.append((self.path, json.loads(body)))
+        self.send_response(200)
+        self.send_header("content-type", "application/json")
+        self.end_headers()
+        self.wfile.write(b"{}")
+
+    def log_message(self, *args):
+        pass
+
+
+collector = ThreadingHTTPServer(("127.0.0.1", 0), Collector)
+threading.Thread(target=collector.serve_forever, daemon=True).start()
+
+app = FastrAPI(title="tracing-test", otlp_endpoint=f"http://127.0.0.1:{collector.server_port}")
+
+
+class Item(BaseModel):
+    name: str
+
+
+def dependency():
+    return current_span_context().trace_id
+
+
+@app.post("/items/{item_id}")
+def create_item(item_id: int, item: Item, dep_trace_id: str = Depends(dependency)):
+    span = current_span_context()
+    return {"traceparent": span.traceparent, "tracestate": span.tracestate, "dep": dep_trace_id}
+
+
+@app.get("/async")
+async def async_handler():
+    return {"traceparent": current_span_context().traceparent}
+
+
+async def slow_dependency():
+    await asyncio.sleep(2)
+
+
+@app.get("/slow")
+async def slow_handler(slept: None = Depends(slow_dependency)):
+    return {}
+
+
+handle = app.start("127.0.0.1", 0)
+handle.wait_ready(timeout=10)
+base = f"http://127.0.0.1:{handle.port}"
+headers = {"traceparent": "00-TRACE-PARENT-01", "tracestate": "vendor=value"}
+created = httpx.post(base + "/items/3", json={"name": "x"}, headers=headers, timeout=5).json()
+fresh = httpx.get(base + "/async", timeout=5).json()
+try:
+    httpx.get(base + "/slow", headers={"traceparent": "00-CANCELLED-01"}, timeout=0.3)
+except httpx.TimeoutException:
+    pass
+handle.stop(timeout=10)
+collector.shutdown()
+
+spans = [
+    span
+    for path, payload in exported
+    if path == "/v1/traces"
+    for resource in payload["resourceSpans"]
+    for scope in resource["scopeSpans"]
+    for span in scope["spans"]
+]
+services = [
+    attribute["value"]["stringValue"]
+    for path, payload in exported
+    for resource in payload["resourceSpans"]
+    for attribute in resource["resource"]["attributes"]
+    if attribute["key"] == "service.name"
+]
+print(json.dumps({"created": created, "fresh": fresh, "spans": spans, "services": services}))
+""".replace("TRACE", TRACE_ID).replace("PARENT", PARENT_SPAN_ID).replace(
+    "CANCELLED", f"{CANCELLED_TRACE_ID}-{PARENT_SPAN_ID}"
+)
+
+
+def attributes(span):
+    return {
+        attribute["key"]: next(iter(attribute["value"].values()))
+        for attribute in span.get("attributes", [])
+    }
+
+
+def run_traced_app(tmp_path: Path):
+    script = tmp_path / "tracing_app.py"
+    script.write_text(SCRIPT, encoding="utf-8")
+    result = subprocess.run(
+        [sys.executable, str(script)],
+        cwd=ROOT,
+        capture_output=True,
+        text=True,
+        timeout=60,
+    )
+    assert result.returncode == 0, result.stderr
+    return json.loads(result.stdout.strip().splitlines()[-1])
+
+
+def test_request_spans_are_exported_and_continue_the_trace(tmp_path: Path):
+    output = run_traced_app(tmp_path)
+    spans = output["spans"]
+    assert "tracing-test" in output["services"]
+
+    server = next(span for span in spans if span["name"] == "POST /items/{item_id}")
+    assert server["traceId"] == TRACE_ID
+    assert server["parentSpanId"] == PARENT_SPAN_ID
+    server_attributes = attributes(server)
+    assert server_attributes["http.route"] == "/items/{item_id}"
+    assert server_attributes["http.request.method"] == "POST"
+    assert int(server_attributes["http.response.status_code"]) == 200
+    assert server_attributes["fastrapi.execution_mode"] == "SyncDepsNoReq"
+
+    children = {
+        span["name"] for span in spans if span.get("parentSpanId") == server["spanId"]
+    }
+    assert {"extract_payload", "execute_dependencies", "serialize_response"} <= children
+
+
+def test_child_span_ends_when_the_request_is_cancelled(tmp_path: Path):
+    spans = run_traced_app(tmp_path)["spans"]
+
+    # the client gave up while dependencies were still running
+    assert any(
+        span["name"] == "execute_dependencies" and span["traceId"] == CANCELLED_TRACE_ID
+        for span in spans
+    )
+
+
+def test_span_context_is_visible_to_python(tmp_path: Path):
+    output = run_traced_app(tmp_path)
+    created = output["created"]
+
+    assert created["traceparent"].startswith(f"00-{TRACE_ID}-")
+    assert not created["traceparent"].startswith(f"00-{TRACE_ID}-{PARENT_SPAN_ID}")
+    assert created["tracestate"] == "vendor=value"
+    assert created["dep"] == TRACE_ID
+
+    # without an incoming traceparent a new trace is started, async handlers included
+    fresh = output["fresh"]["traceparent"]
+    assert fresh.startswith("00-") and TRACE_ID not in fresh
+
+
+def test_no_span_context_outside_requests():
+    assert current_span_context() is None
@@ -0,0 +1,96 @@
+import os
+import signal
+import subprocess
+import sys
+import time
+from pathlib import Path
+
+import httpx
+import pytest
+
+from conftest import get_free_port
+
+
+ROOT = Path(__file__).resolve().parents[1]
+
+pytestmark = pytest.mark.skipif(
+    sys.platform == "win32", reason="workers rely on SO_REUSEPORT"
+)
+
+
+def collect_pids(proc: subprocess.Popen[str], port: int, timeout: float = 15.0):
+    deadline = time.time() + timeout
+    pids = set()
+
+    while time.time() < deadline and len(pids) < 2:
+        if proc.poll() is not None:
+            output = proc.stdout.read() if proc.stdout is not None else ""
+            raise AssertionError(
+                f"supervisor exited early with code {proc.returncode}\n{output}"
+            )
+        try:
+            # a fresh connection each time so SO_REUSEPORT can pick another worker
+            response = httpx.get(f"http://127.0.0.1:{port}/pid", timeout=0.5)
+            pids.add(response.json()["pid"])
+        except Exception:  # pragma: no cover - retry loop
+            time.sleep(0.05)
+
+    return pids
+
+
+def test_workers_share_port_and_stop_on_sigterm(tmp_path: Path):
+    port = get_free_port()
+    events_file = tmp_path / "worker-events.txt"
+    script_file = tmp_path / "workers_app.py"
+    script_file.write_text(
+        f'''
+import os
+from pathlib import Path
+
+from fastrapi import FastrAPI
+
+
+EVENTS = Path(r"{events_file}")
+
+
+def shutdown() -> None:
+    with EVENTS.open("a", encoding="utf-8") as handle:
+        handle.write(f"shutdown {{os.getpid()}}\\n")
+
+
+app = FastrAPI(on_shutdown=[shutdown])
+
+
+@app.get("/pid")
+def pid():
+    return {{"pid": os.getpid()}}
+
+
+app.serve(host="127.0.0.1", port={port}, workers=2)
+''',
+        encoding="utf-8",
+    )
+
+    proc = subprocess.Popen(
+        [sys.executable, str(script_file)],
+        cwd=ROOT,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        text=True,
+    )
+
+    try:
+        pids = collect_pids(proc, port)
+        assert len(pids) == 2
+        assert os.getpid() not in pids
+        assert proc.pid not in pids
+
+        proc.send_signal(signal.SIGTERM)
+        proc.communicate(timeout=15.0)
+
+        shutdowns = events_file.read_text(encoding="utf-8").splitlines()
+        assert sorted(shutdowns) == sorted(f"shutdown {pid}" for pid in pids)
+    finally:
+        if proc.poll() is None:
+            proc.kill()
+            proc.communicate(timeout=5.0)